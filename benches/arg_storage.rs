@@ -0,0 +1,112 @@
+//! Measures the storage of values, which is inline while an argument is
+//! supplied at most once, e.g. `#[my_attr(name = "a", level = 1)]`, and only
+//! spills to the heap once it is supplied again.
+//!
+//! Allocations are counted by a global allocator, both while parsing whole
+//! attributes and while storing values alone. The latter takes no allocation
+//! for a single value, while repeated values allocate their arrays on the
+//! heap.
+//!
+//! Run with `cargo bench --bench arg_storage`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use plap::{define_args, Arg, Args, Entry};
+use syn::parse::Parser;
+use syn::{Attribute, LitInt, LitStr};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+define_args! {
+    struct StorageArgs {
+        #[arg(is_expr)]
+        name: Arg<LitStr>,
+        #[arg(is_expr)]
+        level: Arg<LitInt>,
+    }
+}
+
+const ATTRS: usize = 10_000;
+const ROUNDS: usize = 10;
+
+fn main() {
+    run("once", r#"#[my_attr(name = "a", level = 1)]"#);
+    run(
+        "twice",
+        r#"#[my_attr(name = "a", level = 1, name = "b", level = 2)]"#,
+    );
+    push(1);
+    push(2);
+}
+
+/// Counts the allocations of storing `n` values alone, apart from parsing.
+fn push(n: usize) {
+    let key = syn::Ident::new("level", proc_macro2::Span::call_site());
+    let value = syn::parse_str::<LitInt>("1").unwrap();
+    let mut entries = (0..ATTRS * n)
+        .map(|_| Entry::new(key.clone(), value.clone()))
+        .collect::<Vec<_>>();
+    let mut args = Vec::with_capacity(ATTRS);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ATTRS {
+        let mut arg = Arg::new("level");
+        for entry in entries.drain(..n) {
+            arg.push(entry);
+        }
+        args.push(arg);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "stored {} value(s) per argument with {} allocations per argument",
+        n,
+        allocations as f64 / ATTRS as f64,
+    );
+}
+
+fn run(shape: &str, attr: &str) {
+    let source = attr.repeat(ATTRS);
+    let attrs = Parser::parse_str(Attribute::parse_outer, &source).unwrap();
+
+    let mut best = None::<Duration>;
+    let mut allocations = 0;
+    for _ in 0..ROUNDS {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        for attr in attrs.iter() {
+            let args = attr.parse_args_with(StorageArgs::parse).unwrap();
+            assert!(!args.name.is_empty());
+        }
+        let elapsed = start.elapsed();
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        best = Some(best.map_or(elapsed, |b| b.min(elapsed)));
+    }
+    let best = best.unwrap();
+    println!(
+        "parsed {} attributes with each argument supplied {} in {:?} ({:?} and {} allocations per \
+         attribute)",
+        ATTRS,
+        shape,
+        best,
+        best / ATTRS as u32,
+        allocations / ATTRS,
+    );
+}
@@ -0,0 +1,64 @@
+//! Measures looking up keys among many arguments, e.g. `#[my_attr(a19 = 1)]`,
+//! where each key is found with a binary search over a table built once per
+//! thread, and keys of no argument fall back to families.
+//!
+//! Run with `cargo bench --bench key_lookup`.
+
+use std::time::Instant;
+
+use plap::{define_args, Arg, Args};
+use syn::parse::Parser;
+use syn::{Attribute, Expr, LitInt};
+
+define_args! {
+    struct WideArgs {
+        #[arg(is_expr, family = "on_")]
+        on: Arg<Expr>,
+        #[arg(is_expr)] a00: Arg<LitInt>,
+        #[arg(is_expr)] a01: Arg<LitInt>,
+        #[arg(is_expr)] a02: Arg<LitInt>,
+        #[arg(is_expr)] a03: Arg<LitInt>,
+        #[arg(is_expr)] a04: Arg<LitInt>,
+        #[arg(is_expr)] a05: Arg<LitInt>,
+        #[arg(is_expr)] a06: Arg<LitInt>,
+        #[arg(is_expr)] a07: Arg<LitInt>,
+        #[arg(is_expr)] a08: Arg<LitInt>,
+        #[arg(is_expr)] a09: Arg<LitInt>,
+        #[arg(is_expr)] a10: Arg<LitInt>,
+        #[arg(is_expr)] a11: Arg<LitInt>,
+        #[arg(is_expr)] a12: Arg<LitInt>,
+        #[arg(is_expr)] a13: Arg<LitInt>,
+        #[arg(is_expr)] a14: Arg<LitInt>,
+        #[arg(is_expr)] a15: Arg<LitInt>,
+        #[arg(is_expr)] a16: Arg<LitInt>,
+        #[arg(is_expr)] a17: Arg<LitInt>,
+        #[arg(is_expr)] a18: Arg<LitInt>,
+        #[arg(is_expr)] a19: Arg<LitInt>,
+    }
+}
+
+const ATTRS: usize = 10_000;
+const ROUNDS: usize = 10;
+
+fn main() {
+    let source = "#[my_attr(a19 = 1, a00 = 2, a10 = 3, on_create = x)]".repeat(ATTRS);
+    let attrs = Parser::parse_str(Attribute::parse_outer, &source).unwrap();
+
+    let mut best = None;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        for attr in attrs.iter() {
+            let args = attr.parse_args_with(WideArgs::parse).unwrap();
+            assert!(!args.on.is_empty());
+        }
+        let elapsed = start.elapsed();
+        best = Some(best.map_or(elapsed, |b: std::time::Duration| b.min(elapsed)));
+    }
+    let best = best.unwrap();
+    println!(
+        "looked up {} keys in {:?} ({:?} per key)",
+        ATTRS * 4,
+        best,
+        best / (ATTRS * 4) as u32,
+    );
+}
@@ -0,0 +1,45 @@
+//! Measures the hot path of parsing flags, e.g. `#[my_attr(quiet, verbose)]`,
+//! which is the most common shape of attributes.
+//!
+//! Run with `cargo bench --bench parse_flags`.
+
+use std::time::Instant;
+
+use plap::{define_args, Arg, Args};
+use syn::parse::Parser;
+use syn::{Attribute, LitBool};
+
+define_args! {
+    struct FlagArgs {
+        #[arg(is_flag)]
+        quiet: Arg<LitBool>,
+        #[arg(is_flag)]
+        verbose: Arg<LitBool>,
+    }
+}
+
+const ATTRS: usize = 10_000;
+const ROUNDS: usize = 10;
+
+fn main() {
+    let source = "#[my_attr(quiet, verbose)]".repeat(ATTRS);
+    let attrs = Parser::parse_str(Attribute::parse_outer, &source).unwrap();
+
+    let mut best = None;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        for attr in attrs.iter() {
+            let args = attr.parse_args_with(FlagArgs::parse).unwrap();
+            assert!(!args.quiet.is_empty());
+        }
+        let elapsed = start.elapsed();
+        best = Some(best.map_or(elapsed, |b: std::time::Duration| b.min(elapsed)));
+    }
+    let best = best.unwrap();
+    println!(
+        "parsed {} flag attributes in {:?} ({:?} per attribute)",
+        ATTRS,
+        best,
+        best / ATTRS as u32,
+    );
+}
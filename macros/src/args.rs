@@ -1,7 +1,7 @@
 use plap::{AnyArg, Arg, ArgAttrs, ArgKind, Args, Checker, Parser};
 use syn::parse::ParseStream;
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Ident, LitBool, Token};
+use syn::{Attribute, Ident, LitBool, LitInt, Token};
 
 use crate::define_args::{ArgDefs, GroupDef};
 
@@ -108,6 +108,12 @@ define_plap_args! {
         pub required_any: MaybeList<Ident>,
         #[arg(is_expr)]
         pub blocked_each: MaybeList<Ident>,
+        #[arg(is_expr)]
+        pub max_members: Bounded<1>,
+        #[arg(is_expr)]
+        pub min_members: Bounded<1>,
+        #[arg(is_expr)]
+        pub members_between: Bounded<2>,
     }
 }
 
@@ -122,16 +128,48 @@ mod ApplyContainerCheck {
         target: &mut Target,
         arg: &Arg<T>,
         ctx: &'a Context,
-        f: impl Fn(&mut Target, T::Type) -> &mut Target,
+        f: impl ApplyGroup<T::Type>,
     ) -> syn::Result<()>
     where
         T: ToAnyArg<'a>,
     {
         for val in arg.values() {
-            f(target, val.to_any_arg(ctx)?);
+            f.apply(target, val.to_any_arg(ctx)?);
         }
         Ok(())
     }
+
+    /// Checks taking a group, optionally followed by bounds on its members.
+    pub(super) trait ApplyGroup<T> {
+        fn apply(&self, target: &mut Target, val: T);
+    }
+
+    impl<'a, F> ApplyGroup<Vec<&'a dyn AnyArg>> for F
+    where
+        F: for<'t> Fn(&'t mut Target, Vec<&'a dyn AnyArg>) -> &'t mut Target,
+    {
+        fn apply(&self, target: &mut Target, val: Vec<&'a dyn AnyArg>) {
+            self(target, val);
+        }
+    }
+
+    impl<'a, F> ApplyGroup<(Vec<&'a dyn AnyArg>, [usize; 1])> for F
+    where
+        F: for<'t> Fn(&'t mut Target, Vec<&'a dyn AnyArg>, usize) -> &'t mut Target,
+    {
+        fn apply(&self, target: &mut Target, (group, [n]): (Vec<&'a dyn AnyArg>, [usize; 1])) {
+            self(target, group, n);
+        }
+    }
+
+    impl<'a, F> ApplyGroup<(Vec<&'a dyn AnyArg>, [usize; 2])> for F
+    where
+        F: for<'t> Fn(&'t mut Target, Vec<&'a dyn AnyArg>, usize, usize) -> &'t mut Target,
+    {
+        fn apply(&self, target: &mut Target, (group, [a, b]): (Vec<&'a dyn AnyArg>, [usize; 2])) {
+            self(target, group, a, b);
+        }
+    }
 }
 
 define_plap_args! {
@@ -146,6 +184,8 @@ define_plap_args! {
         #[arg(is_flag)]
         pub is_help: LitBool,
         #[arg(is_flag)]
+        pub is_verbatim: LitBool,
+        #[arg(is_flag)]
         pub optional: LitBool,
     }
 }
@@ -163,8 +203,8 @@ mod ApplyArg {
         _ctx: &Context,
         f: impl Fn(&mut Target) -> &mut Target,
     ) -> syn::Result<()> {
-        for (key, val) in arg.keys().iter().zip(arg.values()) {
-            ensure_is_true(key, val)?;
+        for entry in arg.entries() {
+            ensure_is_true(&entry.key, &entry.value)?;
             f(target);
         }
         Ok(())
@@ -188,6 +228,8 @@ define_plap_args! {
         pub conflicts_with: Ident,
         #[arg(is_expr)]
         pub conflicts_with_each: MaybeList<Ident>,
+        #[arg(is_expr)]
+        pub conflicts_with_all: MaybeList<Ident>,
         #[arg(is_flag)]
         pub blocked: LitBool,
     }
@@ -210,8 +252,8 @@ mod ApplyCheck {
         f: impl for<'t> Fn(&'t mut Target, &dyn AnyArg) -> &'t mut Target,
     ) -> syn::Result<()> {
         let a = ctx.field.to_any_arg(ctx.defs)?;
-        for (key, val) in arg.keys().iter().zip(arg.values()) {
-            ensure_is_true(key, val)?;
+        for entry in arg.entries() {
+            ensure_is_true(&entry.key, &entry.value)?;
             f(target, a);
         }
         Ok(())
@@ -262,7 +304,7 @@ impl CheckArgs {
     }
 }
 
-trait ToAnyArg<'a> {
+pub(crate) trait ToAnyArg<'a> {
     type Type;
 
     fn to_any_arg(&self, defs: &'a ArgDefs) -> syn::Result<Self::Type>;
@@ -273,10 +315,11 @@ impl<'a> ToAnyArg<'a> for Ident {
 
     fn to_any_arg(&self, defs: &'a ArgDefs) -> syn::Result<Self::Type> {
         defs.get(self)
-            .ok_or_else(|| syn_error!(self.span(), "undefined argument"))
+            .ok_or_else(|| syn_error!(self.span(), "undefined argument `{}`", self))
             .and_then(|d| {
-                d.as_arg()
-                    .ok_or_else(|| syn_error!(self.span(), "group cannot be used as argument"))
+                d.as_arg().ok_or_else(|| {
+                    syn_error!(self.span(), "group `{}` cannot be used as argument", self)
+                })
             })
             .map(|a| &a.i as &dyn AnyArg)
     }
@@ -333,13 +376,50 @@ impl<'a> ToAnyArg<'a> for MaybeList<Ident> {
         match self {
             Self::Elem(i) => defs
                 .get(i)
-                .ok_or_else(|| syn_error!(i.span(), "undefined group"))
+                .ok_or_else(|| syn_error!(i.span(), "undefined group `{}`", i))
                 .and_then(|d| {
-                    d.as_group()
-                        .ok_or_else(|| syn_error!(i.span(), "argument cannot be used as group"))
+                    d.as_group().ok_or_else(|| {
+                        syn_error!(i.span(), "argument `{}` cannot be used as group", i)
+                    })
                 })
                 .and_then(|g| g.members.iter().map(|i| i.to_any_arg(defs)).collect()),
             Self::List(l) => l.to_any_arg(defs),
         }
     }
 }
+
+/// A group followed by `N` bounds on its members, e.g. `max_members(grp, 2)`.
+pub(crate) struct Bounded<const N: usize> {
+    pub group: MaybeList<Ident>,
+    pub bounds: [LitInt; N],
+}
+
+impl<const N: usize> syn::parse::Parse for Bounded<N> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let group = input.parse()?;
+        let mut bounds = Vec::with_capacity(N);
+        for _ in 0..N {
+            input.parse::<Token![,]>()?;
+            bounds.push(input.parse::<LitInt>()?);
+        }
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self {
+            group,
+            bounds: bounds
+                .try_into()
+                .unwrap_or_else(|_| unreachable!("exactly `N` bounds are parsed")),
+        })
+    }
+}
+
+impl<'a, const N: usize> ToAnyArg<'a> for Bounded<N> {
+    type Type = (Vec<&'a dyn AnyArg>, [usize; N]);
+
+    fn to_any_arg(&self, defs: &'a ArgDefs) -> syn::Result<Self::Type> {
+        let mut bounds = [0; N];
+        for (n, lit) in bounds.iter_mut().zip(self.bounds.iter()) {
+            *n = lit.base10_parse()?;
+        }
+        Ok((self.group.to_any_arg(defs)?, bounds))
+    }
+}
@@ -5,13 +5,24 @@ use proc_macro2::{Ident, Span, TokenStream};
 use syn::parse::{Nothing, ParseStream};
 use syn::{Attribute, Data, DeriveInput, Field, GenericArgument, ItemStruct, PathArguments, Type};
 
-use crate::args::{CheckArgs, ContainerCheckArgs};
+use crate::args::{CheckArgs, ContainerCheckArgs, ToAnyArg};
 use crate::dyn_parser::DynParser;
 
 pub fn expand(input: ItemStruct, item: DeriveInput) -> syn::Result<TokenStream> {
     let (groups, check) = crate::args::parse_container_args(&input.attrs)?;
     let mut defs = parse_defs(&input)?;
-    defs.extend(groups.into_iter().map(|(k, v)| (k, Def::Group(v))));
+    let mut errors = Errors::default();
+    for (name, group) in groups {
+        if let Err(name) = defs.insert(name, Def::Group(group)) {
+            errors.add(syn_error!(
+                name.span(),
+                "`{}` is defined more than once",
+                name
+            ));
+        }
+    }
+    errors.fail::<()>()?;
+    resolve(&check, &defs)?;
 
     let mut errors = Errors::default();
     Checker {
@@ -26,20 +37,42 @@ pub fn expand(input: ItemStruct, item: DeriveInput) -> syn::Result<TokenStream>
     errors.fail()
 }
 
+/// Resolves the names referred to by groups and checks, which is deferred
+/// until all arguments and groups are declared, so that their order does not
+/// matter.
+fn resolve(check: &ContainerCheckArgs, defs: &ArgDefs) -> syn::Result<()> {
+    let mut errors = Errors::default();
+    for def in defs.values() {
+        if let Some(group) = def.as_group() {
+            for member in group.members.iter() {
+                errors.add_result(member.to_any_arg(defs));
+            }
+        }
+    }
+    // checks are applied to absent arguments, which only fails on unresolved names
+    let mut c = plap::Checker::default();
+    errors.add_result(check.check(&mut c, defs));
+    for (field, def) in defs.iter() {
+        if let Some(arg) = def.as_arg() {
+            errors.add_result(arg.check.check(&mut c, defs, field));
+        }
+    }
+    errors.fail()
+}
+
 fn parse_defs(input: &ItemStruct) -> syn::Result<ArgDefs> {
     let mut defs = ArgDefs::default();
     for field in input.fields.iter() {
         let (name, parser) = parse_field(field)?;
         let (arg, check) = crate::args::parse_field_args(&field.attrs)?;
-        defs.insert(
-            name.clone(),
-            Def::Arg(ArgDef {
-                i: Arg::from_string(name.to_string()),
-                parser,
-                attrs: arg.build_arg_attrs()?,
-                check,
-            }),
-        );
+        let def = Def::Arg(ArgDef {
+            i: Arg::from_string(name.to_string()),
+            parser,
+            attrs: arg.build_arg_attrs()?,
+            check,
+        });
+        // fields are unique, as rustc has checked
+        let _ = defs.insert(name.clone(), def);
     }
     Ok(defs)
 }
@@ -86,8 +119,47 @@ fn infer_arg_type(ty: &Type) -> Option<&Ident> {
     }
 }
 
-pub(crate) type ArgDefs = BTreeMap<Ident, Def>;
+/// Arguments and groups by name, which are iterated in declaration order, so
+/// that checks run in the same order as those of `plap::define_args!`.
+#[derive(Default)]
+pub(crate) struct ArgDefs {
+    defs: BTreeMap<Ident, Def>,
+    order: Vec<Ident>,
+}
+
+impl ArgDefs {
+    /// Adds a definition, or returns its name if it is already defined.
+    pub fn insert(&mut self, name: Ident, def: Def) -> Result<(), Ident> {
+        if self.defs.contains_key(&name) {
+            return Err(name);
+        }
+        self.order.push(name.clone());
+        self.defs.insert(name, def);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &Ident) -> Option<&Def> {
+        self.defs.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &Ident) -> Option<&mut Def> {
+        self.defs.get_mut(name)
+    }
+
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (&Ident, &Def)> {
+        self.order.iter().map(move |name| (name, &self.defs[name]))
+    }
+
+    pub fn values(&self) -> impl '_ + Iterator<Item = &Def> {
+        self.iter().map(|(_, def)| def)
+    }
+
+    pub fn values_mut(&mut self) -> impl '_ + Iterator<Item = &mut Def> {
+        self.defs.values_mut()
+    }
+}
 
+#[allow(clippy::large_enum_variant)]
 pub(crate) enum Def {
     Arg(ArgDef),
     Group(GroupDef),
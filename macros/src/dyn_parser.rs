@@ -1,22 +1,23 @@
 use std::collections::BTreeMap;
 
-use proc_macro2::{Ident, Span};
+use proc_macro2::Ident;
 use syn::parse::{Parse, ParseStream};
 
-type DynParserMap = BTreeMap<Ident, fn() -> DynParser>;
+// keyed by names, since idents cannot outlive a macro invocation
+type DynParserMap = BTreeMap<&'static str, fn() -> DynParser>;
 
 macro_rules! make_parsers {
     ($($name:ident = $ty:ty,)*) => {{
         let mut map = DynParserMap::default();
         $(map.insert(
-            Ident::new(stringify!($name), Span::call_site()),
+            stringify!($name),
             || DynParser(|input| {
                 <$ty as Parse>::parse(input).map(|_| ())
             }),
         );
         // optional parser
         map.insert(
-            Ident::new(concat!("Optional", stringify!($name)), Span::call_site()),
+            concat!("Optional", stringify!($name)),
             || DynParser(|input| {
                 <::plap::Optional<$ty> as Parse>::parse(input).map(|_| ())
             }),
@@ -39,6 +40,7 @@ thread_local! {
             LitInt = syn::LitInt,
             LitStr = syn::LitStr,
             Meta = syn::Meta,
+            TokenStream = proc_macro2::TokenStream,
             Path = syn::Path,
             Type = syn::Type,
             Visibility = syn::Visibility,
@@ -53,7 +55,9 @@ pub(crate) struct DynParser(fn(ParseStream) -> syn::Result<()>);
 
 impl DynParser {
     pub fn get(ty: &Ident) -> Option<Self> {
-        DYN_PARSER_MAP.with(|m| m.get(ty).copied()).map(|f| f())
+        DYN_PARSER_MAP
+            .with(|m| m.get(ty.to_string().as_str()).copied())
+            .map(|f| f())
     }
 
     pub fn parse(&self, input: ParseStream) -> syn::Result<()> {
@@ -1,9 +1,49 @@
-use proc_macro2::Ident;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use proc_macro2::{Ident, Span};
+use syn::parse::ParseStream;
+
+use crate::code::{with_docs, ErrorCode};
+use crate::fingerprint::Fingerprint;
+use crate::small_vec::SmallVec;
+use crate::target::Target;
 
 #[derive(Debug, Default)]
 pub struct ArgAttrs {
     kind: ArgKind,
     optional: bool,
+    accepts_empty: bool,
+    delimiter: Option<ArgDelimiter>,
+    range: Option<(Bound<Number>, Bound<Number>)>,
+    suffix: Option<&'static str>,
+    value_name: Option<&'static str>,
+    env: Option<&'static str>,
+    unstable: Option<&'static str>,
+    requires_feature: Option<FeatureGate>,
+    display_order: Option<usize>,
+    heading: Option<&'static str>,
+    rename: Option<&'static str>,
+    explicit: bool,
+    family: Option<&'static str>,
+    only_on: Option<&'static [Target]>,
+    docs_url: Option<&'static str>,
+    validator: Option<DebugValidator>,
+}
+
+/// Checks the tokens of a value before it is parsed, see
+/// [`ArgAttrs::validate`].
+pub type Validator = fn(ParseStream) -> syn::Result<()>;
+
+// higher-ranked function pointers do not implement `Debug` on older compilers
+#[derive(Clone, Copy)]
+struct DebugValidator(Validator);
+
+impl fmt::Debug for DebugValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Validator")
+    }
 }
 
 impl ArgAttrs {
@@ -28,11 +68,152 @@ impl ArgAttrs {
         self.kind(ArgKind::Help)
     }
 
+    pub fn is_verbatim(&mut self) -> &mut Self {
+        self.kind(ArgKind::Verbatim)
+    }
+
     pub fn optional(&mut self) -> &mut Self {
         self.optional = true;
         self
     }
 
+    /// Declares that the value type parses empty input, e.g.
+    /// [`syn::Visibility`], so that the key alone supplies an empty value,
+    /// e.g. `vis` for the inherited visibility. Values after `=` must still
+    /// be written out.
+    ///
+    /// [`debug_assert_schema!`](crate::debug_assert_schema) panics if an
+    /// expression type parses empty input without this, or with this fails
+    /// to.
+    pub fn accepts_empty(&mut self) -> &mut Self {
+        self.accepts_empty = true;
+        self
+    }
+
+    /// Requires a value to be written out, i.e. forbids the shorthand `flag`
+    /// for `flag = true`, and the empty value of an
+    /// [`optional`](Self::optional) argument.
+    pub fn explicit(&mut self) -> &mut Self {
+        self.explicit = true;
+        self
+    }
+
+    /// Only accepts values delimited in the given style. Omitted values, such
+    /// as flag shorthands, are still controlled by the [`ArgKind`].
+    pub fn delimiter(&mut self, delimiter: ArgDelimiter) -> &mut Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Requires numeric literal values to fall in `range`, e.g. `1..=10` or
+    /// `0.0..1.0`.
+    pub fn range<N: RangeBound>(&mut self, range: impl RangeBounds<N>) -> &mut Self {
+        fn to_number<N: RangeBound>(bound: Bound<&N>) -> Bound<Number> {
+            match bound {
+                Bound::Included(&n) => Bound::Included(n.to_number()),
+                Bound::Excluded(&n) => Bound::Excluded(n.to_number()),
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        }
+        self.range = Some((to_number(range.start_bound()), to_number(range.end_bound())));
+        self
+    }
+
+    /// Requires numeric literal values to have exactly the given suffix, where
+    /// `""` only accepts unsuffixed literals.
+    pub fn suffix(&mut self, suffix: &'static str) -> &mut Self {
+        self.suffix = Some(suffix);
+        self
+    }
+
+    /// Names the value in [usage](Self::usage) and errors, e.g. `PATH` for
+    /// `path = <PATH>`.
+    pub fn value_name(&mut self, name: &'static str) -> &mut Self {
+        self.value_name = Some(name);
+        self
+    }
+
+    /// Falls back to the environment variable `var` of the process running the
+    /// macro, if the argument is not supplied, see
+    /// [`Args::resolve_defaults`](crate::Args::resolve_defaults).
+    pub fn env(&mut self, var: &'static str) -> &mut Self {
+        self.env = Some(var);
+        self
+    }
+
+    /// Only accepts the argument after `unstable(feature)` is supplied, to ship
+    /// a preview of it.
+    pub fn unstable(&mut self, feature: &'static str) -> &mut Self {
+        self.unstable = Some(feature);
+        self
+    }
+
+    /// Only accepts the argument if the macro crate is built with a cargo
+    /// feature, e.g. `requires_feature = plap::feature!("extra-codegen")`.
+    /// Otherwise, the argument stays known, and supplying it reports which
+    /// feature to enable rather than an unknown argument.
+    pub fn requires_feature(&mut self, gate: FeatureGate) -> &mut Self {
+        self.requires_feature = Some(gate);
+        self
+    }
+
+    /// Sorts the argument in [help](crate::Args::help) output, where arguments
+    /// without an order follow in declaration order.
+    pub fn display_order(&mut self, order: usize) -> &mut Self {
+        self.display_order = Some(order);
+        self
+    }
+
+    /// Lists the argument under `heading` in [help](crate::Args::help) output.
+    pub fn heading(&mut self, heading: &'static str) -> &mut Self {
+        self.heading = Some(heading);
+        self
+    }
+
+    /// Accepts the argument under the key `name` rather than the name of its
+    /// field or variant.
+    pub fn rename(&mut self, name: &'static str) -> &mut Self {
+        self.rename = Some(name);
+        self
+    }
+
+    /// Accepts any key starting with `prefix`, e.g. `on_create` and
+    /// `on_delete` for `on_`, each of which is supplied at most once, see
+    /// [`Arg::suffixes`].
+    ///
+    /// Keys of other arguments take precedence over families regardless of
+    /// the order they are declared in, e.g. `on_ready` of a field named so is
+    /// never taken by the family `on_`, while families are tried in
+    /// declaration order.
+    pub fn family(&mut self, prefix: &'static str) -> &mut Self {
+        self.family = Some(prefix);
+        self
+    }
+
+    /// Only accepts the argument in attributes placed on one of `targets`, see
+    /// [`Args::check_placement`](crate::Args::check_placement), which is
+    /// also noted in [help](crate::Args::help) output.
+    pub fn only_on(&mut self, targets: &'static [Target]) -> &mut Self {
+        self.only_on = Some(targets);
+        self
+    }
+
+    /// Links to the docs of the argument from errors reported on it, e.g.
+    /// `https://docs.rs/my-crate/latest/my_crate/#rename`, unless turned off
+    /// by [`ErrorCode::show_docs`](crate::ErrorCode::show_docs).
+    pub fn docs_url(&mut self, url: &'static str) -> &mut Self {
+        self.docs_url = Some(url);
+        self
+    }
+
+    /// Checks each value with `validator` before it is parsed, e.g. one of
+    /// [`validators`](crate::validators). The validator reads a fork of the
+    /// value, and should leave values of unexpected types to the parser.
+    pub fn validate(&mut self, validator: Validator) -> &mut Self {
+        self.validator = Some(DebugValidator(validator));
+        self
+    }
+
     pub fn get_kind(&self) -> ArgKind {
         self.kind
     }
@@ -40,14 +221,174 @@ impl ArgAttrs {
     pub fn get_optional(&self) -> bool {
         self.optional
     }
+
+    pub fn get_accepts_empty(&self) -> bool {
+        self.accepts_empty
+    }
+
+    pub fn get_explicit(&self) -> bool {
+        self.explicit
+    }
+
+    pub fn get_delimiter(&self) -> Option<ArgDelimiter> {
+        self.delimiter
+    }
+
+    pub fn get_range(&self) -> Option<(Bound<Number>, Bound<Number>)> {
+        self.range
+    }
+
+    pub fn get_suffix(&self) -> Option<&'static str> {
+        self.suffix
+    }
+
+    pub fn get_value_name(&self) -> Option<&'static str> {
+        self.value_name
+    }
+
+    pub fn get_env(&self) -> Option<&'static str> {
+        self.env
+    }
+
+    pub fn get_unstable(&self) -> Option<&'static str> {
+        self.unstable
+    }
+
+    pub fn get_requires_feature(&self) -> Option<FeatureGate> {
+        self.requires_feature
+    }
+
+    pub fn get_display_order(&self) -> Option<usize> {
+        self.display_order
+    }
+
+    pub fn get_heading(&self) -> Option<&'static str> {
+        self.heading
+    }
+
+    pub fn get_rename(&self) -> Option<&'static str> {
+        self.rename
+    }
+
+    pub fn get_family(&self) -> Option<&'static str> {
+        self.family
+    }
+
+    pub fn get_only_on(&self) -> Option<&'static [Target]> {
+        self.only_on
+    }
+
+    pub fn get_docs_url(&self) -> Option<&'static str> {
+        self.docs_url
+    }
+
+    pub fn get_validator(&self) -> Option<Validator> {
+        self.validator.map(|v| v.0)
+    }
+
+    /// Returns how the argument `name` is supplied, e.g. `path = <PATH>`, or
+    /// `path[ = <PATH>]` if its value may be omitted, where families of keys
+    /// are named by their prefixes, e.g. `on_* = <value>`.
+    pub fn usage(&self, name: &str) -> String {
+        let family;
+        let name = match self.family {
+            Some(prefix) => {
+                family = format!("{}*", prefix);
+                &family
+            }
+            None => name,
+        };
+        match self.kind {
+            ArgKind::Flag | ArgKind::Help => name.to_owned(),
+            _ => {
+                let value = self.value_name.unwrap_or("value");
+                if self.optional || self.accepts_empty {
+                    format!("{}[ = <{}>]", name, value)
+                } else {
+                    format!("{} = <{}>", name, value)
+                }
+            }
+        }
+    }
+}
+
+/// Lists the kind and every non-default attribute, e.g.
+/// `expr, optional, range = 1..=8`.
+impl fmt::Display for ArgAttrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.kind {
+            ArgKind::Expr => "expr",
+            ArgKind::Flag => "flag",
+            ArgKind::TokenTree => "token_tree",
+            ArgKind::Help => "help",
+            ArgKind::Verbatim => "verbatim",
+        })?;
+        if self.optional {
+            f.write_str(", optional")?;
+        }
+        if self.accepts_empty {
+            f.write_str(", accepts_empty")?;
+        }
+        if self.explicit {
+            f.write_str(", explicit")?;
+        }
+        if let Some(delimiter) = self.delimiter {
+            write!(f, ", delimiter = {:?}", delimiter)?;
+        }
+        if let Some((start, end)) = self.range {
+            write!(f, ", range = {}", FmtRange(start, end))?;
+        }
+        if let Some(suffix) = self.suffix {
+            write!(f, ", suffix = {:?}", suffix)?;
+        }
+        if let Some(value_name) = self.value_name {
+            write!(f, ", value_name = {:?}", value_name)?;
+        }
+        if let Some(env) = self.env {
+            write!(f, ", env = {:?}", env)?;
+        }
+        if let Some(feature) = self.unstable {
+            write!(f, ", unstable = {:?}", feature)?;
+        }
+        if let Some(gate) = self.requires_feature {
+            write!(f, ", requires_feature = {:?}", gate.name())?;
+        }
+        if let Some(order) = self.display_order {
+            write!(f, ", display_order = {}", order)?;
+        }
+        if let Some(heading) = self.heading {
+            write!(f, ", heading = {:?}", heading)?;
+        }
+        if let Some(name) = self.rename {
+            write!(f, ", rename = {:?}", name)?;
+        }
+        if let Some(prefix) = self.family {
+            write!(f, ", family = {:?}", prefix)?;
+        }
+        if let Some(targets) = self.only_on {
+            write!(f, ", only_on = {:?}", targets)?;
+        }
+        if let Some(url) = self.docs_url {
+            write!(f, ", docs_url = {:?}", url)?;
+        }
+        if self.validator.is_some() {
+            f.write_str(", validate")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ArgKind {
     Expr,
     Flag,
+    /// A quoted value after `=`, e.g. `ty = "Vec<T>"`, or tokens in
+    /// parentheses. Unquoted values are also accepted after `=` if they end at
+    /// the next top-level separator.
     TokenTree,
     Help,
+    /// Raw tokens after `=` up to the next top-level `,`, without quotes.
+    Verbatim,
 }
 
 impl Default for ArgKind {
@@ -56,14 +397,416 @@ impl Default for ArgKind {
     }
 }
 
-#[derive(Debug)]
+/// A bound of numeric literals accepted by [`ArgAttrs::range`], i.e. any
+/// primitive number.
+pub trait RangeBound: Copy {
+    fn to_number(self) -> Number;
+}
+
+macro_rules! impl_range_bound {
+    (int $($ty:ty),*) => {$(
+        impl RangeBound for $ty {
+            fn to_number(self) -> Number {
+                Number::Int(self as i128)
+            }
+        }
+    )*};
+    (float $($ty:ty),*) => {$(
+        impl RangeBound for $ty {
+            fn to_number(self) -> Number {
+                Number::Float(self as f64)
+            }
+        }
+    )*};
+}
+
+impl_range_bound!(int i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+impl_range_bound!(float f32, f64);
+
+impl RangeBound for u128 {
+    fn to_number(self) -> Number {
+        Number::from_magnitude(false, self)
+    }
+}
+
+/// A bound of [`ArgAttrs::range`], or the value of a numeric literal checked
+/// against it, where integers are compared exactly, even beyond the integers
+/// a `f64` can hold, and only floats are compared as such.
+#[derive(Clone, Copy, Debug)]
+pub enum Number {
+    Int(i128),
+    /// An integer above [`i128::MAX`].
+    UInt(u128),
+    Float(f64),
+}
+
+impl Number {
+    /// Returns the integer `magnitude` negated if `negative`, or the nearest
+    /// float if no integer holds it, e.g. `-2^128`.
+    pub(crate) fn from_magnitude(negative: bool, magnitude: u128) -> Self {
+        const MAX: u128 = i128::MAX as u128;
+        match (negative, magnitude) {
+            (false, m) if m <= MAX => Number::Int(m as i128),
+            (false, m) => Number::UInt(m),
+            (true, m) if m <= MAX => Number::Int(-(m as i128)),
+            (true, m) if m == MAX + 1 => Number::Int(i128::MIN),
+            (true, m) => Number::Float(-(m as f64)),
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::UInt(n) => n as f64,
+            Number::Float(n) => n,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (*self, *other) {
+            (Number::Int(a), Number::Int(b)) => a.partial_cmp(&b),
+            (Number::UInt(a), Number::UInt(b)) => a.partial_cmp(&b),
+            (Number::Int(_), Number::UInt(_)) => Some(Ordering::Less),
+            (Number::UInt(_), Number::Int(_)) => Some(Ordering::Greater),
+            (a, b) => a.to_f64().partial_cmp(&b.to_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(n) => write!(f, "{}", n),
+            Number::UInt(n) => write!(f, "{}", n),
+            Number::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Shows a range as written in Rust, e.g. `1..=8`, or as a tuple of bounds if
+/// its start is excluded, which no range expression can write.
+pub(crate) struct FmtRange(pub Bound<Number>, pub Bound<Number>);
+
+impl fmt::Display for FmtRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn fmt_bound(f: &mut fmt::Formatter, bound: Bound<Number>) -> fmt::Result {
+            match bound {
+                Bound::Included(n) => write!(f, "Included({})", n),
+                Bound::Excluded(n) => write!(f, "Excluded({})", n),
+                Bound::Unbounded => f.write_str("Unbounded"),
+            }
+        }
+        match (self.0, self.1) {
+            (Bound::Excluded(_), end) => {
+                f.write_str("(")?;
+                fmt_bound(f, self.0)?;
+                f.write_str(", ")?;
+                fmt_bound(f, end)?;
+                f.write_str(")")
+            }
+            (start, end) => {
+                if let Bound::Included(n) = start {
+                    write!(f, "{}", n)?;
+                }
+                match end {
+                    Bound::Included(n) => write!(f, "..={}", n),
+                    Bound::Excluded(n) => write!(f, "..{}", n),
+                    Bound::Unbounded => f.write_str(".."),
+                }
+            }
+        }
+    }
+}
+
+/// How a value is separated from its key.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ArgDelimiter {
+    /// `key = value`
+    Eq,
+    /// `key(value)`
+    Paren,
+    /// `key`, where the value is implied by the [`ArgKind`]
+    Omitted,
+}
+
+/// A cargo feature of the macro crate, which an argument
+/// [requires](ArgAttrs::requires_feature), created by
+/// [`feature!`](crate::feature).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FeatureGate {
+    name: &'static str,
+    krate: &'static str,
+    enabled: bool,
+}
+
+impl FeatureGate {
+    pub fn new(name: &'static str, krate: &'static str, enabled: bool) -> Self {
+        Self {
+            name,
+            krate,
+            enabled,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the name of the crate the feature belongs to, e.g. `my-macros`.
+    pub fn krate(&self) -> &'static str {
+        self.krate
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Creates a [`FeatureGate`] of the cargo feature `name` of the crate this is
+/// written in, which is enabled if the crate is built with it. The feature
+/// should be declared in its `Cargo.toml`, even if nothing else depends on it.
+///
+/// ```
+/// # use plap::{define_args, Arg, Args};
+/// # use syn::LitBool;
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct MyArgs {
+///         #[arg(is_flag, requires_feature = plap::feature!("extra-codegen"))]
+///         codegen: Arg<LitBool>,
+///     }
+/// }
+///
+/// let err = syn::parse::Parser::parse_str(MyArgs::parse, "codegen").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "`codegen` requires the `extra-codegen` feature of `plap`, enable it to use `codegen`",
+/// );
+/// ```
+#[macro_export]
+macro_rules! feature {
+    ($name:literal) => {
+        $crate::FeatureGate::new(
+            $name,
+            ::std::env!("CARGO_PKG_NAME"),
+            ::std::cfg!(feature = $name),
+        )
+    };
+}
+
+/// Where a value of an [`Arg`] comes from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ValueSource {
+    /// Supplied in the parsed input.
+    Supplied,
+    /// Provided by [`Arg::default_value`].
+    Default,
+    /// Read from the named environment variable, see [`ArgAttrs::env`].
+    Env(&'static str),
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Supplied => f.write_str("supplied"),
+            Self::Default => f.write_str("by default"),
+            Self::Env(var) => write!(f, "from environment variable `{}`", var),
+        }
+    }
+}
+
+/// A value of an [`Arg`], along with the key it is supplied to.
+#[derive(Clone, Debug)]
+pub struct Entry<T> {
+    pub key: Ident,
+    pub value: T,
+    pub delimiter: ArgDelimiter,
+    pub source: ValueSource,
+    /// Where the value is written, see [`Parser::value_span`].
+    ///
+    /// [`Parser::value_span`]: crate::Parser::value_span
+    pub value_span: Span,
+    /// A hash of the token text of the value, see
+    /// [`Parser::value_fingerprint`], or `0` if the value is not parsed.
+    ///
+    /// [`Parser::value_fingerprint`]: crate::Parser::value_fingerprint
+    pub fingerprint: u64,
+    /// The index of the argument in the input it is parsed from, see
+    /// [`Parser::position`], or [`usize::MAX`] if the value is not parsed,
+    /// e.g. a default one. See [`Checker`](crate::Checker) for how findings
+    /// are ordered by it.
+    ///
+    /// [`Parser::position`]: crate::Parser::position
+    pub position: usize,
+}
+
+impl<T> Entry<T> {
+    /// Creates an entry supplied without a delimiter, whose value is spanned
+    /// by `key`.
+    pub fn new(key: Ident, value: T) -> Self {
+        Self {
+            value_span: key.span(),
+            fingerprint: 0,
+            position: usize::MAX,
+            key,
+            value,
+            delimiter: ArgDelimiter::Omitted,
+            source: ValueSource::Supplied,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.key.span()
+    }
+}
+
+/// The presence of an argument, regardless of the type of its values, e.g. to
+/// write helpers generic over all arguments.
+pub trait Presence {
+    fn name(&self) -> &str;
+
+    /// Returns the key of the `i`-th occurrence.
+    fn key(&self, i: usize) -> Option<&Ident>;
+
+    fn occurrences(&self) -> usize;
+
+    /// Returns the key of each occurrence, see [`Keys::new`] for trait
+    /// objects.
+    fn keys(&self) -> Keys<'_, Self>
+    where
+        Self: Sized,
+    {
+        Keys::new(self)
+    }
+
+    /// Returns where the value of the `i`-th occurrence is written.
+    fn value_span(&self, i: usize) -> Option<Span>;
+
+    /// Returns where the value of the `i`-th occurrence comes from.
+    fn source(&self, i: usize) -> Option<ValueSource>;
+
+    /// Returns the [position](Entry::position) of the `i`-th occurrence, if
+    /// known.
+    fn position(&self, i: usize) -> Option<usize> {
+        let _ = i;
+        None
+    }
+
+    /// Returns the link to the docs of the argument, see
+    /// [`ArgAttrs::docs_url`].
+    fn docs_url(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns where the key of the `i`-th occurrence is written, which is
+    /// where duplicates should be reported, since for flags the value is
+    /// spanned by the key anyway.
+    fn span_of_key(&self, i: usize) -> Option<Span> {
+        self.key(i).map(Ident::span)
+    }
+
+    /// Returns the key the last supplied occurrence is written under, which
+    /// may differ from the [name](Self::name), e.g. `db_url` of a merged
+    /// argument, or `on_create` of a [family](ArgAttrs::family).
+    fn used_key_str(&self) -> Option<String> {
+        Keys::new(self)
+            .enumerate()
+            .rev()
+            .find(|(i, _)| self.source(*i).map_or(true, |s| s == ValueSource::Supplied))
+            .map(|(_, key)| key.to_string())
+    }
+
+    /// Returns the indices of supplied occurrences grouped by the text of
+    /// their keys, keeping only keys supplied more than once, in the order
+    /// they are first supplied. Keys of a [family](ArgAttrs::family) are told
+    /// apart, e.g. `on_create` and `on_delete`.
+    fn duplicate_keys(&self) -> Vec<Vec<usize>> {
+        let mut groups = Vec::<(String, Vec<usize>)>::new();
+        for (i, key) in Keys::new(self).enumerate() {
+            if self.source(i).map_or(false, |s| s != ValueSource::Supplied) {
+                continue;
+            }
+            let key = key.to_string();
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, group)) => group.push(i),
+                None => groups.push((key, vec![i])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, group)| group)
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    fn is_present(&self) -> bool {
+        self.occurrences() > 0
+    }
+
+    /// Returns `true` if the argument is present, but not supplied by the
+    /// user, e.g. given by [`Arg::default_value`].
+    fn is_defaulted(&self) -> bool {
+        self.is_present()
+            && (0..self.occurrences()).all(|i| self.source(i) != Some(ValueSource::Supplied))
+    }
+}
+
+/// An iterator over the keys of an argument, see [`Presence::keys`].
+pub struct Keys<'a, P: ?Sized> {
+    arg: &'a P,
+    range: std::ops::Range<usize>,
+}
+
+impl<'a, P: ?Sized + Presence> Keys<'a, P> {
+    /// Iterates over the keys of `arg`, which may be a trait object, e.g.
+    /// `&dyn AnyArg`.
+    pub fn new(arg: &'a P) -> Self {
+        Self {
+            arg,
+            range: 0..arg.occurrences(),
+        }
+    }
+}
+
+impl<'a, P: ?Sized + Presence> Iterator for Keys<'a, P> {
+    type Item = &'a Ident;
+
+    fn next(&mut self) -> Option<&'a Ident> {
+        self.range.next().and_then(|i| self.arg.key(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, P: ?Sized + Presence> DoubleEndedIterator for Keys<'a, P> {
+    fn next_back(&mut self) -> Option<&'a Ident> {
+        self.range.next_back().and_then(|i| self.arg.key(i))
+    }
+}
+
+impl<P: ?Sized + Presence> ExactSizeIterator for Keys<'_, P> {}
+
+#[derive(Clone, Debug)]
 pub struct Arg<T> {
     #[cfg(feature = "string")]
     name: crate::str::Str,
     #[cfg(not(feature = "string"))]
     name: &'static str,
-    keys: Vec<Ident>,
-    values: Vec<T>,
+    entries: SmallVec<Entry<T>>,
+    docs_url: Option<&'static str>,
+    // the prefix of a family, along with the length of the prefix of merged
+    // arguments its keys are supplied with, e.g. `db_` of `db_on_create`
+    family: Option<(&'static str, usize)>,
 }
 
 impl<T> Arg<T> {
@@ -74,8 +817,9 @@ impl<T> Arg<T> {
             name: name.into(),
             #[cfg(not(feature = "string"))]
             name,
-            keys: <_>::default(),
-            values: <_>::default(),
+            entries: <_>::default(),
+            docs_url: None,
+            family: None,
         }
     }
 
@@ -84,11 +828,20 @@ impl<T> Arg<T> {
     pub fn from_string(name: impl Into<String>) -> Self {
         Self {
             name: crate::str::Str::from(name.into()),
-            keys: <_>::default(),
-            values: <_>::default(),
+            entries: <_>::default(),
+            docs_url: None,
+            family: None,
         }
     }
 
+    pub(crate) fn set_docs_url(&mut self, url: Option<&'static str>) {
+        self.docs_url = url;
+    }
+
+    pub(crate) fn set_family(&mut self, prefix: Option<&'static str>, merged_len: usize) {
+        self.family = prefix.map(|prefix| (prefix, merged_len));
+    }
+
     pub fn name(&self) -> &str {
         #[cfg(feature = "string")]
         return self.name.as_str();
@@ -97,64 +850,489 @@ impl<T> Arg<T> {
     }
 
     pub fn len(&self) -> usize {
-        self.keys.len()
+        self.entries.len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    pub fn keys(&self) -> &[Ident] {
-        &self.keys
+    /// Returns all values along with where they come from, in the order they
+    /// are added.
+    pub fn entries(&self) -> &[Entry<T>] {
+        &self.entries
+    }
+
+    #[deprecated(note = "use `entries`")]
+    pub fn keys(&self) -> Keys<'_, Self> {
+        Presence::keys(self)
+    }
+
+    /// Returns each value, see [`entries`](Self::entries).
+    pub fn values(&self) -> impl '_ + DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.entries.iter().map(|e| &e.value)
+    }
+
+    /// Returns where the key of the `i`-th value is written, see
+    /// [`Presence::span_of_key`].
+    pub fn span_of_key(&self, i: usize) -> Option<Span> {
+        Presence::span_of_key(self, i)
+    }
+
+    /// Returns the key the last value is supplied under, as the user spells
+    /// it, see [`Presence::used_key_str`].
+    pub fn used_key_str(&self) -> Option<String> {
+        Presence::used_key_str(self)
+    }
+
+    /// Returns the indices of values supplied under the same key, see
+    /// [`Presence::duplicate_keys`].
+    pub fn duplicate_keys(&self) -> Vec<Vec<usize>> {
+        Presence::duplicate_keys(self)
+    }
+
+    #[deprecated(note = "use `entries`")]
+    pub fn delimiters(&self) -> Vec<ArgDelimiter> {
+        self.entries.iter().map(|e| e.delimiter).collect()
+    }
+
+    /// Returns where each value comes from.
+    #[deprecated(note = "use `entries`")]
+    pub fn sources(&self) -> Vec<ValueSource> {
+        self.entries.iter().map(|e| e.source).collect()
     }
 
-    pub fn values(&self) -> &[T] {
-        &self.values
+    /// Returns where each value is written, which should be preferred over
+    /// the keys when reporting invalid values.
+    #[deprecated(note = "use `entries`")]
+    pub fn value_spans(&self) -> Vec<Span> {
+        self.entries.iter().map(|e| e.value_span).collect()
+    }
+
+    /// Returns a hash of the name, and the source and token text of each
+    /// value, which stays the same as long as the supplied tokens do, see
+    /// [`Args::fingerprint`](crate::Args::fingerprint).
+    pub fn fingerprint(&self) -> u64 {
+        let mut h = Fingerprint::default();
+        h.write_str(self.name());
+        for entry in self.entries.iter() {
+            h.write_str(&entry.source.to_string());
+            h.write_u64(entry.fingerprint);
+        }
+        h.finish()
+    }
+
+    /// Returns the suffix of each key after the prefix of its family, spanned
+    /// by the key, along with its value, e.g. `create` of `on_create` for an
+    /// argument declared with [`ArgAttrs::family`], or of `db_on_create` once
+    /// merged with the prefix `db_`. Keys without the prefix, or of arguments
+    /// not in a family, are returned as is.
+    pub fn suffixes(&self) -> Vec<(Ident, &T)> {
+        let (prefix, merged_len) = self.family.unwrap_or_default();
+        self.entries
+            .iter()
+            .map(|Entry { key, value, .. }| {
+                let s = key.to_string();
+                let rest = s.get(merged_len..).unwrap_or_default();
+                let suffix = match rest.strip_prefix(prefix).filter(|_| !prefix.is_empty()) {
+                    Some(suffix) if suffix.starts_with(|c: char| c == '_' || c.is_alphabetic()) => {
+                        Ident::new(suffix, key.span())
+                    }
+                    _ => key.clone(),
+                };
+                (suffix, value)
+            })
+            .collect()
     }
 
     pub fn add(&mut self, key: Ident, value: T) {
-        self.keys.push(key);
-        self.values.push(value);
+        self.push(Entry::new(key, value));
+    }
+
+    /// Adds a value along with where it comes from. This is the only way
+    /// values are added.
+    pub fn push(&mut self, entry: Entry<T>) {
+        self.entries.push(entry);
+    }
+
+    /// Replaces the entries with those returned by `f`, e.g. to reorder or
+    /// drop values, see [`normalize`](crate::normalize).
+    pub fn map_entries(&mut self, f: impl FnOnce(Vec<Entry<T>>) -> Vec<Entry<T>>) {
+        for entry in f(self.take_entries()) {
+            self.push(entry);
+        }
+    }
+
+    /// Returns all values along with where they come from.
+    pub fn into_entries(mut self) -> Vec<Entry<T>> {
+        self.take_entries()
+    }
+
+    fn take_entries(&mut self) -> Vec<Entry<T>> {
+        std::mem::take(&mut self.entries).into_vec()
+    }
+
+    /// Copies all values of `parent` if no value is supplied to `self`.
+    pub fn inherit_from(&mut self, parent: &Self)
+    where
+        T: Clone,
+    {
+        if self.is_empty() {
+            self.entries.extend_from_slice(&parent.entries);
+        }
+    }
+
+    /// Adds `value` under a key named after the argument if no value is
+    /// supplied, e.g. to default to a value known only by the caller.
+    pub fn default_value(&mut self, value: T) {
+        if self.is_empty() {
+            let key = Ident::new(self.name(), Span::call_site());
+            self.push(Entry {
+                source: ValueSource::Default,
+                ..Entry::new(key, value)
+            });
+        }
+    }
+
+    /// Copies all values of the sibling argument `other` if no value is
+    /// supplied, failing if `other` has no value either.
+    pub fn default_from(&mut self, other: &Self) -> syn::Result<()>
+    where
+        T: Clone,
+    {
+        self.default_from_at(other, Span::call_site())
+    }
+
+    /// Like [`default_from`](Self::default_from), but fails at `span`, usually
+    /// that of the attribute, since neither argument has a key to point at.
+    pub fn default_from_at(&mut self, other: &Self, span: Span) -> syn::Result<()>
+    where
+        T: Clone,
+    {
+        if !self.is_empty() {
+            Ok(())
+        } else if other.is_empty() {
+            Err(syn::Error::new(
+                span,
+                format!(
+                    "`{}` defaults to `{}`, which is not supplied",
+                    self.name(),
+                    other.name()
+                ),
+            ))
+        } else {
+            self.inherit_from(other);
+            Ok(())
+        }
     }
 
     pub fn clear(&mut self) {
-        self.keys.clear();
-        self.values.clear();
+        self.entries.clear();
     }
 
     pub fn take_last(mut self) -> Option<T> {
-        self.values.pop()
+        self.entries.pop().map(|e| e.value)
     }
 
-    pub fn take_one(mut self) -> T {
-        let val = self
-            .values
-            .pop()
-            .unwrap_or_else(|| panic!("too few values provided"));
-        if !self.values.is_empty() {
-            panic!("too many values provided");
+    /// Takes the only value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one value.
+    #[deprecated(note = "use `try_take_one`, which reports a wrong number of values")]
+    pub fn take_one(self) -> T {
+        if self.len() != 1 {
+            panic!(
+                "`{}` has {} values where exactly one is expected, declare it with \
+                 `#[check(required, exclusive)]` or use `try_take_one`",
+                self.name(),
+                self.len(),
+            );
         }
-        val
+        self.take_last().unwrap()
     }
 
+    /// Takes all values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value.
+    #[deprecated(note = "use `try_take_many`, which reports a missing value")]
     pub fn take_many(self) -> Vec<T> {
-        if self.values.is_empty() {
-            panic!("too few values provided");
+        if self.is_empty() {
+            panic!(
+                "`{}` has no value, declare it with `#[check(required)]` or use `try_take_many`",
+                self.name(),
+            );
         }
-        self.values
+        self.take_any()
     }
 
     pub fn take_any(self) -> Vec<T> {
-        self.values
+        self.entries
+            .into_vec()
+            .into_iter()
+            .map(|e| e.value)
+            .collect()
+    }
+
+    /// Takes the only value, or reports that it is absent or supplied more
+    /// than once.
+    pub fn try_take_one(self) -> syn::Result<T> {
+        self.try_take_one_at(Span::call_site())
+    }
+
+    /// Like [`try_take_one`](Self::try_take_one), but reports an absent value
+    /// at `span`, usually that of the attribute, since there is no key to
+    /// point at.
+    pub fn try_take_one_at(self, span: Span) -> syn::Result<T> {
+        if self.is_empty() {
+            let msg = format_args!("`{}` is required", self.name());
+            return Err(self.required_error(span, msg));
+        }
+        self.at_most_one_or_err().map(Option::unwrap)
+    }
+
+    /// Takes all values, or reports that there is none.
+    pub fn try_take_many(self) -> syn::Result<Vec<T>> {
+        self.try_take_many_at(Span::call_site())
+    }
+
+    /// Like [`try_take_many`](Self::try_take_many), but reports an absent
+    /// value at `span`.
+    pub fn try_take_many_at(self, span: Span) -> syn::Result<Vec<T>> {
+        if self.is_empty() {
+            let msg = format_args!("`{}` is required", self.name());
+            return Err(self.required_error(span, msg));
+        }
+        Ok(self.take_any())
+    }
+
+    /// Like [`try_take_one`](Self::try_take_one), but names the attribute the
+    /// argument belongs to in errors, e.g. `my_attr`.
+    pub fn exactly_one_or_err(self, namespace: &str) -> syn::Result<T> {
+        self.exactly_one_or_err_at(namespace, Span::call_site())
+    }
+
+    /// Like [`exactly_one_or_err`](Self::exactly_one_or_err), but reports an
+    /// absent value at `span`.
+    pub fn exactly_one_or_err_at(self, namespace: &str, span: Span) -> syn::Result<T> {
+        if self.is_empty() {
+            let msg = format_args!("`{}` is required in `#[{}(...)]`", self.name(), namespace);
+            return Err(self.required_error(span, msg));
+        }
+        self.at_most_one_or_err().map(Option::unwrap)
+    }
+
+    /// Takes the only value if any, or reports the keys supplied after the
+    /// first one.
+    pub fn at_most_one_or_err(mut self) -> syn::Result<Option<T>> {
+        let mut errors = self.entries[1.min(self.len())..]
+            .iter()
+            .map(|Entry { key, .. }| {
+                let msg = format_args!("`{}` has too many values (<= 1)", self.name());
+                syn::Error::new(
+                    key.span(),
+                    with_docs(ErrorCode::TooManyValues.display(msg), self.docs_url),
+                )
+            });
+        if let Some(mut first) = errors.next() {
+            first.extend(errors);
+            return Err(first);
+        }
+        Ok(self.entries.pop().map(|e| e.value))
+    }
+
+    fn required_error(&self, span: Span, msg: impl fmt::Display) -> syn::Error {
+        syn::Error::new(
+            span,
+            with_docs(ErrorCode::Required.display(msg), self.docs_url),
+        )
+    }
+}
+
+impl<T> Presence for Arg<T> {
+    fn name(&self) -> &str {
+        self.name()
+    }
+
+    fn key(&self, i: usize) -> Option<&Ident> {
+        self.entries.get(i).map(|e| &e.key)
+    }
+
+    fn occurrences(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn value_span(&self, i: usize) -> Option<Span> {
+        self.entries.get(i).map(|e| e.value_span)
+    }
+
+    fn source(&self, i: usize) -> Option<ValueSource> {
+        self.entries.get(i).map(|e| e.source)
+    }
+
+    fn position(&self, i: usize) -> Option<usize> {
+        self.entries.get(i).map(|e| e.position)
+    }
+
+    fn docs_url(&self) -> Option<&'static str> {
+        self.docs_url
+    }
+}
+
+/// Compares names, keys and values, where keys are compared by their text and
+/// spans are ignored.
+impl<T: PartialEq> PartialEq for Arg<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+            && self.len() == other.len()
+            && (self.entries.iter().zip(other.entries.iter()))
+                .all(|(a, b)| a.key == b.key && a.value == b.value)
+    }
+}
+
+impl<T: Eq> Eq for Arg<T> {}
+
+/// Emits the values as a comma-separated list, where each comma takes the
+/// span of the value before it, e.g. to forward `derive(...)`-like lists.
+///
+/// ```
+/// # use plap::{define_args, Arg};
+/// # use syn::Path;
+/// define_args! {
+///     struct DeriveArgs {
+///         #[arg(is_expr)]
+///         derive: Arg<Path>,
+///     }
+/// }
+///
+/// # use plap::Args;
+/// let args = syn::parse::Parser::parse_str(DeriveArgs::parse, "derive(Clone), derive(Debug)")?;
+/// let derive = &args.derive;
+/// assert_eq!(quote::quote!(#[derive(#derive)]).to_string(), "# [derive (Clone , Debug)]");
+/// # syn::Result::Ok(())
+/// ```
+#[cfg(feature = "printing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "printing")))]
+impl<T: quote::ToTokens> quote::ToTokens for Arg<T> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        use proc_macro2::{Punct, Spacing, TokenTree};
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                let mut comma = Punct::new(',', Spacing::Alone);
+                comma.set_span(self.entries[i - 1].value_span);
+                tokens.extend([TokenTree::from(comma)]);
+            }
+            entry.value.to_tokens(tokens);
+        }
     }
 }
 
 impl Arg<syn::LitBool> {
+    /// Returns the last supplied value as a [`Flag`], which tells an absent
+    /// flag apart from an explicit `false`.
+    pub fn to_flag(&self) -> Flag {
+        Flag {
+            value: self
+                .entries
+                .last()
+                .map(|e| (e.value.value(), e.value.span())),
+        }
+    }
+
     pub fn take_flag(self) -> bool {
         self.take_flag_or(false)
     }
 
     pub fn take_flag_or(self, default: bool) -> bool {
-        self.take_last().map(|b| b.value()).unwrap_or(default)
+        self.to_flag().value_or(default)
+    }
+}
+
+/// A tri-state flag, which is either absent, explicitly `true` or explicitly
+/// `false`.
+///
+/// Being set is the same notion of presence as used by the checks, i.e. a flag
+/// given as `flag = false` still satisfies `required` and triggers conflicts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Flag {
+    value: Option<(bool, Span)>,
+}
+
+impl Flag {
+    pub fn is_set(&self) -> bool {
+        self.value.is_some()
+    }
+
+    pub fn value(&self) -> Option<bool> {
+        self.value.map(|(b, _)| b)
+    }
+
+    pub fn value_or(&self, default: bool) -> bool {
+        self.value().unwrap_or(default)
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.value.map(|(_, s)| s)
+    }
+}
+
+/// A case which keys are renamed to by `#[rename_all(...)]`, written as in
+/// `serde`, e.g. `"camelCase"`. Other cases, such as `kebab-case`, are not
+/// supported since they would not produce identifiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+}
+
+/// Converts `name` in either `snake_case` or `PascalCase` to `case`.
+pub(crate) fn to_case(name: &str, case: Case) -> String {
+    let mut words = Vec::<String>::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            prev_lower = false;
+            words.push(String::new());
+            continue;
+        }
+        if words.is_empty() || (c.is_uppercase() && prev_lower) {
+            words.push(String::new());
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        words.last_mut().unwrap().extend(c.to_lowercase());
+    }
+    words.retain(|w| !w.is_empty());
+
+    let capitalize = |w: &String| {
+        let mut chars = w.chars();
+        match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    };
+    match case {
+        Case::Lower => words.concat(),
+        Case::Upper => words.concat().to_uppercase(),
+        Case::Snake => words.join("_"),
+        Case::ScreamingSnake => words.join("_").to_uppercase(),
+        Case::Pascal => words.iter().map(capitalize).collect(),
+        Case::Camel => {
+            let mut s = words.first().cloned().unwrap_or_default();
+            s.extend(words.iter().skip(1).map(capitalize));
+            s
+        }
     }
 }
@@ -1,65 +1,390 @@
-use std::fmt;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::rc::Rc;
 
 use proc_macro2::{Ident, Span};
 
-use crate::errors::Errors;
+use crate::arg::{Keys, Presence, ValueSource};
+use crate::code::{with_docs, ErrorCode, ReportTarget};
+use crate::constraints::{self as rules, FmtWith};
+use crate::errors::{ErrorReport, Errors};
+use crate::fingerprint::{Fingerprint, HashWriter};
 
+/// An argument which can be checked, i.e. any [`Presence`].
 #[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
-pub trait AnyArg {
-    fn name(&self) -> &str;
-
-    fn keys(&self) -> &[Ident];
-}
-
-impl<T> AnyArg for crate::arg::Arg<T> {
-    fn name(&self) -> &str {
-        self.name()
-    }
-
-    fn keys(&self) -> &[Ident] {
-        self.keys()
-    }
-}
-
+pub trait AnyArg: Presence {}
+
+impl<T: Presence + ?Sized> AnyArg for T {}
+
+/// Runs checks on arguments, collecting findings until
+/// [`finish`](Self::finish).
+///
+/// Findings are reported in a canonical order, regardless of how checks are
+/// called: those on supplied arguments follow the order of the input, i.e.
+/// attribute order, while the rest, such as missing arguments, follow them in
+/// the order of checks, i.e. schema order, which also breaks ties on the same
+/// argument. Any deviation from this order is a bug.
+///
+/// A checker can be reused across items, e.g. all attributes of a struct, by
+/// calling [`finish`](Self::finish) after each one, which keeps its
+/// allocations and settings; [`reset`](Self::reset) discards the settings as
+/// well. This is how `plap-macros` checks each attribute site of a derive
+/// input, while [`Args::finish`] takes checkers from a [pool](Self::pooled)
+/// of the current thread, which resets them once done.
+///
+/// [`Args::finish`]: crate::Args::finish
 #[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
 #[derive(Default)]
 pub struct Checker {
     errors: Errors,
+    // findings since the last flush, along with their positions in the input
+    // and the arguments they pertain to
+    pending: Vec<(usize, bool, Option<String>, syn::Error)>,
     spans: Vec<Span>,
+    prefix: String,
+    // findings reported on keys, identified by their codes and messages
+    reported: HashSet<(KeyId, ErrorCode, Rc<str>)>,
+    // messages formatted since the last finish, shared by the keys they are
+    // reported on, see `Checker::message_at_key`
+    messages: HashMap<(ErrorCode, u64), Rc<str>>,
+    warning: bool,
+    // whether hard rules are recorded, only to validate a schema
+    recording: bool,
+    rules: Rules,
+    // where findings are reported for each code, on keys by default
+    targets: Vec<(ErrorCode, ReportTarget)>,
+    // whether codes are prefixed to messages, as set on the thread by default
+    codes: Option<bool>,
+    // named groups, along with the addresses of their members
+    groups: Vec<(&'static str, Vec<usize>)>,
+    // pairs of conflicting keys reported, in either order
+    conflicts: HashSet<(KeyId, KeyId)>,
+}
+
+thread_local! {
+    static POOL: RefCell<Vec<Checker>> = RefCell::new(Vec::new());
 }
 
 impl Checker {
+    /// Runs `f` with a checker taken from a pool of the current thread, or a
+    /// new one if the pool is empty, which is [reset](Self::reset) and put
+    /// back once `f` returns, so that its allocations are reused.
+    pub fn pooled<T>(f: impl FnOnce(&mut Self) -> T) -> T {
+        let mut checker = POOL
+            .with(|pool| pool.borrow_mut().pop())
+            .unwrap_or_default();
+        let output = f(&mut checker);
+        checker.reset();
+        POOL.with(|pool| pool.borrow_mut().push(checker));
+        output
+    }
+
+    /// Discards all findings, sources and settings, such as the
+    /// [error limit](Self::with_error_limit), keeping the capacity of
+    /// internal buffers.
+    pub fn reset(&mut self) {
+        self.errors.clear();
+        self.errors.set_limit(None);
+        self.pending.clear();
+        self.spans.clear();
+        self.prefix.clear();
+        self.reported.clear();
+        self.messages.clear();
+        self.warning = false;
+        self.rules.clear();
+        self.targets.clear();
+        self.codes = None;
+        self.groups.clear();
+        self.conflicts.clear();
+    }
+
     pub fn with_result(&mut self, res: syn::Result<()>) -> &mut Self {
-        self.errors.add_result(res);
+        if let Err(e) = res {
+            self.with_error(e);
+        }
         self
     }
 
     pub fn with_error(&mut self, err: syn::Error) -> &mut Self {
-        self.errors.add(err);
+        self.with_error_at_position(usize::MAX, err)
+    }
+
+    fn with_error_at_position(&mut self, position: usize, err: syn::Error) -> &mut Self {
+        self.with_error_on(position, None, err)
+    }
+
+    fn with_error_on(
+        &mut self,
+        position: usize,
+        arg: Option<String>,
+        err: syn::Error,
+    ) -> &mut Self {
+        self.pending.push((position, self.warning, arg, err));
         self
     }
 
+    // moves findings into `errors` in the canonical order
+    fn flush(&mut self) {
+        // the sort is stable, which keeps the order of checks on ties
+        self.pending.sort_by_key(|(position, ..)| *position);
+        for (_, warning, arg, err) in self.pending.drain(..) {
+            match arg {
+                _ if warning => self.errors.add_warning(err),
+                Some(arg) => self.errors.add_for(&arg, err),
+                None => self.errors.add(err),
+            }
+        }
+    }
+
     pub fn with_error_at(&mut self, span: Span, msg: impl fmt::Display) -> &mut Self {
-        self.errors.add_at(span, msg);
+        self.with_error(syn::Error::new(span, msg))
+    }
+
+    /// Runs the checks in `f` as soft rules, whose findings are collected as
+    /// warnings and do not fail [`finish`](Self::finish).
+    pub fn warn(&mut self, f: impl FnOnce(&mut Self)) -> &mut Self {
+        let warning = std::mem::replace(&mut self.warning, true);
+        f(self);
+        self.warning = warning;
+        self
+    }
+
+    /// Reports at most `limit` errors in detail on each
+    /// [`finish`](Self::finish), followed by a summary like `and 3 more
+    /// errors`.
+    pub fn with_error_limit(&mut self, limit: usize) -> &mut Self {
+        self.errors.set_limit(Some(limit));
+        self
+    }
+
+    /// Reports findings with `code` on supplied arguments at `target`, which
+    /// is [`ReportTarget::Key`] by default.
+    pub fn with_report_target(&mut self, code: ErrorCode, target: ReportTarget) -> &mut Self {
+        self.targets.retain(|(c, _)| *c != code);
+        self.targets.push((code, target));
+        self
+    }
+
+    /// Returns where findings with `code` are reported, see
+    /// [`with_report_target`](Self::with_report_target).
+    pub fn report_target(&self, code: ErrorCode) -> ReportTarget {
+        self.targets
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map_or(ReportTarget::Key, |(_, t)| *t)
+    }
+
+    /// Sets whether codes are prefixed to the messages of findings, which
+    /// otherwise follows [`ErrorCode::is_shown`].
+    pub fn with_codes(&mut self, yes: bool) -> &mut Self {
+        self.codes = Some(yes);
+        self
+    }
+
+    /// Formats `msg` for `code`, prefixed with the code if
+    /// [shown](Self::with_codes).
+    fn message(&self, code: ErrorCode, msg: impl fmt::Display) -> String {
+        let shown = self.codes.unwrap_or_else(ErrorCode::is_shown);
+        code.display_if(shown, msg).to_string()
+    }
+
+    /// Takes the warnings collected so far.
+    pub fn take_warnings(&mut self) -> Vec<syn::Error> {
+        self.flush();
+        self.errors.take_warnings()
+    }
+
+    /// Reports an error on an occurrence of an argument, unless the same
+    /// message has been reported on it since the last
+    /// [`finish`](Self::finish), e.g. when a conflict is declared by both a
+    /// group and one of its members.
+    ///
+    /// The error is placed on the key, the value or both, as configured by
+    /// [`with_report_target`](Self::with_report_target). Messages on values
+    /// not supplied by the user tell where they come from instead, e.g. an
+    /// environment variable.
+    ///
+    /// The error is attributed to `arg` if given, e.g. the group of a rule,
+    /// or the argument of the key otherwise. `args` are what `msg` says
+    /// besides the key, e.g. the name of another argument, which identify the
+    /// message, see [`message_at_key`](Self::message_at_key).
+    fn with_error_at_key(
+        &mut self,
+        occurrence: Occurrence,
+        arg: Option<&str>,
+        code: ErrorCode,
+        args: &[&dyn fmt::Display],
+        msg: impl fmt::Display,
+    ) -> &mut Self {
+        let (key, value, position, ..) = occurrence;
+        let msg = self.message_at_key(occurrence, code, args, msg);
+        let finding = (self.key_id(occurrence), code, msg);
+        if !self.reported.contains(&finding) {
+            let (_, _, msg) = &finding;
+            let target = self.report_target(code);
+            let arg = Some(match arg {
+                Some(arg) => arg.to_owned(),
+                None => self.full_name_of(occurrence),
+            });
+            if target != ReportTarget::Value {
+                let err = syn::Error::new(key.span(), msg);
+                self.with_error_on(position, arg.clone(), err);
+            }
+            if target != ReportTarget::Key {
+                self.with_error_on(position, arg, syn::Error::new(value, msg));
+            }
+            self.reported.insert(finding);
+        }
         self
     }
 
+    /// Formats `msg` for `code` on an occurrence, or returns the message
+    /// formatted earlier since the last [`finish`](Self::finish) for the same
+    /// code, key and `args`, so that a finding attached to many keys, e.g.
+    /// repeated occurrences of an argument, is formatted only once.
+    ///
+    /// Messages are identified by the fingerprints of what they say, like
+    /// values are [deduplicated](crate::Arg::dedup), rather than by the
+    /// messages themselves, which would have to be formatted to be looked up.
+    fn message_at_key(
+        &mut self,
+        occurrence: Occurrence,
+        code: ErrorCode,
+        args: &[&dyn fmt::Display],
+        msg: impl fmt::Display,
+    ) -> Rc<str> {
+        let (key, _, _, docs_url, source, ..) = occurrence;
+        let shown = self.codes.unwrap_or_else(ErrorCode::is_shown);
+        let mut h = HashWriter(Fingerprint::default());
+        h.0.write(&[shown as u8]);
+        write!(h, "{}", key).unwrap();
+        h.0.write(&[0xff]);
+        for arg in args {
+            write!(h, "{}", arg).unwrap();
+            h.0.write(&[0xff]);
+        }
+        h.0.write_str(docs_url.unwrap_or_default());
+        write!(h, "{}", source).unwrap();
+        let id = (code, h.0.finish());
+        if let Some(msg) = self.messages.get(&id) {
+            return msg.clone();
+        }
+
+        let msg = FmtWith(|f| match source {
+            ValueSource::Supplied => write!(f, "{}", msg),
+            _ => write!(f, "{} (`{}` is set {})", msg, key, source),
+        });
+        let msg = Rc::<str>::from(self.message(code, with_docs(msg, docs_url)));
+        self.messages.insert(id, msg.clone());
+        msg
+    }
+
+    /// Identifies an occurrence by the fingerprint of the full name of its
+    /// argument and its index, which stay the same while arguments are
+    /// checked, unlike addresses of keys when checkers are
+    /// [pooled](Self::pooled).
+    fn key_id(&self, (.., name, index): Occurrence) -> KeyId {
+        let mut h = Fingerprint::default();
+        h.write(self.prefix.as_bytes());
+        h.write_str(name);
+        (h.finish(), index)
+    }
+
+    fn full_name_of(&self, (.., name, _): Occurrence) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
     pub fn with_source(&mut self, span: Span) -> &mut Self {
         self.spans.push(span);
         self
     }
 
-    pub fn with_error_at_source(&mut self, msg: impl fmt::Display + Clone) -> &mut Self {
+    /// Reports an error at each [source](Self::with_source), or the call site
+    /// if there is none, formatting `msg` only once for all of them.
+    pub fn with_error_at_source(&mut self, msg: impl fmt::Display) -> &mut Self {
+        self.with_error_at_source_on(None, msg.to_string())
+    }
+
+    fn with_error_at_source_on(&mut self, arg: Option<String>, msg: String) -> &mut Self {
         if self.spans.is_empty() {
-            self.errors.add_at(Span::call_site(), msg);
+            self.with_error_on(usize::MAX, arg, syn::Error::new(Span::call_site(), msg));
         } else {
-            for &span in self.spans.iter() {
-                self.errors.add_at(span, msg.clone());
+            for i in 0..self.spans.len() {
+                let err = syn::Error::new(self.spans[i], &msg);
+                self.with_error_on(usize::MAX, arg.clone(), err);
             }
         }
         self
     }
 
+    /// Runs the checks of `args`, which were parsed with
+    /// [`Parser::parse_next_prefixed`](crate::Parser::parse_next_prefixed),
+    /// reporting their names with `prefix` prepended.
+    pub fn check_prefixed<A>(&mut self, prefix: &str, args: &A) -> &mut Self
+    where
+        A: crate::define_args::Args,
+    {
+        let len = self.prefix.len();
+        self.prefix.push_str(prefix);
+        args.check(self);
+        self.prefix.truncate(len);
+        self
+    }
+
+    /// Runs the checks of `args` to find those which can never be satisfied
+    /// together, see [`Args::validate_schema`](crate::Args::validate_schema).
+    pub(crate) fn validate_rules<A>(args: &A) -> Result<(), String>
+    where
+        A: crate::define_args::Args,
+    {
+        let mut checker = Self {
+            recording: true,
+            ..Self::default()
+        };
+        args.check(&mut checker);
+        checker
+            .rules
+            .validate()
+            .map_err(|msg| format!("unsatisfiable checks: {}", msg))
+    }
+
+    // soft rules never make a schema unsatisfiable
+    fn records_rules(&self) -> bool {
+        self.recording && !self.warning
+    }
+
+    fn full_name(&self, a: &dyn AnyArg) -> String {
+        format!("{}{}", self.prefix, a.name())
+    }
+
+    /// Names a group, so that findings of rules on it, such as
+    /// [`exclusive_group`](Self::exclusive_group), can cite the group by name
+    /// until [`finish`](Self::finish).
+    pub fn name_group<'a>(
+        &mut self,
+        name: &'static str,
+        args: impl AsRef<[&'a dyn AnyArg]>,
+    ) -> &mut Self {
+        let members = args.as_ref().iter().map(|a| address(*a)).collect();
+        self.groups.push((name, members));
+        self
+    }
+
+    fn named_group(&self, args: &[&dyn AnyArg]) -> Option<&'static str> {
+        let named = self.groups.iter().rev().find(|(_, members)| {
+            members.len() == args.len() && members.iter().zip(args).all(|(m, a)| *m == address(*a))
+        });
+        named.map(|(name, _)| *name)
+    }
+
+    fn record_required_any(&mut self, args: &[&dyn AnyArg]) {
+        if self.records_rules() {
+            let group = args.iter().map(|a| self.full_name(*a)).collect();
+            self.rules.required_any.push(group);
+        }
+    }
+
     /* ---------------------- *
      * container level checks *
      * ---------------------- */
@@ -80,9 +405,8 @@ impl Checker {
     }
 
     fn _required_any(&mut self, args: &[&dyn AnyArg]) -> &mut Self {
-        if count_group(args) == 0 {
-            self.with_error_at_source(format!("`{}` is required", fmt_group(args)));
-        }
+        self.record_required_any(args);
+        rules::required_any(self, args);
         self
     }
 
@@ -91,22 +415,56 @@ impl Checker {
     }
 
     fn _exclusive_group(&mut self, args: &[&dyn AnyArg]) -> &mut Self {
-        for (&a, &b) in combination(args) {
-            self.conflicts_with(a, b);
+        // conflicts are recorded even if none is present
+        for (&a, &b) in rules::combination(args) {
+            self.record_conflict(a, b);
         }
+        rules::exclusive_group(self, args);
         self
     }
 
     pub fn exclusive_aliases<'a>(&mut self, args: impl AsRef<[&'a dyn AnyArg]>) -> &mut Self {
-        self._exclusive_aliases(args.as_ref())
+        rules::exclusive_aliases(self, args.as_ref());
+        self
     }
 
-    fn _exclusive_aliases(&mut self, args: &[&dyn AnyArg]) -> &mut Self {
-        if count_group(args) > 1 {
-            for &arg in args {
-                self._too_many_values(arg);
-            }
+    /// Allows at most `max` members of the group to be present, reporting the
+    /// surplus members.
+    pub fn max_members<'a>(&mut self, args: impl AsRef<[&'a dyn AnyArg]>, max: usize) -> &mut Self {
+        rules::max_members(self, args.as_ref(), max);
+        self
+    }
+
+    /// Requires at least `min` members of the group to be present.
+    pub fn min_members<'a>(&mut self, args: impl AsRef<[&'a dyn AnyArg]>, min: usize) -> &mut Self {
+        self._min_members(args.as_ref(), min)
+    }
+
+    fn _min_members(&mut self, args: &[&dyn AnyArg], min: usize) -> &mut Self {
+        if min > 0 {
+            self.record_required_any(args);
         }
+        rules::min_members(self, args, min);
+        self
+    }
+
+    /// Allows between `min` and `max` members of the group to be present, e.g.
+    /// to pick one or two of several options. Missing members are reported on
+    /// the source, and surplus members on their keys.
+    pub fn members_between<'a>(
+        &mut self,
+        args: impl AsRef<[&'a dyn AnyArg]>,
+        min: usize,
+        max: usize,
+    ) -> &mut Self {
+        self._members_between(args.as_ref(), min, max)
+    }
+
+    fn _members_between(&mut self, args: &[&dyn AnyArg], min: usize, max: usize) -> &mut Self {
+        if min > 0 {
+            self.record_required_any(args);
+        }
+        rules::members_between(self, args, min, max);
         self
     }
 
@@ -126,32 +484,18 @@ impl Checker {
      * ------------------ */
 
     pub fn required(&mut self, arg: &dyn AnyArg) -> &mut Self {
-        if arg.keys().is_empty() {
-            self.with_error_at_source(format!("`{}` is required", arg.name()));
-        }
+        self.record_required_any(&[arg]);
+        rules::required(self, arg);
         self
     }
 
     pub fn exclusive(&mut self, a: &dyn AnyArg) -> &mut Self {
-        if a.keys().len() > 1 {
-            self._too_many_values(a);
-        }
+        rules::exclusive(self, a);
         self
     }
 
-    fn _too_many_values(&mut self, a: &dyn AnyArg) {
-        for a in a.keys() {
-            self.with_error_at(a.span(), format!("`{}` has too many values (<= 1)", a));
-        }
-    }
-
     pub fn requires(&mut self, a: &dyn AnyArg, b: &dyn AnyArg) -> &mut Self {
-        if b.keys().is_empty() {
-            let b_name = b.name();
-            for a in a.keys() {
-                self.with_error_at(a.span(), format!("`{}` requires `{}`", a, b_name));
-            }
-        }
+        rules::requires(self, a, b);
         self
     }
 
@@ -175,28 +519,21 @@ impl Checker {
         a: &dyn AnyArg,
         b: impl AsRef<[&'b dyn AnyArg]>,
     ) -> &mut Self {
-        self._requires_any(a, b.as_ref())
+        rules::requires_any(self, a, b.as_ref());
+        self
     }
 
-    fn _requires_any(&mut self, a: &dyn AnyArg, args: &[&dyn AnyArg]) -> &mut Self {
-        if count_group(args) == 0 {
-            for a in a.keys() {
-                self.with_error_at(a.span(), format!("`{}` requires `{}`", a, fmt_group(args)));
-            }
-        }
+    pub fn conflicts_with(&mut self, a: &dyn AnyArg, b: &dyn AnyArg) -> &mut Self {
+        self.record_conflict(a, b);
+        rules::conflicts_with(self, a, b, None);
         self
     }
 
-    pub fn conflicts_with(&mut self, a: &dyn AnyArg, b: &dyn AnyArg) -> &mut Self {
-        let b_keys = b.keys();
-        for a in a.keys() {
-            for b in b_keys {
-                // conflicts are always bidirectional
-                self.with_error_at(a.span(), format!("`{}` conflicts with `{}`", a, b));
-                self.with_error_at(b.span(), format!("`{}` conflicts with `{}`", b, a));
-            }
+    fn record_conflict(&mut self, a: &dyn AnyArg, b: &dyn AnyArg) {
+        if self.records_rules() {
+            let pair = (self.full_name(a), self.full_name(b));
+            self.rules.conflicts.push(pair);
         }
-        self
     }
 
     pub fn conflicts_with_each<'b>(
@@ -214,53 +551,233 @@ impl Checker {
         self
     }
 
+    /// Reports `a` only if all of `b` are present as well, unlike
+    /// [`conflicts_with_each`](Self::conflicts_with_each) which reports `a`
+    /// if any of them is present.
+    pub fn conflicts_with_all<'b>(
+        &mut self,
+        a: &dyn AnyArg,
+        b: impl AsRef<[&'b dyn AnyArg]>,
+    ) -> &mut Self {
+        rules::conflicts_with_all(self, a, b.as_ref());
+        self
+    }
+
     pub fn blocked(&mut self, a: &dyn AnyArg) -> &mut Self {
-        for a in a.keys() {
-            self.with_error_at(a.span(), format!("`{}` is not allowed in this context", a));
+        if self.records_rules() {
+            let name = self.full_name(a);
+            self.rules.blocked.push(name);
         }
+        rules::blocked(self, a);
         self
     }
 
+    /// Finishes the checks performed since the last call.
+    ///
+    /// Unlike [`Args::finish`](crate::Args::finish), which consumes the
+    /// arguments it validates, a checker is kept to be reused for the next
+    /// run, e.g. of another attribute.
     pub fn finish(&mut self) -> syn::Result<()> {
+        self.finish_report().into_result()
+    }
+
+    /// Like [`finish`](Self::finish), but returns the errors partitioned by
+    /// the arguments they pertain to.
+    pub fn finish_report(&mut self) -> ErrorReport {
+        self.take_report()
+    }
+
+    pub(crate) fn take_report(&mut self) -> ErrorReport {
         self.spans.clear();
-        self.errors.fail()
+        self.reported.clear();
+        self.messages.clear();
+        self.groups.clear();
+        self.conflicts.clear();
+        self.flush();
+        self.errors.report()
     }
 }
 
-fn count_group(args: &[&dyn AnyArg]) -> usize {
-    args.iter().map(|a| a.keys().len()).sum()
+/// Evaluates the shared [rules](rules) on arguments, reporting findings on
+/// their keys, or the sources for missing arguments.
+impl<'a> rules::Subject<'a> for Checker {
+    type Arg = &'a dyn AnyArg;
+    type Key = &'a Ident;
+    type Occurrence = Occurrence<'a>;
+    type Occurrences = Occurrences<'a>;
+
+    fn count(&self, a: Self::Arg) -> usize {
+        a.occurrences()
+    }
+
+    fn occurrences(&self, a: Self::Arg) -> Occurrences<'a> {
+        Occurrences {
+            arg: a,
+            keys: Keys::new(a).enumerate(),
+        }
+    }
+
+    fn key(o: Occurrence<'a>) -> &'a Ident {
+        o.0
+    }
+
+    fn write_name(&self, a: Self::Arg, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, a.name())
+    }
+
+    fn docs_url(&self, a: Self::Arg) -> Option<&'static str> {
+        a.docs_url()
+    }
+
+    /// Returns the name of a group if it is [named](Self::name_group), or
+    /// `fast | safe` otherwise.
+    fn group_name(&self, args: &[Self::Arg]) -> String {
+        match self.named_group(args) {
+            Some(name) => format!("{}{}", self.prefix, name),
+            None => fmt_group(&self.prefix, args).to_string(),
+        }
+    }
+
+    /// Describes a group as ``group `modes` `` if it is
+    /// [named](Self::name_group), or `` `fast | safe` `` otherwise.
+    fn describe_group(&self, args: &[Self::Arg]) -> String {
+        match self.named_group(args) {
+            Some(name) => format!("group `{}`", name),
+            None => format!("`{}`", fmt_group(&self.prefix, args)),
+        }
+    }
+
+    fn report(&mut self, name: &str, code: ErrorCode, msg: &dyn fmt::Display) {
+        let msg = self.message(code, msg);
+        self.with_error_at_source_on(Some(name.to_owned()), msg);
+    }
+
+    fn report_at(
+        &mut self,
+        o: Occurrence<'a>,
+        group: Option<&str>,
+        code: ErrorCode,
+        args: &[&dyn fmt::Display],
+        msg: &dyn fmt::Display,
+    ) {
+        self.with_error_at_key(o, group, code, args, msg);
+    }
+
+    fn first_conflict(&mut self, a: Occurrence<'a>, b: Occurrence<'a>) -> bool {
+        let (a, b) = (self.key_id(a), self.key_id(b));
+        self.conflicts.insert((a.min(b), a.max(b)))
+    }
 }
 
-fn combination<T>(arr: &[T]) -> impl '_ + Iterator<Item = (&'_ T, &'_ T)> {
-    arr.iter()
-        .enumerate()
-        .flat_map(|(k, t1)| arr[(k + 1)..].iter().map(move |t2| (t1, t2)))
+/// Hard rules recorded to detect unsatisfiable schemas, see
+/// [`Checker::validate_rules`].
+#[derive(Default)]
+struct Rules {
+    // groups of which at least one member must be present
+    required_any: Vec<Vec<String>>,
+    conflicts: Vec<(String, String)>,
+    blocked: Vec<String>,
 }
 
-fn fmt_group<'a>(args: &'a [&dyn AnyArg]) -> impl 'a + fmt::Display {
-    FmtWith(|f| {
-        use fmt::Display;
-        let mut iter = args.iter();
-        if let Some(first) = iter.next() {
-            first.name().fmt(f)?;
+impl Rules {
+    fn clear(&mut self) {
+        self.required_any.clear();
+        self.conflicts.clear();
+        self.blocked.clear();
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut required = Vec::<&str>::new();
+        for group in self.required_any.iter() {
+            if let [a] = group.as_slice() {
+                if !required.contains(&a.as_str()) {
+                    required.push(a);
+                }
+            }
+        }
+        let conflicts = |a: &str, b: &str| {
+            self.conflicts
+                .iter()
+                .any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+        };
+
+        for &a in required.iter() {
+            if self.blocked.iter().any(|b| b == a) {
+                return Err(format!("`{}` is both required and blocked", a));
+            }
         }
-        for a in iter {
-            f.write_str(" | ")?;
-            a.name().fmt(f)?;
+        for (&a, &b) in rules::combination(&required) {
+            if conflicts(a, b) {
+                return Err(format!(
+                    "`{}` and `{}` are both required but conflict with each other",
+                    a, b
+                ));
+            }
+        }
+        for group in self.required_any.iter().filter(|g| g.len() > 1) {
+            let unavailable =
+                |m: &String| self.blocked.contains(m) || required.iter().any(|&r| conflicts(r, m));
+            if group.iter().all(unavailable) {
+                return Err(format!(
+                    "`{}` is required but each of its members is blocked or conflicts with a \
+                     required argument",
+                    group.join(" | ")
+                ));
+            }
         }
         Ok(())
-    })
+    }
 }
 
-struct FmtWith<F>(pub F)
-where
-    F: Fn(&mut fmt::Formatter) -> fmt::Result;
+/// Returns the key, value span, position, docs, source, the name of the
+/// argument and the index of each occurrence of `a`.
+type Occurrence<'a> = (
+    &'a Ident,
+    Span,
+    usize,
+    Option<&'static str>,
+    ValueSource,
+    &'a str,
+    usize,
+);
+
+/// An occurrence of an argument, see [`Checker::key_id`].
+type KeyId = (u64, usize);
+
+/// The occurrences of an argument.
+pub(crate) struct Occurrences<'a> {
+    arg: &'a dyn AnyArg,
+    keys: std::iter::Enumerate<Keys<'a, dyn AnyArg + 'a>>,
+}
 
-impl<F> fmt::Display for FmtWith<F>
-where
-    F: Fn(&mut fmt::Formatter) -> fmt::Result,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        (self.0)(f)
+impl<'a> Iterator for Occurrences<'a> {
+    type Item = Occurrence<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, k) = self.keys.next()?;
+        let a = self.arg;
+        let span = a.value_span(i).unwrap_or_else(|| k.span());
+        Some((
+            k,
+            span,
+            a.position(i).unwrap_or(usize::MAX),
+            a.docs_url(),
+            a.source(i).unwrap_or(ValueSource::Supplied),
+            a.name(),
+            i,
+        ))
     }
 }
+
+/// Identifies an argument by its address, which is only stable while it is
+/// borrowed.
+fn address(a: &dyn AnyArg) -> usize {
+    a as *const dyn AnyArg as *const () as usize
+}
+
+fn fmt_group<'a>(prefix: &'a str, args: &'a [&dyn AnyArg]) -> impl 'a + fmt::Display {
+    rules::fmt_group(
+        args.iter()
+            .map(move |a| FmtWith(move |f| write!(f, "{}{}", prefix, a.name()))),
+    )
+}
@@ -0,0 +1,228 @@
+use std::cell::Cell;
+use std::fmt;
+
+thread_local! {
+    static SHOW_CODES: Cell<bool> = Cell::new(false);
+    static SHOW_DOCS: Cell<bool> = Cell::new(true);
+}
+
+/// A stable code identifying the kind of a diagnostic.
+///
+/// Codes are prefixed to messages, e.g. `[PLAP002] `x` is required`, once
+/// [shown](Self::show), after which they can be recovered from errors with
+/// [`ErrorCode::of`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorCode {
+    /// An argument is supplied more times than allowed.
+    TooManyValues,
+    /// A required argument is absent.
+    Required,
+    /// An argument is supplied without another one it requires.
+    Requires,
+    /// Two conflicting arguments are supplied together.
+    Conflict,
+    /// An argument is not allowed in this context.
+    NotAllowed,
+    /// More members of a group are supplied than allowed.
+    TooManyMembers,
+    /// A key names no known argument.
+    UnknownArgument,
+    /// A key is not followed by a value.
+    MissingValue,
+    /// A value is delimited in a disallowed style.
+    UnexpectedDelimiter,
+    /// A literal is out of range or has a wrong suffix.
+    InvalidLiteral,
+    /// An attribute is placed on a disallowed item.
+    InvalidTarget,
+    /// An unstable argument is supplied without opting in.
+    Unstable,
+    /// A value is rejected by a [validator](crate::ArgAttrs::validate).
+    InvalidValue,
+    /// A value is nested deeper than the parser allows.
+    TooDeep,
+    /// An argument is supplied, but the macro crate is built without the
+    /// feature it requires.
+    FeatureDisabled,
+}
+
+/// Where a finding on a supplied argument is reported, see
+/// [`Checker::with_report_target`].
+///
+/// [`Checker::with_report_target`]: crate::Checker::with_report_target
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReportTarget {
+    /// On the key, e.g. `a` in `a = 1`.
+    Key,
+    /// On the value, e.g. `1` in `a = 1`, or the key if the value is implied.
+    Value,
+    /// On both the key and the value, with the same message.
+    Both,
+}
+
+const CODES: &[(ErrorCode, &str)] = &[
+    (ErrorCode::TooManyValues, "PLAP001"),
+    (ErrorCode::Required, "PLAP002"),
+    (ErrorCode::Requires, "PLAP003"),
+    (ErrorCode::Conflict, "PLAP004"),
+    (ErrorCode::NotAllowed, "PLAP005"),
+    (ErrorCode::TooManyMembers, "PLAP006"),
+    (ErrorCode::UnknownArgument, "PLAP007"),
+    (ErrorCode::MissingValue, "PLAP008"),
+    (ErrorCode::UnexpectedDelimiter, "PLAP009"),
+    (ErrorCode::InvalidLiteral, "PLAP010"),
+    (ErrorCode::InvalidTarget, "PLAP011"),
+    (ErrorCode::Unstable, "PLAP012"),
+    (ErrorCode::InvalidValue, "PLAP013"),
+    (ErrorCode::TooDeep, "PLAP014"),
+    (ErrorCode::FeatureDisabled, "PLAP015"),
+];
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        CODES.iter().find(|(c, _)| *c == self).unwrap().1
+    }
+
+    /// Sets whether codes are prefixed to messages reported on the current
+    /// thread, which is off by default, until the returned guard is dropped.
+    ///
+    /// This is the default of checkers and parsers, which can set their own
+    /// with [`Checker::with_codes`] and [`Parser::with_codes`].
+    ///
+    /// ```
+    /// # use plap::ErrorCode;
+    /// {
+    ///     let _codes = ErrorCode::show(true);
+    ///     assert!(ErrorCode::is_shown());
+    /// }
+    /// assert!(!ErrorCode::is_shown());
+    /// ```
+    ///
+    /// [`Checker::with_codes`]: crate::Checker::with_codes
+    /// [`Parser::with_codes`]: crate::Parser::with_codes
+    pub fn show(yes: bool) -> CodesShown {
+        CodesShown::new(yes)
+    }
+
+    /// Returns whether codes are [shown](Self::show) on the current thread.
+    pub fn is_shown() -> bool {
+        SHOW_CODES.with(Cell::get)
+    }
+
+    /// Sets whether links declared by [`ArgAttrs::docs_url`] are appended to
+    /// messages reported on the current thread, which is on by default.
+    ///
+    /// [`ArgAttrs::docs_url`]: crate::ArgAttrs::docs_url
+    pub fn show_docs(yes: bool) {
+        SHOW_DOCS.with(|c| c.set(yes));
+    }
+
+    /// Returns whether links to docs are [shown](Self::show_docs) on the
+    /// current thread.
+    pub fn are_docs_shown() -> bool {
+        SHOW_DOCS.with(Cell::get)
+    }
+
+    /// Returns `msg`, prefixed with this code if codes are
+    /// [shown](Self::show).
+    pub fn message(self, msg: impl fmt::Display) -> String {
+        self.display(msg).to_string()
+    }
+
+    /// Like [`message`](Self::message), but formats lazily.
+    pub(crate) fn display<M: fmt::Display>(self, msg: M) -> impl fmt::Display {
+        self.display_if(Self::is_shown(), msg)
+    }
+
+    /// Like [`display`](Self::display), but prefixes the code only if
+    /// `shown`, regardless of the setting of the current thread.
+    pub(crate) fn display_if<M: fmt::Display>(self, shown: bool, msg: M) -> impl fmt::Display {
+        Prefixed {
+            code: Some(self).filter(|_| shown),
+            msg,
+        }
+    }
+
+    /// Returns the code prefixed to the message of `err`.
+    pub fn of(err: &syn::Error) -> Option<Self> {
+        let msg = err.to_string();
+        let code = msg.strip_prefix('[')?.split(']').next()?;
+        CODES.iter().find(|(_, s)| *s == code).map(|(c, _)| *c)
+    }
+}
+
+struct Prefixed<M> {
+    code: Option<ErrorCode>,
+    msg: M,
+}
+
+impl<M: fmt::Display> fmt::Display for Prefixed<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(code) = self.code {
+            write!(f, "[{}] ", code)?;
+        }
+        self.msg.fmt(f)
+    }
+}
+
+/// Appends a link to the docs of an argument to `msg`, if any is declared and
+/// [shown](ErrorCode::show_docs).
+pub(crate) fn with_docs<M: fmt::Display>(msg: M, url: Option<&'static str>) -> impl fmt::Display {
+    WithDocs {
+        msg,
+        url: url.filter(|_| ErrorCode::are_docs_shown()),
+    }
+}
+
+/// Like [`with_docs`], but for all messages of `err`.
+pub(crate) fn error_with_docs(err: syn::Error, url: Option<&'static str>) -> syn::Error {
+    let url = match url.filter(|_| ErrorCode::are_docs_shown()) {
+        Some(url) => url,
+        None => return err,
+    };
+    let mut errors = err
+        .into_iter()
+        .map(|e| syn::Error::new(e.span(), with_docs(e, Some(url))));
+    let mut first = errors.next().unwrap();
+    first.extend(errors);
+    first
+}
+
+struct WithDocs<M> {
+    msg: M,
+    url: Option<&'static str>,
+}
+
+impl<M: fmt::Display> fmt::Display for WithDocs<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.msg.fmt(f)?;
+        if let Some(url) = self.url {
+            write!(f, "\n\nsee {} for details", url)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps codes [shown](ErrorCode::show) or hidden on the current thread as
+/// long as it lives, after which the previous setting is restored.
+#[must_use = "codes are shown only until the guard is dropped"]
+#[derive(Debug)]
+pub struct CodesShown(bool);
+
+impl CodesShown {
+    fn new(yes: bool) -> Self {
+        CodesShown(SHOW_CODES.with(|c| c.replace(yes)))
+    }
+}
+
+impl Drop for CodesShown {
+    fn drop(&mut self) {
+        SHOW_CODES.with(|c| c.set(self.0));
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
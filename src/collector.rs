@@ -0,0 +1,116 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use syn::parse::ParseStream;
+
+use crate::define_args::ArgEnum;
+use crate::parser::Parser;
+
+/// Accumulates the values of an [`ArgEnum`] parsed from several attribute
+/// bodies, and checks them as a whole once all are parsed.
+///
+/// ```
+/// # use plap::{define_args, Collector};
+/// # use syn::{LitBool, LitInt};
+/// define_args! {
+///     #[check(requires(pretty, indent))]
+///     enum Style {
+///         #[arg(is_flag)]
+///         pretty(LitBool),
+///         #[arg(is_expr)]
+///         indent(LitInt),
+///     }
+/// }
+///
+/// let mut collector = Collector::<Style>::new();
+/// collector.parse_tokens("pretty".parse().unwrap())?;
+/// collector.parse_tokens("indent = 4".parse().unwrap())?;
+/// assert_eq!(collector.finish()?.len(), 2);
+/// # syn::Result::Ok(())
+/// ```
+pub struct Collector<E> {
+    values: Vec<(Ident, E)>,
+    // paths of the parsed attributes
+    #[cfg_attr(not(feature = "checking"), allow(dead_code))]
+    sources: Vec<Span>,
+}
+
+impl<E> Default for Collector<E> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl<E: ArgEnum> Collector<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses all arguments in `input`, which are added to the values
+    /// collected so far.
+    pub fn parse(&mut self, input: ParseStream) -> syn::Result<()> {
+        self.parse_with(&mut Parser::new(input))
+    }
+
+    fn parse_with(&mut self, parser: &mut Parser) -> syn::Result<()> {
+        let values = &mut self.values;
+        parser.with_unstable_features(E::unstable_features());
+        parser.parse_all_with(|parser| {
+            Ok(E::parse_next(parser)?.map(|(key, value)| {
+                let span = key.span();
+                values.push((key, value));
+                span
+            }))
+        })
+    }
+
+    pub fn parse_tokens(&mut self, tokens: TokenStream) -> syn::Result<()> {
+        syn::parse::Parser::parse2(|input: ParseStream| self.parse(input), tokens)
+    }
+
+    /// Parses each attribute named `path` in `attrs`, e.g. `my::attr`, and
+    /// skips the others. Errors of all attributes are combined.
+    #[cfg(feature = "attributes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "attributes")))]
+    pub fn parse_attrs(&mut self, path: &str, attrs: &[syn::Attribute]) -> syn::Result<()> {
+        let mut errors = crate::errors::Errors::default();
+        for attr in attrs {
+            let ctx = crate::context::ParseContext::of(attr);
+            if !ctx.is(path) {
+                continue;
+            }
+            self.sources.push(crate::context::path_span(attr));
+            let res = attr.meta.require_list().and_then(|list| {
+                list.parse_args_with(|input: ParseStream| {
+                    self.parse_with(Parser::new(input).insert_context(ctx))
+                })
+            });
+            errors.add_result(res);
+        }
+        errors.fail()
+    }
+
+    /// Returns the values collected so far, each along with its key.
+    pub fn values(&self) -> &[(Ident, E)] {
+        &self.values
+    }
+
+    /// Runs the checks declared on `E` over all collected values, and then
+    /// returns them.
+    ///
+    /// Errors without a span of their own are reported on the paths of the
+    /// attributes parsed by [`parse_attrs`](Self::parse_attrs), if any.
+    pub fn finish(self) -> syn::Result<Vec<(Ident, E)>> {
+        #[cfg(feature = "checking")]
+        {
+            let mut checker = crate::checker::Checker::default();
+            for &span in self.sources.iter() {
+                checker.with_source(span);
+            }
+            E::check(&self.values, &mut checker);
+            checker.finish()?;
+        }
+        Ok(self.values)
+    }
+}
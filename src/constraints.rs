@@ -0,0 +1,709 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::code::{with_docs, ErrorCode};
+
+/// A finding of [`Constraints`].
+#[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    pub code: ErrorCode,
+    /// What the finding is attributed to, the same as by
+    /// [`Checker`](crate::Checker): the argument to blame, or the group of a
+    /// rule on a group, e.g. `a | b`.
+    pub name: String,
+    pub message: String,
+}
+
+/// The rules of [`Checker`](crate::Checker), applied to the number of times
+/// each argument is supplied rather than to parsed arguments, e.g. to
+/// validate a config file with the same schema as a proc-macro.
+///
+/// Findings read the same as those of `Checker`, except that they name
+/// arguments rather than the keys supplied, and are ordered the same way,
+/// where arguments are in the order they are first counted.
+///
+/// ```
+/// # use plap::{Constraints, ErrorCode};
+/// let mut c = Constraints::new([("color", 1), ("quiet", 2)]);
+/// c.exclusive("quiet")
+///     .conflicts_with("color", "quiet")
+///     .required("name");
+/// let msgs = c
+///     .finish()
+///     .into_iter()
+///     .map(|v| v.message)
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     msgs,
+///     [
+///         "`color` conflicts with `quiet`",
+///         "`quiet` has too many values (<= 1)",
+///         "`quiet` conflicts with `color`",
+///         "`name` is required",
+///     ],
+/// );
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    counts: Vec<(String, usize)>,
+    // findings since the last finish, along with the positions of the
+    // arguments they are reported on
+    violations: Vec<(usize, Violation)>,
+    // pairs of conflicting arguments reported, by their positions in either
+    // order
+    conflicts: HashSet<(usize, usize)>,
+}
+
+impl Constraints {
+    /// Creates constraints over the arguments supplied the given number of
+    /// times, in the order of the input. Arguments not listed are regarded as
+    /// absent.
+    pub fn new<'a>(counts: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut c = Self::default();
+        for (name, n) in counts {
+            c.add(name, n);
+        }
+        c
+    }
+
+    /// Adds `n` occurrences of `name`.
+    pub fn add(&mut self, name: &str, n: usize) -> &mut Self {
+        match self.counts.iter_mut().find(|(k, _)| k == name) {
+            Some((_, count)) => *count += n,
+            None => self.counts.push((name.to_owned(), n)),
+        }
+        self
+    }
+
+    /// Returns how many times `name` is supplied.
+    pub fn count(&self, name: &str) -> usize {
+        self.counts
+            .iter()
+            .find(|(k, _)| k == name)
+            .map_or(0, |(_, n)| *n)
+    }
+
+    /* ---------------------- *
+     * container level checks *
+     * ---------------------- */
+
+    pub fn required_each(&mut self, names: &[&str]) -> &mut Self {
+        for a in names {
+            self.required(a);
+        }
+        self
+    }
+
+    pub fn required_any(&mut self, names: &[&str]) -> &mut Self {
+        required_any(self, names);
+        self
+    }
+
+    pub fn exclusive_group(&mut self, names: &[&str]) -> &mut Self {
+        exclusive_group(self, names);
+        self
+    }
+
+    pub fn exclusive_aliases(&mut self, names: &[&str]) -> &mut Self {
+        exclusive_aliases(self, names);
+        self
+    }
+
+    pub fn max_members(&mut self, names: &[&str], max: usize) -> &mut Self {
+        max_members(self, names, max);
+        self
+    }
+
+    pub fn min_members(&mut self, names: &[&str], min: usize) -> &mut Self {
+        min_members(self, names, min);
+        self
+    }
+
+    pub fn members_between(&mut self, names: &[&str], min: usize, max: usize) -> &mut Self {
+        members_between(self, names, min, max);
+        self
+    }
+
+    pub fn blocked_each(&mut self, names: &[&str]) -> &mut Self {
+        for a in names {
+            self.blocked(a);
+        }
+        self
+    }
+
+    /* ------------------ *
+     * field level checks *
+     * ------------------ */
+
+    pub fn required(&mut self, a: &str) -> &mut Self {
+        required(self, a);
+        self
+    }
+
+    pub fn exclusive(&mut self, a: &str) -> &mut Self {
+        exclusive(self, a);
+        self
+    }
+
+    pub fn requires(&mut self, a: &str, b: &str) -> &mut Self {
+        requires(self, a, b);
+        self
+    }
+
+    pub fn requires_each(&mut self, a: &str, b: &[&str]) -> &mut Self {
+        for b in b {
+            self.requires(a, b);
+        }
+        self
+    }
+
+    pub fn requires_any(&mut self, a: &str, b: &[&str]) -> &mut Self {
+        requires_any(self, a, b);
+        self
+    }
+
+    pub fn conflicts_with(&mut self, a: &str, b: &str) -> &mut Self {
+        conflicts_with(self, a, b, None);
+        self
+    }
+
+    pub fn conflicts_with_each(&mut self, a: &str, b: &[&str]) -> &mut Self {
+        for b in b {
+            self.conflicts_with(a, b);
+        }
+        self
+    }
+
+    /// Reports `a` only if all of `b` are supplied as well.
+    pub fn conflicts_with_all(&mut self, a: &str, b: &[&str]) -> &mut Self {
+        conflicts_with_all(self, a, b);
+        self
+    }
+
+    pub fn blocked(&mut self, a: &str) -> &mut Self {
+        blocked(self, a);
+        self
+    }
+
+    /// Takes the violations found since the last call.
+    pub fn finish(&mut self) -> Vec<Violation> {
+        self.conflicts.clear();
+        // the sort is stable, which keeps the order of checks on ties
+        self.violations.sort_by_key(|(position, _)| *position);
+        self.violations.drain(..).map(|(_, v)| v).collect()
+    }
+}
+
+impl<'a> Subject<'a> for Constraints {
+    type Arg = &'a str;
+    type Key = &'a str;
+    type Occurrence = (&'a str, usize);
+    type Occurrences = std::option::IntoIter<(&'a str, usize)>;
+
+    fn count(&self, a: &'a str) -> usize {
+        Constraints::count(self, a)
+    }
+
+    // an argument supplied several times is reported once
+    fn occurrences(&self, a: &'a str) -> Self::Occurrences {
+        let position = self.counts.iter().position(|(k, n)| k == a && *n > 0);
+        position.map(|i| (a, i)).into_iter()
+    }
+
+    fn key((a, _): Self::Occurrence) -> &'a str {
+        a
+    }
+
+    fn write_name(&self, a: &'a str, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(a)
+    }
+
+    fn report(&mut self, name: &str, code: ErrorCode, msg: &dyn fmt::Display) {
+        let violation = Violation {
+            code,
+            name: name.to_owned(),
+            message: code.message(msg),
+        };
+        self.violations.push((usize::MAX, violation));
+    }
+
+    fn report_at(
+        &mut self,
+        (a, position): Self::Occurrence,
+        group: Option<&str>,
+        code: ErrorCode,
+        _: &[&dyn fmt::Display],
+        msg: &dyn fmt::Display,
+    ) {
+        let message = code.message(msg);
+        // the same message is reported once on an argument, like by `Checker`
+        let reported = self
+            .violations
+            .iter()
+            .any(|(p, v)| *p == position && v.code == code && v.message == message);
+        if !reported {
+            let violation = Violation {
+                code,
+                name: group.unwrap_or(a).to_owned(),
+                message,
+            };
+            self.violations.push((position, violation));
+        }
+    }
+
+    fn first_conflict(&mut self, (_, a): Self::Occurrence, (_, b): Self::Occurrence) -> bool {
+        self.conflicts.insert((a.min(b), a.max(b)))
+    }
+}
+
+/* ------------------------------------------------------------ *
+ * rules shared with `Checker`, which are decided on counts and *
+ * worded the same regardless of spans                          *
+ * ------------------------------------------------------------ */
+
+/// What rules are evaluated on, i.e. the arguments of a
+/// [`Checker`](crate::Checker) or the counts of [`Constraints`], and where
+/// their findings are reported.
+pub(crate) trait Subject<'a> {
+    type Arg: Copy;
+    /// What messages cite an occurrence by, e.g. the key supplied.
+    type Key: fmt::Display;
+    type Occurrence: Copy;
+    type Occurrences: Iterator<Item = Self::Occurrence>;
+
+    fn count(&self, a: Self::Arg) -> usize;
+
+    /// Returns the occurrences of `a` on which findings are reported.
+    fn occurrences(&self, a: Self::Arg) -> Self::Occurrences;
+
+    fn key(o: Self::Occurrence) -> Self::Key;
+
+    /// Writes the name of `a` as cited in messages.
+    fn write_name(&self, a: Self::Arg, f: &mut fmt::Formatter) -> fmt::Result;
+
+    fn docs_url(&self, _: Self::Arg) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns what findings of rules on a group are attributed to.
+    fn group_name(&self, args: &[Self::Arg]) -> String {
+        group_of(self, args)
+    }
+
+    /// Describes a group as cited in messages.
+    fn describe_group(&self, args: &[Self::Arg]) -> String {
+        format!("`{}`", group_of(self, args))
+    }
+
+    /// Reports a finding on the input as a whole, e.g. a missing argument,
+    /// attributed to `name`.
+    fn report(&mut self, name: &str, code: ErrorCode, msg: &dyn fmt::Display);
+
+    /// Reports a finding on an occurrence, attributed to `group` if given, or
+    /// the argument of the occurrence otherwise. `args` are what `msg` says
+    /// besides the key.
+    fn report_at(
+        &mut self,
+        o: Self::Occurrence,
+        group: Option<&str>,
+        code: ErrorCode,
+        args: &[&dyn fmt::Display],
+        msg: &dyn fmt::Display,
+    );
+
+    /// Returns whether the conflict between `a` and `b`, in either order, is
+    /// not reported yet, and marks it as reported.
+    fn first_conflict(&mut self, a: Self::Occurrence, b: Self::Occurrence) -> bool;
+}
+
+fn name_of<'a, S: Subject<'a>>(s: &S, a: S::Arg) -> String {
+    FmtWith(|f| s.write_name(a, f)).to_string()
+}
+
+/// Formats the names of a group like `a | b | c`.
+fn group_of<'a, S>(s: &S, args: &[S::Arg]) -> String
+where
+    S: Subject<'a> + ?Sized,
+{
+    fmt_group(args.iter().map(|&a| FmtWith(move |f| s.write_name(a, f)))).to_string()
+}
+
+fn count_group<'a, S: Subject<'a>>(s: &S, args: &[S::Arg]) -> usize {
+    args.iter().map(|&a| s.count(a)).sum()
+}
+
+/// Returns the number of supplied members of a group.
+fn count_members<'a, S: Subject<'a>>(s: &S, args: &[S::Arg]) -> usize {
+    args.iter().filter(|&&a| s.count(a) > 0).count()
+}
+
+pub(crate) fn required<'a, S: Subject<'a>>(s: &mut S, a: S::Arg) {
+    if s.count(a) == 0 {
+        let name = name_of(s, a);
+        let msg = with_docs(msg_required(&name), s.docs_url(a));
+        s.report(&name, ErrorCode::Required, &msg);
+    }
+}
+
+pub(crate) fn required_any<'a, S: Subject<'a>>(s: &mut S, args: &[S::Arg]) {
+    if count_members(s, args) == 0 {
+        let msg = msg_required(group_of(s, args));
+        let name = s.group_name(args);
+        s.report(&name, ErrorCode::Required, &msg);
+    }
+}
+
+pub(crate) fn exclusive_group<'a, S: Subject<'a>>(s: &mut S, args: &[S::Arg]) {
+    let group = if count_group(s, args) > 1 {
+        Some((s.describe_group(args), s.group_name(args)))
+    } else {
+        None
+    };
+    let group = group
+        .as_ref()
+        .map(|(desc, name)| (desc.as_str(), name.as_str()));
+    for (&a, &b) in combination(args) {
+        conflicts_with(s, a, b, group);
+    }
+}
+
+pub(crate) fn exclusive_aliases<'a, S: Subject<'a>>(s: &mut S, args: &[S::Arg]) {
+    if count_group(s, args) > 1 {
+        let group = s.group_name(args);
+        for &a in args {
+            too_many_values(s, a, Some(&group));
+        }
+    }
+}
+
+/// Reports the supplied members of a group beyond the first `max` ones, with
+/// `msg` formatted for each key, see [`Subject::report_at`] for `fmt_args`.
+fn surplus<'a, S, M, D>(
+    s: &mut S,
+    args: &[S::Arg],
+    max: usize,
+    fmt_args: &[&dyn fmt::Display],
+    msg: M,
+) where
+    S: Subject<'a>,
+    M: Fn(S::Key) -> D,
+    D: fmt::Display,
+{
+    let name = s.group_name(args);
+    let mut supplied = 0;
+    for &a in args {
+        if s.count(a) == 0 {
+            continue;
+        }
+        supplied += 1;
+        if supplied <= max {
+            continue;
+        }
+        for o in s.occurrences(a) {
+            let msg = msg(S::key(o));
+            s.report_at(o, Some(&name), ErrorCode::TooManyMembers, fmt_args, &msg);
+        }
+    }
+}
+
+pub(crate) fn max_members<'a, S: Subject<'a>>(s: &mut S, args: &[S::Arg], max: usize) {
+    if count_members(s, args) > max {
+        let group = group_of(s, args);
+        let msg = |k| msg_exceeds_max(k, max, &group);
+        surplus(s, args, max, &[&max, &group], msg);
+    }
+}
+
+pub(crate) fn min_members<'a, S: Subject<'a>>(s: &mut S, args: &[S::Arg], min: usize) {
+    let count = count_members(s, args);
+    if count < min {
+        let msg = msg_at_least(min, group_of(s, args), count);
+        let name = s.group_name(args);
+        s.report(&name, ErrorCode::Required, &msg);
+    }
+}
+
+pub(crate) fn members_between<'a, S: Subject<'a>>(
+    s: &mut S,
+    args: &[S::Arg],
+    min: usize,
+    max: usize,
+) {
+    let count = count_members(s, args);
+    if count < min {
+        let msg = msg_between(min, max, group_of(s, args), count);
+        let name = s.group_name(args);
+        s.report(&name, ErrorCode::Required, &msg);
+    }
+    if count > max {
+        let group = group_of(s, args);
+        let msg = |k| msg_exceeds_range(k, min, max, &group, count);
+        surplus(s, args, max, &[&min, &max, &group, &count], msg);
+    }
+}
+
+pub(crate) fn exclusive<'a, S: Subject<'a>>(s: &mut S, a: S::Arg) {
+    if s.count(a) > 1 {
+        too_many_values(s, a, None);
+    }
+}
+
+fn too_many_values<'a, S: Subject<'a>>(s: &mut S, a: S::Arg, group: Option<&str>) {
+    for o in s.occurrences(a) {
+        let msg = msg_too_many_values(S::key(o));
+        s.report_at(o, group, ErrorCode::TooManyValues, &[], &msg);
+    }
+}
+
+pub(crate) fn requires<'a, S: Subject<'a>>(s: &mut S, a: S::Arg, b: S::Arg) {
+    if s.count(a) > 0 && s.count(b) == 0 {
+        let b = name_of(s, b);
+        for o in s.occurrences(a) {
+            let msg = msg_requires(S::key(o), &b);
+            s.report_at(o, None, ErrorCode::Requires, &[&b], &msg);
+        }
+    }
+}
+
+pub(crate) fn requires_any<'a, S: Subject<'a>>(s: &mut S, a: S::Arg, args: &[S::Arg]) {
+    if s.count(a) > 0 && count_members(s, args) == 0 {
+        let group = group_of(s, args);
+        for o in s.occurrences(a) {
+            let msg = msg_requires(S::key(o), &group);
+            s.report_at(o, None, ErrorCode::Requires, &[&group], &msg);
+        }
+    }
+}
+
+/// Reports conflicts between `a` and `b`, citing the `group` they are
+/// exclusive in, if that is why they conflict, as described in messages and
+/// as the findings are attributed to.
+pub(crate) fn conflicts_with<'a, S: Subject<'a>>(
+    s: &mut S,
+    a: S::Arg,
+    b: S::Arg,
+    group: Option<(&str, &str)>,
+) {
+    for a in s.occurrences(a) {
+        for b in s.occurrences(b) {
+            // a pair declared to conflict by several rules, in either order,
+            // is reported once, as the first rule explains it
+            if !s.first_conflict(a, b) {
+                continue;
+            }
+            // conflicts are always bidirectional
+            let code = ErrorCode::Conflict;
+            let (desc, name) = (group.map(|g| g.0), group.map(|g| g.1));
+            let d = desc.unwrap_or_default();
+            let msg = msg_conflicts_in(S::key(a), S::key(b), desc);
+            s.report_at(a, name, code, &[&S::key(b), &d], &msg);
+            let msg = msg_conflicts_in(S::key(b), S::key(a), desc);
+            s.report_at(b, name, code, &[&S::key(a), &d], &msg);
+        }
+    }
+}
+
+pub(crate) fn conflicts_with_all<'a, S: Subject<'a>>(s: &mut S, a: S::Arg, b: &[S::Arg]) {
+    if s.count(a) == 0 || b.is_empty() || count_members(s, b) < b.len() {
+        return;
+    }
+    // all of `b` are present here
+    let others = b.iter().map(|&b| S::key(s.occurrences(b).next().unwrap()));
+    let others = fmt_combination(others).to_string();
+    for o in s.occurrences(a) {
+        let msg = msg_conflicts_all(S::key(o), &others);
+        s.report_at(o, None, ErrorCode::Conflict, &[&others], &msg);
+    }
+}
+
+pub(crate) fn blocked<'a, S: Subject<'a>>(s: &mut S, a: S::Arg) {
+    for o in s.occurrences(a) {
+        let msg = msg_not_allowed(S::key(o));
+        s.report_at(o, None, ErrorCode::NotAllowed, &[], &msg);
+    }
+}
+
+pub(crate) fn combination<T>(arr: &[T]) -> impl '_ + Iterator<Item = (&'_ T, &'_ T)> {
+    arr.iter()
+        .enumerate()
+        .flat_map(|(k, t1)| arr[(k + 1)..].iter().map(move |t2| (t1, t2)))
+}
+
+pub(crate) fn msg_required(group: impl fmt::Display) -> impl fmt::Display {
+    FmtWith(move |f| write!(f, "`{}` is required", group))
+}
+
+pub(crate) fn msg_too_many_values(key: impl fmt::Display) -> impl fmt::Display {
+    FmtWith(move |f| write!(f, "`{}` has too many values (<= 1)", key))
+}
+
+pub(crate) fn msg_requires(key: impl fmt::Display, b: impl fmt::Display) -> impl fmt::Display {
+    FmtWith(move |f| write!(f, "`{}` requires `{}`", key, b))
+}
+
+pub(crate) fn msg_conflicts(key: impl fmt::Display, b: impl fmt::Display) -> impl fmt::Display {
+    FmtWith(move |f| write!(f, "`{}` conflicts with `{}`", key, b))
+}
+
+/// Like [`msg_conflicts`], but cites the `group` the arguments are exclusive
+/// in, if any, e.g. ``group `modes` ``.
+pub(crate) fn msg_conflicts_in<'a>(
+    key: impl 'a + fmt::Display,
+    b: impl 'a + fmt::Display,
+    group: Option<&'a str>,
+) -> impl 'a + fmt::Display {
+    FmtWith(move |f| {
+        write!(f, "{}", msg_conflicts(&key, &b))?;
+        if let Some(group) = group {
+            write!(f, ", as members of {} are mutually exclusive", group)?;
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn msg_conflicts_all(
+    key: impl fmt::Display,
+    combination: impl fmt::Display,
+) -> impl fmt::Display {
+    FmtWith(move |f| {
+        write!(
+            f,
+            "`{}` conflicts with the combination of {}",
+            key, combination
+        )
+    })
+}
+
+pub(crate) fn msg_not_allowed(key: impl fmt::Display) -> impl fmt::Display {
+    FmtWith(move |f| write!(f, "`{}` is not allowed in this context", key))
+}
+
+pub(crate) fn msg_exceeds_max(
+    key: impl fmt::Display,
+    max: usize,
+    group: impl fmt::Display,
+) -> impl fmt::Display {
+    FmtWith(move |f| {
+        write!(
+            f,
+            "`{}` exceeds the maximum of {} arguments from `{}`",
+            key, max, group
+        )
+    })
+}
+
+pub(crate) fn msg_exceeds_range(
+    key: impl fmt::Display,
+    min: usize,
+    max: usize,
+    group: impl fmt::Display,
+    count: usize,
+) -> impl fmt::Display {
+    FmtWith(move |f| {
+        write!(
+            f,
+            "`{}` exceeds the range of {} to {} arguments from `{}`, where {}",
+            key,
+            min,
+            max,
+            group,
+            fmt_supplied(count),
+        )
+    })
+}
+
+pub(crate) fn msg_at_least(
+    min: usize,
+    group: impl fmt::Display,
+    count: usize,
+) -> impl fmt::Display {
+    FmtWith(move |f| {
+        write!(
+            f,
+            "at least {} of `{}` are required, but {}",
+            min,
+            group,
+            fmt_supplied(count),
+        )
+    })
+}
+
+pub(crate) fn msg_between(
+    min: usize,
+    max: usize,
+    group: impl fmt::Display,
+    count: usize,
+) -> impl fmt::Display {
+    FmtWith(move |f| {
+        write!(
+            f,
+            "{} to {} of `{}` are required, but {}",
+            min,
+            max,
+            group,
+            fmt_supplied(count),
+        )
+    })
+}
+
+fn fmt_supplied(count: usize) -> impl fmt::Display {
+    FmtWith(move |f| match count {
+        0 => f.write_str("none is supplied"),
+        1 => f.write_str("1 is supplied"),
+        n => write!(f, "{} are supplied", n),
+    })
+}
+
+/// Formats a group like `a | b | c`.
+pub(crate) fn fmt_group<I>(names: I) -> impl fmt::Display
+where
+    I: Clone + IntoIterator,
+    I::Item: fmt::Display,
+{
+    FmtWith(move |f| {
+        for (i, name) in names.clone().into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            write!(f, "{}", name)?;
+        }
+        Ok(())
+    })
+}
+
+/// Formats a combination like `` `a`, `b` and `c` ``.
+pub(crate) fn fmt_combination<I>(names: I) -> impl fmt::Display
+where
+    I: Clone + IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: fmt::Display,
+{
+    FmtWith(move |f| {
+        let names = names.clone().into_iter();
+        let len = names.len();
+        for (i, name) in names.enumerate() {
+            let sep = match i {
+                0 => "",
+                _ if i + 1 == len => " and ",
+                _ => ", ",
+            };
+            write!(f, "{}`{}`", sep, name)?;
+        }
+        Ok(())
+    })
+}
+
+pub(crate) struct FmtWith<F>(pub F)
+where
+    F: Fn(&mut fmt::Formatter) -> fmt::Result;
+
+impl<F> fmt::Display for FmtWith<F>
+where
+    F: Fn(&mut fmt::Formatter) -> fmt::Result,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
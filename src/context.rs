@@ -0,0 +1,188 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use syn::parse::ParseStream;
+use syn::{AttrStyle, Attribute, Path};
+
+use crate::define_args::Args;
+use crate::parser::Parser;
+
+/// The attribute arguments are parsed from, e.g. `#[my::attr(...)]`, which
+/// is stored in the [context](crate::Parser::context) of the parser by
+/// [`Registry::parse_attrs`] and [`Collector::parse_attrs`].
+///
+/// [`Registry::parse_attrs`]: crate::Registry::parse_attrs
+/// [`Collector::parse_attrs`]: crate::Collector::parse_attrs
+#[derive(Clone)]
+pub struct ParseContext {
+    path: Path,
+    style: AttrStyle,
+}
+
+impl ParseContext {
+    pub fn new(path: Path, style: AttrStyle) -> Self {
+        Self { path, style }
+    }
+
+    pub fn of(attr: &Attribute) -> Self {
+        Self::new(attr.path().clone(), attr.style)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn style(&self) -> AttrStyle {
+        self.style
+    }
+
+    /// Returns whether the attribute is inner, i.e. `#![...]`.
+    pub fn is_inner(&self) -> bool {
+        matches!(self.style, AttrStyle::Inner(_))
+    }
+
+    /// Returns the path as written without spaces, e.g. `my::attr`.
+    pub fn path_string(&self) -> String {
+        let mut s = String::new();
+        if self.path.leading_colon.is_some() {
+            s.push_str("::");
+        }
+        for (i, segment) in self.path.segments.iter().enumerate() {
+            if i > 0 {
+                s.push_str("::");
+            }
+            s.push_str(&segment.ident.to_string());
+        }
+        s
+    }
+
+    /// Returns whether the path is written as `path`, e.g. `my::attr`.
+    pub fn is(&self, path: &str) -> bool {
+        self.path_string() == path
+    }
+}
+
+/// Writes the attribute with its arguments elided, e.g. `#[my::attr(...)]`.
+impl fmt::Display for ParseContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bang = if self.is_inner() { "!" } else { "" };
+        write!(f, "#{}[{}(...)]", bang, self.path_string())
+    }
+}
+
+impl fmt::Debug for ParseContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseContext")
+            .field("path", &self.path_string())
+            .field("inner", &self.is_inner())
+            .finish()
+    }
+}
+
+/// Matches `attr` against one or more accepted paths, e.g. a namespace and its
+/// aliases, returning an [`AttrMatch`] to parse its arguments if any matches.
+///
+/// The type of the arguments is either given after `=>` or inferred.
+///
+/// ```
+/// # use plap::{attr_name, define_args, Arg};
+/// # use syn::LitStr;
+/// define_args! {
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         rename: Arg<LitStr>,
+///     }
+/// }
+///
+/// let attrs = syn::parse::Parser::parse_str(
+///     syn::Attribute::parse_outer,
+///     r#"#[doc = "a"] #[my::attr(rename = "b")]"#,
+/// )?;
+/// let mut args = None;
+/// for attr in attrs.iter() {
+///     if let Some(m) = attr_name!(attr, "my_attr" | "my::attr" => MyArgs) {
+///         assert_eq!(m.context().path_string(), "my::attr");
+///         args = Some(m.parse()?);
+///     }
+/// }
+/// assert_eq!(args.unwrap().rename.entries()[0].value.value(), "b");
+/// # syn::Result::Ok(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "attributes")))]
+#[macro_export]
+macro_rules! attr_name {
+    ($attr:expr, $($path:literal)|+ => $ty:ty) => {
+        $crate::AttrMatch::<$ty>::new($attr, &[$($path),+])
+    };
+    ($attr:expr, $($path:literal)|+) => {
+        $crate::AttrMatch::new($attr, &[$($path),+])
+    };
+}
+
+/// An attribute whose path is accepted by [`attr_name!`], from which arguments
+/// of `A` are parsed.
+pub struct AttrMatch<'a, A> {
+    attr: &'a Attribute,
+    ctx: ParseContext,
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<'a, A: Args> AttrMatch<'a, A> {
+    /// Returns a match if the path of `attr` is written as any of `paths`,
+    /// e.g. `my::attr`.
+    pub fn new(attr: &'a Attribute, paths: &[&str]) -> Option<Self> {
+        let ctx = ParseContext::of(attr);
+        if !paths.iter().any(|p| ctx.is(p)) {
+            return None;
+        }
+        Some(Self {
+            attr,
+            ctx,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn attr(&self) -> &'a Attribute {
+        self.attr
+    }
+
+    pub fn context(&self) -> &ParseContext {
+        &self.ctx
+    }
+
+    /// Parses the arguments of the attribute, which must be a list like
+    /// `#[my_attr(...)]`. The [`ParseContext`] is stored in the context of
+    /// the parser.
+    pub fn parse(&self) -> syn::Result<A> {
+        let mut args = A::init();
+        self.parse_into(&mut args)?;
+        Ok(args)
+    }
+
+    /// Like [`parse`](Self::parse), but adds to `args`, e.g. to collect
+    /// repeated attributes.
+    pub fn parse_into(&self, args: &mut A) -> syn::Result<()> {
+        self.attr
+            .meta
+            .require_list()?
+            .parse_args_with(|input: ParseStream| {
+                Parser::new(input)
+                    .insert_context(self.ctx.clone())
+                    .parse_all(args)
+            })
+    }
+}
+
+impl<A> fmt::Debug for AttrMatch<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttrMatch").field("ctx", &self.ctx).finish()
+    }
+}
+
+/// Returns the span of the last segment of the path of `attr`, i.e. its name.
+pub(crate) fn path_span(attr: &Attribute) -> proc_macro2::Span {
+    match attr.path().segments.last() {
+        Some(segment) => segment.ident.span(),
+        None => proc_macro2::Span::call_site(),
+    }
+}
@@ -1,17 +1,269 @@
 use proc_macro2::{Ident, Span};
 use syn::parse::ParseStream;
 
+use crate::arg::{ArgAttrs, Presence};
+use crate::metrics::Phase;
 use crate::parser::Parser;
+use crate::target::Target;
 
 pub trait Args: Sized {
     fn init() -> Self;
 
     fn parse_next(&mut self, parser: &mut Parser) -> syn::Result<Option<Span>>;
 
+    /// Returns whether the arguments are parsed from input marked by
+    /// [`mark_parsed`](crate::mark_parsed), which is skipped, so that they
+    /// are returned as is by [`finish`](Self::finish).
+    fn is_skipped(&self) -> bool;
+
+    #[doc(hidden)]
+    fn set_skipped(&mut self);
+
+    /// Calls `f` with the name and presence of each argument, including those
+    /// of merged argument sets, whose names are prefixed as their keys are,
+    /// e.g. `db_url`.
+    fn for_each_arg<'a>(&'a self, f: &mut dyn FnMut(&str, &'a dyn Presence));
+
+    /// Returns the name and presence of each supplied argument, see
+    /// [`for_each_arg`](Self::for_each_arg).
+    fn supplied(&self) -> Vec<(String, &dyn Presence)> {
+        let mut supplied = Vec::new();
+        self.for_each_arg(&mut |name, arg| {
+            if arg.is_present() {
+                supplied.push((name.to_owned(), arg));
+            }
+        });
+        supplied
+    }
+
+    /// Returns a canonical text form of the declared arguments and checks, see
+    /// [`assert_schema_snapshot!`](crate::assert_schema_snapshot).
+    fn schema() -> String;
+
+    /// Returns the usage and the first doc line of each argument, sorted and
+    /// sectioned by their `display_order` and `heading`.
+    fn help() -> String {
+        let mut w = crate::help::HelpWriter::default();
+        Self::describe_args(&mut |name, attrs, doc| w.arg(name, attrs, doc));
+        w.finish()
+    }
+
+    /// Calls `f` with the name, attributes and doc lines of each declared
+    /// argument, where names of merged arguments are prefixed.
+    fn describe_args(f: &mut dyn FnMut(&str, &ArgAttrs, &[&str]));
+
+    /// Returns an error describing each mistake in the declared arguments,
+    /// such as an argument parsing empty values without
+    /// [`accepts_empty`](ArgAttrs::accepts_empty), or checks which can never
+    /// be satisfied together, e.g. two required arguments in an exclusive
+    /// group, see [`debug_assert_schema!`](crate::debug_assert_schema).
+    ///
+    /// Such mistakes are never reported while parsing, which would fail on
+    /// the input of users rather than the declarations of macro authors.
+    fn validate_schema() -> Result<(), String> {
+        Ok(())
+    }
+
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut new = Self::init();
-        Parser::new(input).parse_all(&mut new)?;
-        Ok(new)
+        measure::<Self, _>(Phase::Parse, input.span(), || {
+            let mut new = Self::init();
+            Parser::new(input).parse_all(&mut new)?;
+            Ok(new)
+        })
+    }
+
+    /// Parses like [`parse`](Self::parse), but stops at the first error, see
+    /// [`Parser::with_fail_fast`].
+    fn parse_fail_fast(input: ParseStream) -> syn::Result<Self> {
+        measure::<Self, _>(Phase::Parse, input.span(), || {
+            let mut new = Self::init();
+            Parser::new(input)
+                .with_fail_fast(true)
+                .parse_all(&mut new)?;
+            Ok(new)
+        })
+    }
+
+    /// Parses the argument named by `meta` in a callback of
+    /// [`parse_nested_meta`](syn::Attribute::parse_nested_meta), e.g. to mix
+    /// hand-written nested metas with arguments of `Self` while migrating.
+    ///
+    /// ```
+    /// # use plap::{define_args, Arg, Args};
+    /// # use syn::LitStr;
+    /// define_args! {
+    ///     struct MyArgs {
+    ///         #[arg(is_expr)]
+    ///         rename: Arg<LitStr>,
+    ///     }
+    /// }
+    ///
+    /// let attrs = syn::parse::Parser::parse_str(
+    ///     syn::Attribute::parse_outer,
+    ///     r#"#[my_attr(skip, rename = "a")]"#,
+    /// )?;
+    /// let mut args = MyArgs::init();
+    /// let mut skip = false;
+    /// attrs[0].parse_nested_meta(|meta| {
+    ///     if meta.path.is_ident("skip") {
+    ///         skip = true;
+    ///         Ok(())
+    ///     } else {
+    ///         args.parse_nested_meta(meta)
+    ///     }
+    /// })?;
+    /// assert!(skip);
+    /// assert_eq!(args.rename.entries()[0].value.value(), "a");
+    /// # syn::Result::Ok(())
+    /// ```
+    #[cfg(feature = "attributes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "attributes")))]
+    fn parse_nested_meta(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        crate::nested::parse_nested_meta(self, meta)
+    }
+
+    /// Resolves arguments declared with `#[arg(env = "...")]` from the
+    /// environment, and then those declared with `#[default_from(...)]` from
+    /// their siblings. This should be called once after parsing and any
+    /// [`default_value`](crate::Arg::default_value) the caller provides.
+    ///
+    /// Errors are reported at the call site, see
+    /// [`resolve_defaults_at`](Self::resolve_defaults_at).
+    fn resolve_defaults(&mut self) -> syn::Result<()> {
+        self.resolve_defaults_at(Span::call_site())
+    }
+
+    /// Like [`resolve_defaults`](Self::resolve_defaults), but reports errors
+    /// at `span`, usually that of the attribute the arguments are parsed
+    /// from, since the arguments they concern are not supplied.
+    fn resolve_defaults_at(&mut self, span: Span) -> syn::Result<()> {
+        let _ = span;
+        Ok(())
+    }
+
+    /// Runs the passes declared with `#[normalize(...)]`, e.g. to deduplicate
+    /// or sort values, so that checks and generated code see the same values
+    /// regardless of how they are written, see [`normalize`](crate::normalize).
+    /// This should be called after [`resolve_defaults`](Self::resolve_defaults)
+    /// and before checks.
+    fn normalize(&mut self) {}
+
+    /// Resolves defaults, normalizes values and runs the declared checks,
+    /// returning the arguments only if they are valid, so that none of them is
+    /// used unchecked.
+    ///
+    /// This is how parsed arguments are finished, as are those built by a
+    /// `#[builder(...)]` or converted by a `#[resolved(...)]`. Warnings are
+    /// discarded, see [`finish_report`](Self::finish_report) to read them.
+    /// Arguments [skipped](Self::is_skipped) are returned as is.
+    ///
+    /// Errors without a span of their own, such as missing arguments, are
+    /// reported at the call site, see [`finish_at`](Self::finish_at).
+    fn finish(self) -> syn::Result<Self> {
+        self.finish_at(Span::call_site())
+    }
+
+    /// Like [`finish`](Self::finish), but reports errors without a span of
+    /// their own at `span`, usually that of the attribute the arguments are
+    /// parsed from.
+    fn finish_at(mut self, span: Span) -> syn::Result<Self> {
+        if self.is_skipped() {
+            return Ok(self);
+        }
+        measure::<Self, _>(Phase::Check, span, || {
+            self.resolve_defaults_at(span)?;
+            self.normalize();
+            #[cfg(feature = "checking")]
+            crate::checker::Checker::pooled(|checker| {
+                checker.with_source(span);
+                self.check(checker);
+                checker.finish()
+            })?;
+            Ok(self)
+        })
+    }
+
+    /// Like [`finish`](Self::finish), but returns the arguments along with
+    /// the findings of checks partitioned by the arguments they pertain to,
+    /// leaving the caller to decide which ones to fail on, see
+    /// [`ErrorReport`](crate::ErrorReport), as well as the
+    /// [warnings](crate::ErrorReport::warnings). Errors on resolving defaults
+    /// are still returned early.
+    fn finish_report(self) -> syn::Result<(Self, crate::errors::ErrorReport)> {
+        self.finish_report_at(Span::call_site())
+    }
+
+    /// Like [`finish_report`](Self::finish_report), but reports errors
+    /// without a span of their own at `span`, see
+    /// [`finish_at`](Self::finish_at).
+    fn finish_report_at(mut self, span: Span) -> syn::Result<(Self, crate::errors::ErrorReport)> {
+        if self.is_skipped() {
+            return Ok((self, Default::default()));
+        }
+        measure::<Self, _>(Phase::Check, span, || {
+            self.resolve_defaults_at(span)?;
+            self.normalize();
+            #[cfg(feature = "checking")]
+            {
+                let report = crate::checker::Checker::pooled(|checker| {
+                    checker.with_source(span);
+                    self.check(checker);
+                    let warnings = checker.take_warnings();
+                    checker.take_report().with_warnings(warnings)
+                });
+                Ok((self, report))
+            }
+            #[cfg(not(feature = "checking"))]
+            Ok((self, Default::default()))
+        })
+    }
+
+    /// Returns a hash of the sources and token text of all values, e.g. to
+    /// cache code generated from the arguments, which stays the same as long
+    /// as the supplied tokens do, regardless of their spans and whitespace.
+    ///
+    /// The hash does not vary across platforms and compilations, but may
+    /// change with the version of this crate or of the compiler, whose
+    /// tokens are printed differently. Values not parsed from tokens, such as
+    /// those given by [`Arg::default_value`](crate::Arg::default_value), are
+    /// only hashed by their sources.
+    fn fingerprint(&self) -> u64;
+
+    /// Returns the items declared in `#[only_on(...)]`, or `None` if the
+    /// arguments are allowed anywhere.
+    fn targets() -> Option<&'static [Target]> {
+        None
+    }
+
+    /// Returns an error at `span` if the attribute `name` is placed on an item
+    /// not declared in [`targets`](Self::targets).
+    fn check_target(span: Span, name: &str, found: Target) -> syn::Result<()> {
+        match Self::targets() {
+            Some(allowed) => crate::target::check_target(span, name, found, allowed),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns an error at each key of a supplied argument whose
+    /// [`only_on`](ArgAttrs::only_on) does not include `found`, the item the
+    /// attribute is placed on.
+    fn check_placement(&self, found: Target) -> syn::Result<()> {
+        let _ = found;
+        Ok(())
+    }
+
+    /// Fills arguments declared in `#[inherit(...)]` from `parent` if they are
+    /// absent in `self`, e.g. to let field attributes fall back to container
+    /// attributes.
+    fn inherit_from(&mut self, parent: &Self) {
+        let _ = parent;
+    }
+
+    /// Clears the values of all arguments, including those of merged argument
+    /// sets, so that the same instance can be reused to parse the next item,
+    /// e.g. each field of a struct, without allocating anew.
+    fn reset(&mut self) {
+        *self = Self::init();
     }
 
     #[cfg(feature = "checking")]
@@ -19,21 +271,263 @@ pub trait Args: Sized {
     fn check(&self, checker: &mut crate::checker::Checker);
 }
 
+/// Parses arguments from a plain string, such as `"key = 1, flag"`.
+///
+/// The spans of returned errors resolve to lines and columns, as well as byte
+/// ranges, of `s`.
+#[cfg(feature = "string-parse")]
+#[cfg_attr(docsrs, doc(cfg(feature = "string-parse")))]
+pub fn from_str<A: Args>(s: &str) -> syn::Result<A> {
+    syn::parse::Parser::parse_str(A::parse, s)
+}
+
+fn measure<A, T>(phase: Phase, span: Span, f: impl FnOnce() -> T) -> T {
+    crate::metrics::measure(std::any::type_name::<A>(), phase, span, f)
+}
+
 pub trait ArgEnum: Sized {
     fn parse_next(parser: &mut Parser) -> syn::Result<Option<(Ident, Self)>>;
+
+    /// Returns the features variants are marked unstable with, see
+    /// [`ArgAttrs::unstable`].
+    fn unstable_features() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Checks values parsed by [`parse_next`](Self::parse_next), each along
+    /// with its key, against the `#[group(...)]` and `#[check(...)]` declared
+    /// on the enum, where variants are checked like fields of [`Args`].
+    #[cfg(feature = "checking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
+    fn check(values: &[(Ident, Self)], checker: &mut crate::checker::Checker);
 }
 
 #[macro_export]
 macro_rules! define_args {
+    (@targets) => { ::std::option::Option::None };
+    (@targets [$($target:ident),*]) => {
+        ::std::option::Option::Some(&[$($crate::private::Target::$target),*])
+    };
+    (@case) => { ::std::option::Option::None };
+    (@case "lowercase") => { ::std::option::Option::Some($crate::private::Case::Lower) };
+    (@case "UPPERCASE") => { ::std::option::Option::Some($crate::private::Case::Upper) };
+    (@case "snake_case") => { ::std::option::Option::Some($crate::private::Case::Snake) };
+    (@case "SCREAMING_SNAKE_CASE") => {
+        ::std::option::Option::Some($crate::private::Case::ScreamingSnake)
+    };
+    (@case "PascalCase") => { ::std::option::Option::Some($crate::private::Case::Pascal) };
+    (@case "camelCase") => { ::std::option::Option::Some($crate::private::Case::Camel) };
+    (@case $case:tt) => {
+        ::std::compile_error!(::std::concat!(
+            "unsupported case ",
+            ::std::stringify!($case),
+            ", keys must be identifiers",
+        ))
+    };
+    (@group_refs $vis:vis $name:ident ($index:expr) []) => {};
+    (@group_refs $vis:vis $name:ident ($index:expr) [$group:ident $($rest:ident)*]) => {
+        #[doc = concat!("The handle of the group `", stringify!($group), "`.")]
+        $vis const $group: $crate::private::GroupRef<$name> =
+            $crate::private::GroupRef::new(stringify!($group), $index);
+        $crate::define_args!(@group_refs $vis $name ($index + 1) [$($rest)*]);
+    };
+    (@builder [] $($tt:tt)*) => {};
+    (@builder [$builder:ident] $vis:vis $name:ident {$($f_name:ident: $f_ty:ty,)*}) => {
+        #[doc = concat!("A builder of [`", stringify!($name), "`].")]
+        $vis struct $builder {
+            inner: $name,
+        }
+
+        #[automatically_derived]
+        impl $name {
+            $vis fn builder() -> $builder {
+                $builder {
+                    inner: <$name as $crate::private::Args>::init(),
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl $builder {
+            $($vis fn $f_name(
+                mut self,
+                value: impl $crate::private::arg::IntoValue<
+                    <$f_ty as $crate::private::arg::ArgValue>::Value,
+                >,
+            ) -> Self {
+                $crate::private::arg::add_value(&mut self.inner.$f_name, value);
+                self
+            })*
+
+            /// Runs the declared checks against the supplied values, see
+            /// `Args::finish`.
+            $vis fn build(self) -> $crate::private::arg::BuildResult<$name> {
+                self.build_at($crate::private::Span::call_site())
+            }
+
+            /// Like `build`, but reports errors without a span of their own,
+            /// such as missing arguments, at `span`, see `Args::finish_at`.
+            $vis fn build_at(
+                self,
+                span: $crate::private::Span,
+            ) -> $crate::private::arg::BuildResult<$name> {
+                $crate::private::Args::finish_at(self.inner, span)
+            }
+        }
+    };
+    (@resolved [] $($tt:tt)*) => {};
+    (@resolved [$resolved:ident] $vis:vis $name:ident {$($f_name:ident,)*}) => {
+        #[automatically_derived]
+        impl $name {
+            #[doc = concat!(
+                "Finishes the arguments and converts each value into the field of [`",
+                stringify!($resolved),
+                "`] of the same name, reporting all errors together.",
+            )]
+            $vis fn resolve(self) -> $crate::private::syn::Result<$resolved> {
+                self.resolve_at($crate::private::Span::call_site())
+            }
+
+            /// Like `resolve`, but reports errors without a span of their own,
+            /// such as missing arguments, at `span`, see `Args::finish_at`.
+            $vis fn resolve_at(
+                self,
+                span: $crate::private::Span,
+            ) -> $crate::private::syn::Result<$resolved> {
+                let this = $crate::private::Args::finish_at(self, span)?;
+                let mut errors = $crate::private::Errors::default();
+                $(let $f_name = errors.add_result(
+                    $crate::private::FromArg::from_arg_at(this.$f_name, span),
+                );)*
+                errors.fail::<()>()?;
+                ::std::result::Result::Ok($resolved {
+                    $($f_name: $f_name.unwrap(),)*
+                })
+            }
+        }
+    };
+    (@accessors [] $($tt:tt)*) => {};
+    (@accessors [[$acc_vis:vis]] $name:ident {$(
+        $f_name:ident: $f_ty:ty [$($acc:ident)?] [$($arg:ident)*] [$($f_check:ident)*],
+    )*}) => {
+        #[automatically_derived]
+        impl $name {$(
+            $crate::define_args!(
+                @accessor $acc_vis [$($acc)?; $f_name]: $f_ty [$($arg)*] [$($f_check)*]
+            );
+        )*}
+    };
+    // flags are read as `bool`s,
+    (@accessor $vis:vis [$($acc:ident)?; $f_name:ident]: $f_ty:ty [is_flag $($arg:ident)*] [$($f_check:ident)*]) => {
+        $crate::define_args!(@accessor_flag $vis [$($acc)? $f_name] $f_name);
+    };
+    (@accessor $vis:vis [$($acc:ident)?; $f_name:ident]: $f_ty:ty [$_:ident $($arg:ident)*] [$($f_check:ident)*]) => {
+        $crate::define_args!(
+            @accessor $vis [$($acc)?; $f_name]: $f_ty [$($arg)*] [$($f_check)*]
+        );
+    };
+    // exclusive arguments as a single value,
+    (@accessor $vis:vis [$($acc:ident)?; $f_name:ident]: $f_ty:ty [] [exclusive $($f_check:ident)*]) => {
+        $crate::define_args!(@accessor_one $vis [$($acc)? $f_name] $f_name: $f_ty);
+    };
+    (@accessor $vis:vis [$($acc:ident)?; $f_name:ident]: $f_ty:ty [] [$_:ident $($f_check:ident)*]) => {
+        $crate::define_args!(@accessor $vis [$($acc)?; $f_name]: $f_ty [] [$($f_check)*]);
+    };
+    // and the others as all of their values, named explicitly, e.g. `tags_all`
+    (@accessor $vis:vis [$acc:ident; $f_name:ident]: $f_ty:ty [] []) => {
+        $crate::define_args!(@accessor_name $acc);
+        $vis fn $acc(
+            &self,
+        ) -> impl '_
+               + ::std::iter::DoubleEndedIterator<
+            Item = &<$f_ty as $crate::private::arg::ArgValue>::Value,
+        > + ::std::iter::ExactSizeIterator {
+            $crate::private::Arg::values(&self.$f_name)
+        }
+    };
+    (@accessor $vis:vis [; $f_name:ident]: $f_ty:ty [] []) => {
+        ::std::compile_error!(::std::concat!(
+            "`",
+            ::std::stringify!($f_name),
+            "` takes many values, name its accessor with e.g. `#[accessor(",
+            ::std::stringify!($f_name),
+            "_all)]`",
+        ));
+    };
+    (@accessor_flag $vis:vis [$acc:ident $($_f:ident)?] $f_name:ident) => {
+        $crate::define_args!(@accessor_name $acc);
+        $vis fn $acc(&self) -> bool {
+            $crate::private::arg::flag_value(&self.$f_name)
+        }
+    };
+    (@accessor_one $vis:vis [$acc:ident $($_f:ident)?] $f_name:ident: $f_ty:ty) => {
+        $crate::define_args!(@accessor_name $acc);
+        $vis fn $acc(
+            &self,
+        ) -> ::std::option::Option<&<$f_ty as $crate::private::arg::ArgValue>::Value> {
+            $crate::private::Arg::entries(&self.$f_name).last().map(|e| &e.value)
+        }
+    };
+    // accessors would otherwise shadow the methods of `Args`
+    (@accessor_name check) => { $crate::define_args!(@shadows check); };
+    (@accessor_name check_placement) => { $crate::define_args!(@shadows check_placement); };
+    (@accessor_name check_target) => { $crate::define_args!(@shadows check_target); };
+    (@accessor_name describe_args) => { $crate::define_args!(@shadows describe_args); };
+    (@accessor_name fingerprint) => { $crate::define_args!(@shadows fingerprint); };
+    (@accessor_name finish) => { $crate::define_args!(@shadows finish); };
+    (@accessor_name finish_at) => { $crate::define_args!(@shadows finish_at); };
+    (@accessor_name finish_report) => { $crate::define_args!(@shadows finish_report); };
+    (@accessor_name finish_report_at) => { $crate::define_args!(@shadows finish_report_at); };
+    (@accessor_name for_each_arg) => { $crate::define_args!(@shadows for_each_arg); };
+    (@accessor_name help) => { $crate::define_args!(@shadows help); };
+    (@accessor_name inherit_from) => { $crate::define_args!(@shadows inherit_from); };
+    (@accessor_name init) => { $crate::define_args!(@shadows init); };
+    (@accessor_name normalize) => { $crate::define_args!(@shadows normalize); };
+    (@accessor_name parse) => { $crate::define_args!(@shadows parse); };
+    (@accessor_name parse_fail_fast) => { $crate::define_args!(@shadows parse_fail_fast); };
+    (@accessor_name parse_nested_meta) => { $crate::define_args!(@shadows parse_nested_meta); };
+    (@accessor_name parse_next) => { $crate::define_args!(@shadows parse_next); };
+    (@accessor_name reset) => { $crate::define_args!(@shadows reset); };
+    (@accessor_name resolve_defaults) => { $crate::define_args!(@shadows resolve_defaults); };
+    (@accessor_name resolve_defaults_at) => { $crate::define_args!(@shadows resolve_defaults_at); };
+    (@accessor_name schema) => { $crate::define_args!(@shadows schema); };
+    (@accessor_name supplied) => { $crate::define_args!(@shadows supplied); };
+    (@accessor_name targets) => { $crate::define_args!(@shadows targets); };
+    (@accessor_name validate_schema) => { $crate::define_args!(@shadows validate_schema); };
+    (@shadows $acc:ident) => {
+        ::std::compile_error!(::std::concat!(
+            "the accessor `",
+            ::std::stringify!($acc),
+            "` shadows `Args::",
+            ::std::stringify!($acc),
+            "`, name it with e.g. `#[accessor(get_",
+            ::std::stringify!($acc),
+            ")]`",
+        ));
+    };
+    (@accessor_name $_:ident) => {};
     ($(#[doc = $doc:literal])*
     $(#[::$attr:meta])*
     $(#[group($($group:ident = $group_val:expr),* $(,)?)])*
-    $(#[check($($check:ident $(= $check_val:expr)?),* $(,)?)])*
+    $(#[check($($check:ident $(($($check_arg:expr),* $(,)?))? $(= $check_val:expr)?),* $(,)?)])*
+    $(#[warn($($warn:ident $(($($warn_arg:expr),* $(,)?))? $(= $warn_val:expr)?),* $(,)?)])*
+    $(#[rename_all($case:tt)])?
+    $(#[inherit($($inherit:ident),* $(,)?)])*
+    $(#[merge($m_vis:vis $m_name:ident: $m_ty:ty $(, prefix = $m_prefix:literal)? $(,)?)])*
+    $(#[only_on($($target:ident),* $(,)?)])?
+    $(#[builder($builder:ident)])?
+    $(#[accessors($accessors:vis)])?
+    $(#[resolved($resolved:ident)])?
     $vis:vis struct $name:ident {$(
         $(#[doc = $f_doc:literal])*
         $(#[::$f_attr:meta])*
         $(#[arg($($arg:ident $(= $arg_val:expr)?),* $(,)?)])*
-        $(#[check($($f_check:ident $(= $f_check_val:expr)?),* $(,)?)])*
+        $(#[check($($f_check:ident $(($($f_check_arg:expr),* $(,)?))? $(= $f_check_val:expr)?),* $(,)?)])*
+        $(#[warn($($f_warn:ident $(($($f_warn_arg:expr),* $(,)?))? $(= $f_warn_val:expr)?),* $(,)?)])*
+        $(#[group($($f_group:ident),* $(,)?)])*
+        $(#[default_from($f_default:ident)])?
+        $(#[normalize($($f_norm:ident $(= $f_norm_val:expr)?),* $(,)?)])*
+        $(#[accessor($f_acc:ident)])?
         $f_vis:vis $f_name:ident: $f_ty:ty,
     )*}) => {
         $(#[doc = $doc])*
@@ -42,37 +536,265 @@ macro_rules! define_args {
             $(#[doc = $f_doc])*
             $(#[$f_attr])*
             $f_vis $f_name: $f_ty,
-        )*}
+        )*
+        $($m_vis $m_name: $m_ty,)*
+        #[doc(hidden)]
+        __plap_skipped: bool,}
 
-        #[allow(unused_variables)]
+        #[automatically_derived]
+        #[allow(unused_mut, unused_variables)]
         impl $crate::private::Args for $name {
             fn init() -> $name {
+                let rename_all = $crate::define_args!(@case $($case)?);
+                $(let mut $f_name = $crate::private::arg::new_attrs();
+                $($($crate::private::ArgAttrs::$arg(&mut $f_name, $($arg_val,)*);)*)*)*
                 $name {$(
-                    $f_name: $crate::private::Arg::new(stringify!($f_name)),
-                )*}
+                    $f_name: $crate::private::arg::new_arg(
+                        &$f_name,
+                        $crate::private::arg::cased_name(&$f_name, stringify!($f_name), rename_all),
+                    ),
+                )*
+                $($m_name: <$m_ty as $crate::private::Args>::init(),)*
+                __plap_skipped: false,}
             }
 
+            fn is_skipped(&self) -> bool {
+                self.__plap_skipped
+            }
+
+            fn set_skipped(&mut self) {
+                self.__plap_skipped = true;
+            }
+
+            #[allow(unused_assignments)]
             fn parse_next(
                 &mut self,
                 parser: &mut $crate::private::Parser,
             ) -> $crate::private::arg::StructParseResult {
-                // build argument attributes
-                $(let mut $f_name = $crate::private::arg::new_attrs();
-                $($($crate::private::ArgAttrs::$arg(&mut $f_name, $($arg_val,)*);)*)*)*
+                // build argument attributes once per thread, see `KeyTable`
+                ::std::thread_local! {
+                    static KEYS: $crate::private::arg::KeyTable = {
+                        let rename_all = $crate::define_args!(@case $($case)?);
+                        $crate::private::arg::KeyTable::new(::std::vec![$({
+                            let mut attrs = $crate::private::arg::new_attrs();
+                            $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                            let name = stringify!($f_name);
+                            ($crate::private::arg::cased_name(&attrs, name, rename_all), attrs)
+                        }),*])
+                    };
+                }
 
                 // look for a matched argument,
                 let key = $crate::private::arg::parse_key(parser)?;
-                $(if $crate::private::arg::is_key(&key, stringify!($f_name)) {
-                    // and then add its parsed value
-                    return $crate::private::arg::parse_add_value(
-                        parser, &$f_name, key, &mut self.$f_name
-                    );
+                let found = KEYS.with(|keys| {
+                    let (index, attrs) = match keys.find(parser) {
+                        ::std::option::Option::Some(found) => found,
+                        ::std::option::Option::None => return ::std::result::Result::Err(key),
+                    };
+                    let mut i = 0usize;
+                    $(if index == i {
+                        // and then add its parsed value
+                        return ::std::result::Result::Ok($crate::private::arg::parse_add_value(
+                            parser, attrs, key, &mut self.$f_name
+                        ));
+                    }
+                    i += 1;)*
+                    ::std::unreachable!()
+                });
+                let key = match found {
+                    ::std::result::Result::Ok(res) => return res,
+                    ::std::result::Result::Err(key) => key,
+                };
+
+                // then try merged arguments, which only accept prefixed keys
+                $(if let Some(span) = $crate::private::Parser::parse_next_prefixed(
+                    parser, concat!("" $(, $m_prefix)?), &mut self.$m_name
+                )? {
+                    return Ok(Some(span));
                 })*
 
                 // if no match, we return the parsed key as an Err
                 return $crate::private::arg::unknown_argument(key);
             }
 
+            fn schema() -> ::std::string::String {
+                let mut s = $crate::private::SchemaWriter::new(stringify!($name));
+                $($(s.line(0, "group", stringify!($group = $group_val));)*)*
+                $($(s.line(0, "check", stringify!(
+                    $check $(($($check_arg),*))? $(= $check_val)?
+                ));)*)*
+                $($(s.line(0, "warn", stringify!(
+                    $warn $(($($warn_arg),*))? $(= $warn_val)?
+                ));)*)*
+                $(s.line(0, "rename_all", $case);)?
+                $($(s.line(0, "inherit", stringify!($inherit));)*)*
+                $(s.line(0, "only_on", stringify!($($target),*));)?
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    s.arg(stringify!($f_name), &attrs);
+                    $($(s.line(1, "check", stringify!(
+                        $f_check $(($($f_check_arg),*))? $(= $f_check_val)?
+                    ));)*)*
+                    $($(s.line(1, "warn", stringify!(
+                        $f_warn $(($($f_warn_arg),*))? $(= $f_warn_val)?
+                    ));)*)*
+                    $($(s.line(1, "group", stringify!($f_group));)*)*
+                    $(s.line(1, "default_from", stringify!($f_default));)?
+                    $($(s.line(1, "normalize", stringify!($f_norm $(= $f_norm_val)?));)*)*
+                    {
+                        use $crate::private::arg::{NestedSchema as _, NoNestedSchema as _};
+                        let probe = $crate::private::arg::Probe::<$f_ty>(::std::marker::PhantomData);
+                        s.nested((&probe).nested_schema());
+                    }
+                })*
+                $(s.merge(
+                    stringify!($m_name),
+                    concat!("" $(, $m_prefix)?),
+                    &<$m_ty as $crate::private::Args>::schema(),
+                );)*
+                s.finish()
+            }
+
+            fn describe_args(
+                f: &mut dyn FnMut(&str, &$crate::private::ArgAttrs, &[&str]),
+            ) {
+                let rename_all = $crate::define_args!(@case $($case)?);
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    let name = stringify!($f_name);
+                    let name = $crate::private::arg::cased_name(&attrs, name, rename_all);
+                    f(name, &attrs, &[$($f_doc),*]);
+                })*
+                $(<$m_ty as $crate::private::Args>::describe_args(&mut |name, attrs, doc| {
+                    f(&::std::format!("{}{}", concat!("" $(, $m_prefix)?), name), attrs, doc)
+                });)*
+            }
+
+            fn validate_schema() -> ::std::result::Result<(), ::std::string::String> {
+                let mut mistakes = ::std::vec::Vec::new();
+                let rename_all = $crate::define_args!(@case $($case)?);
+                let mut keys = ::std::vec::Vec::new();
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    let name = stringify!($f_name);
+                    let name = $crate::private::arg::cased_name(&attrs, name, rename_all);
+                    mistakes.extend($crate::private::arg::check_empty::<
+                        <$f_ty as $crate::private::arg::ArgValue>::Value,
+                    >(&attrs, name).err());
+                    keys.push((name, attrs));
+                })*
+                // as the table `parse_next` looks keys up in
+                mistakes.extend(
+                    $crate::private::arg::KeyTable::new(keys)
+                        .mistakes(&[$(stringify!($f_name)),*]),
+                );
+                $(mistakes.extend(<$m_ty as $crate::private::Args>::validate_schema().err());)*
+                $crate::private!(@cfg(feature = "checking") {
+                    let args = <$name as $crate::private::Args>::init();
+                    mistakes.extend($crate::private::arg::validate_rules(&args).err());
+                    // members are joined as in `all_group_members`, once each
+                    $(let $f_name: &dyn $crate::private::AnyArg = &args.$f_name;)*
+                    $($(let mut $group = ::std::vec::Vec::new();
+                    for &member in $group_val.iter() {
+                        mistakes.extend($crate::private::arg::join_group(
+                            &mut $group,
+                            stringify!($group),
+                            member,
+                        ).err());
+                    })*)*
+                    $($($(mistakes.extend($crate::private::arg::join_group(
+                        &mut $f_group,
+                        stringify!($f_group),
+                        $f_name,
+                    ).err());)*)*)*
+                });
+                $crate::private::arg::join_mistakes(mistakes)
+            }
+
+            fn resolve_defaults_at(
+                &mut self,
+                span: $crate::private::Span,
+            ) -> $crate::private::arg::BuildResult<()> {
+                let mut errors = $crate::private::Errors::default();
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    errors.add_result($crate::private::arg::env_fallback(
+                        &mut self.$f_name,
+                        &attrs,
+                        span,
+                    ));
+                })*
+                $($(errors.add_result($crate::private::Arg::default_from_at(
+                    &mut self.$f_name,
+                    &self.$f_default,
+                    span,
+                ));)?)*
+                $(errors.add_result($crate::private::Args::resolve_defaults_at(
+                    &mut self.$m_name,
+                    span,
+                ));)*
+                errors.fail()
+            }
+
+            fn normalize(&mut self) {
+                $($($($crate::normalize::$f_norm(&mut self.$f_name, $($f_norm_val,)?);)*)*)*
+                $($crate::private::Args::normalize(&mut self.$m_name);)*
+            }
+
+            fn fingerprint(&self) -> u64 {
+                let mut h = $crate::private::Fingerprint::default();
+                $(h.write_u64($crate::private::Arg::fingerprint(&self.$f_name));)*
+                $(h.write_u64($crate::private::Args::fingerprint(&self.$m_name));)*
+                h.finish()
+            }
+
+            fn targets() -> ::std::option::Option<&'static [$crate::private::Target]> {
+                $crate::define_args!(@targets $([$($target),*])?)
+            }
+
+            fn check_placement(
+                &self,
+                found: $crate::private::Target,
+            ) -> $crate::private::syn::Result<()> {
+                let mut errors = $crate::private::Errors::default();
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    $crate::private::arg::check_placement(
+                        &mut errors,
+                        &self.$f_name,
+                        &attrs,
+                        found,
+                    );
+                })*
+                $(errors.add_result($crate::private::Args::check_placement(&self.$m_name, found));)*
+                errors.fail()
+            }
+
+            fn for_each_arg<'a>(
+                &'a self,
+                f: &mut dyn FnMut(&str, &'a dyn $crate::private::Presence),
+            ) {
+                $(f($crate::private::Arg::name(&self.$f_name), &self.$f_name);)*
+                $($crate::private::Args::for_each_arg(&self.$m_name, &mut |name, arg| {
+                    f(&::std::format!("{}{}", concat!("" $(, $m_prefix)?), name), arg)
+                });)*
+            }
+
+            fn inherit_from(&mut self, parent: &$name) {
+                $($($crate::private::Arg::inherit_from(&mut self.$inherit, &parent.$inherit);)*)*
+                $($crate::private::Args::inherit_from(&mut self.$m_name, &parent.$m_name);)*
+            }
+
+            fn reset(&mut self) {
+                $($crate::private::Arg::clear(&mut self.$f_name);)*
+                $($crate::private::Args::reset(&mut self.$m_name);)*
+            }
+
             $crate::private!(@cfg(feature = "checking")
                 fn check(
                     &self,
@@ -81,12 +803,17 @@ macro_rules! define_args {
                     // generate argument variables, which can be referred in #[check(...)]
                     $(let $f_name: &dyn $crate::private::AnyArg = &self.$f_name;)*
 
-                    // generate group variables
-                    $($(let $group: &[&dyn $crate::private::AnyArg] = &$group_val;)*)*
+                    // generate group variables, built once and indexed by their handles
+                    #[allow(unused_variables)]
+                    let groups = $name::all_group_members(self);
+                    $($(let $group: &[&dyn $crate::private::AnyArg] =
+                        &groups[$name::$group.index()];
+                    $crate::private::Checker::name_group(checker, stringify!($group), $group);)*)*
 
                     // add container level checks, including groups, requirements, etc
                     $($($crate::private::Checker::$check(
                         checker,
+                        $($($check_arg,)*)*
                         $($check_val,)*
                     );)*)*
 
@@ -94,18 +821,93 @@ macro_rules! define_args {
                     $($($($crate::private::Checker::$f_check(
                         checker,
                         $f_name,
+                        $($($f_check_arg,)*)*
                         $($f_check_val,)*
                     );)*)*)*
+
+                    // merged arguments are checked on their own, named with their prefixes
+                    $($crate::private::Checker::check_prefixed(
+                        checker,
+                        concat!("" $(, $m_prefix)?),
+                        &self.$m_name,
+                    );)*
+
+                    // soft rules declared by #[warn(...)] are reported as warnings
+                    $crate::private::Checker::warn(checker, |checker| {
+                        $($($crate::private::Checker::$warn(
+                            checker,
+                            $($($warn_arg,)*)*
+                            $($warn_val,)*
+                        );)*)*
+                        $($($($crate::private::Checker::$f_warn(
+                            checker,
+                            $f_name,
+                            $($($f_warn_arg,)*)*
+                            $($f_warn_val,)*
+                        );)*)*)*
+                    });
+                }
+            );
+        }
+
+        #[automatically_derived]
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $crate::define_args!(@group_refs $vis $name (0) [$($($group)*)*]);
+
+            $crate::private!(@cfg(feature = "checking")
+                /// Returns the members of `group`, including those joining it
+                /// with `#[group(...)]`.
+                $vis fn group_members(
+                    &self,
+                    group: $crate::private::GroupRef<$name>,
+                ) -> ::std::vec::Vec<&dyn $crate::private::AnyArg> {
+                    $name::all_group_members(self).swap_remove(group.index())
+                }
+
+                /// Returns the members of all groups, in the order they are
+                /// declared, see `GroupRef::index`.
+                #[allow(unused_variables)]
+                $vis fn all_group_members(
+                    &self,
+                ) -> ::std::vec::Vec<::std::vec::Vec<&dyn $crate::private::AnyArg>> {
+                    $(let $f_name: &dyn $crate::private::AnyArg = &self.$f_name;)*
+                    $($(let mut $group = ::std::vec::Vec::new();
+                    for &member in $group_val.iter() {
+                        // duplicates are reported by `validate_schema`
+                        let _ = $crate::private::arg::join_group(
+                            &mut $group,
+                            stringify!($group),
+                            member,
+                        );
+                    })*)*
+                    // which may also be joined by members declaring #[group(...)]
+                    $($($(let _ = $crate::private::arg::join_group(
+                        &mut $f_group,
+                        stringify!($f_group),
+                        $f_name,
+                    );)*)*)*
+                    ::std::vec![$($($group),*),*]
                 }
             );
         }
+
+        $crate::define_args!(@builder [$($builder)?] $vis $name {$($f_name: $f_ty,)*});
+        $crate::define_args!(@accessors [$([$accessors])?] $name {$(
+            $f_name: $f_ty [$($f_acc)?] [$($($arg)*)*] [$($($f_check)*)*],
+        )*});
+        $crate::define_args!(@resolved [$($resolved)?] $vis $name {$($f_name,)*});
     };
     ($(#[doc = $doc:literal])*
     $(#[::$attr:meta])*
+    $(#[group($($group:ident = $group_val:expr),* $(,)?)])*
+    $(#[check($($check:ident $(($($check_arg:expr),* $(,)?))? $(= $check_val:expr)?),* $(,)?)])*
+    $(#[rename_all($case:tt)])?
     $vis:vis enum $name:ident {$(
         $(#[doc = $v_doc:literal])*
         $(#[::$v_attr:meta])*
         $(#[arg($($arg:ident $(= $arg_val:expr)?),* $(,)?)])*
+        $(#[check($($v_check:ident $(($($v_check_arg:expr),* $(,)?))? $(= $v_check_val:expr)?),* $(,)?)])*
         $v_name:ident($v_ty:ty),
     )*}) => {
         $(#[doc = $doc])*
@@ -117,23 +919,117 @@ macro_rules! define_args {
             $v_name($v_ty),
         )*}
 
+        #[automatically_derived]
+        #[allow(non_snake_case, unused_mut, unused_variables)]
         impl $crate::private::ArgEnum for $name {
             fn parse_next(
                 parser: &mut $crate::private::Parser,
             ) -> $crate::private::arg::EnumParseResult<$name> {
                 // the parsing process is largely the same as ArgStruct,
-                $(let mut $v_name = $crate::private::arg::new_attrs();
-                $($($crate::private::ArgAttrs::$arg(&mut $v_name, $($arg_val,)*);)*)*)*
+                ::std::thread_local! {
+                    static KEYS: $crate::private::arg::KeyTable = {
+                        let rename_all = $crate::define_args!(@case $($case)?);
+                        $crate::private::arg::KeyTable::new(::std::vec![$({
+                            let mut attrs = $crate::private::arg::new_attrs();
+                            $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                            let name = stringify!($v_name);
+                            ($crate::private::arg::cased_name(&attrs, name, rename_all), attrs)
+                        }),*])
+                    };
+                }
 
                 let key = $crate::private::arg::parse_key(parser)?;
-                $(if $crate::private::arg::is_key(&key, stringify!($v_name)) {
-                    // except here we return the parsed enum directly
-                    return $crate::private::arg::parse_value_into::<_, $name>(
-                        parser, &$v_name, key, $name::$v_name
-                    );
+                KEYS.with(|keys| {
+                    let (index, attrs) = match keys.find(parser) {
+                        ::std::option::Option::Some(found) => found,
+                        ::std::option::Option::None => {
+                            return $crate::private::arg::unknown_argument(key);
+                        }
+                    };
+                    let mut i = 0usize;
+                    $(if index == i {
+                        // except here we return the parsed enum directly
+                        return $crate::private::arg::parse_value_into::<_, $name>(
+                            parser, attrs, key, $name::$v_name
+                        );
+                    }
+                    i += 1;)*
+                    ::std::unreachable!()
+                })
+            }
+
+            fn unstable_features() -> ::std::vec::Vec<&'static str> {
+                let mut features = ::std::vec::Vec::new();
+                $({
+                    let mut attrs = $crate::private::arg::new_attrs();
+                    $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                    if let ::std::option::Option::Some(feature) = attrs.get_unstable() {
+                        if !features.contains(&feature) {
+                            features.push(feature);
+                        }
+                    }
                 })*
+                features
+            }
 
-                return $crate::private::arg::unknown_argument(key);
+            $crate::private!(@cfg(feature = "checking")
+                fn check(
+                    values: &[($crate::private::Ident, $name)],
+                    checker: &mut $crate::private::Checker,
+                ) {
+                    // collect the keys of each variant into an argument,
+                    let rename_all = $crate::define_args!(@case $($case)?);
+                    $(let mut $v_name = {
+                        let mut attrs = $crate::private::arg::new_attrs();
+                        $($($crate::private::ArgAttrs::$arg(&mut attrs, $($arg_val,)*);)*)*
+                        $crate::private::Arg::<()>::new($crate::private::arg::cased_name(
+                            &attrs,
+                            stringify!($v_name),
+                            rename_all,
+                        ))
+                    };)*
+                    for (position, (key, value)) in values.iter().enumerate() {
+                        let entry = $crate::private::Entry {
+                            position,
+                            ..$crate::private::Entry::new(key.clone(), ())
+                        };
+                        match value {
+                            $($name::$v_name(_) => $v_name.push(entry),)*
+                        }
+                    }
+                    $(let $v_name: &dyn $crate::private::AnyArg = &$v_name;)*
+
+                    // which are then grouped and checked as fields of a struct
+                    $($(let $group: &[&dyn $crate::private::AnyArg] = &$group_val;
+                    $crate::private::Checker::name_group(checker, stringify!($group), $group);)*)*
+                    $($($crate::private::Checker::$check(
+                        checker,
+                        $($($check_arg,)*)*
+                        $($check_val,)*
+                    );)*)*
+                    $($($($crate::private::Checker::$v_check(
+                        checker,
+                        $v_name,
+                        $($($v_check_arg,)*)*
+                        $($v_check_val,)*
+                    );)*)*)*
+                }
+            );
+        }
+
+        #[automatically_derived]
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $crate::define_args!(@group_refs $vis $name (0) [$($($group)*)*]);
+        }
+
+        // so that enums can be values of other arguments, e.g. `style = compact`
+        #[automatically_derived]
+        impl $crate::private::syn::parse::Parse for $name {
+            fn parse(
+                input: $crate::private::syn::parse::ParseStream,
+            ) -> $crate::private::syn::Result<$name> {
+                $crate::private::arg::parse_enum(input)
             }
         }
     };
@@ -0,0 +1,229 @@
+use std::fmt;
+
+use proc_macro2::Span;
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+use crate::code::ErrorCode;
+
+/// Structured data embedded in a string literal, e.g.
+/// `config = r#"{"a": 1}"#`, which is parsed from the content of the literal
+/// by [`FromEmbedded`].
+///
+/// Errors are reported where they occur inside the literal if spans can be
+/// resolved there, and at the whole literal otherwise. Either way, the line and
+/// column within the content are appended to the message.
+///
+/// ```
+/// # use plap::{define_args, Arg, Embedded, EmbeddedError, FromEmbedded};
+/// #[derive(Debug)]
+/// struct Pairs(Vec<(String, String)>);
+///
+/// impl FromEmbedded for Pairs {
+///     fn from_embedded(s: &str) -> Result<Self, EmbeddedError> {
+///         let mut pairs = Vec::new();
+///         let mut offset = 0;
+///         for pair in s.split(';') {
+///             match pair.split_once('=') {
+///                 Some((k, v)) => pairs.push((k.to_owned(), v.to_owned())),
+///                 None => return Err(EmbeddedError::at_offset(offset, "expected `=`")),
+///             }
+///             offset += pair.len() + 1;
+///         }
+///         Ok(Pairs(pairs))
+///     }
+/// }
+///
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         env: Arg<Embedded<Pairs>>,
+///     }
+/// }
+///
+/// # use plap::Args;
+/// let args = syn::parse::Parser::parse_str(MyArgs::parse, r#"env = "a=1;b=2""#)?;
+/// assert_eq!(args.env.entries()[0].value.value().0.len(), 2);
+/// let err = syn::parse::Parser::parse_str(MyArgs::parse, r#"env = "a=1;b""#).unwrap_err();
+/// assert_eq!(err.to_string(), "expected `=`, at line 1, column 5");
+/// # syn::Result::Ok(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Embedded<T> {
+    value: T,
+    span: Span,
+}
+
+impl<T> Embedded<T> {
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns where the literal the value is parsed from is written.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl<T: FromEmbedded> Parse for Embedded<T> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit = input.parse::<LitStr>()?;
+        let content = lit.value();
+        match T::from_embedded(&content) {
+            Ok(value) => Ok(Self {
+                value,
+                span: lit.span(),
+            }),
+            Err(e) => Err(e.into_error(&lit, &content)),
+        }
+    }
+}
+
+/// Parses data from the content of a string literal, see [`Embedded`].
+pub trait FromEmbedded: Sized {
+    fn from_embedded(s: &str) -> Result<Self, EmbeddedError>;
+}
+
+/// An error in the content of a string literal, which is remapped into the
+/// literal by [`Embedded`].
+#[derive(Clone, Debug)]
+pub struct EmbeddedError {
+    msg: String,
+    at: Position,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Position {
+    Unknown,
+    Offset(usize),
+    LineColumn(usize, usize),
+}
+
+impl EmbeddedError {
+    /// Creates an error spanned by the whole literal.
+    pub fn new(msg: impl fmt::Display) -> Self {
+        Self {
+            msg: msg.to_string(),
+            at: Position::Unknown,
+        }
+    }
+
+    /// Creates an error at the byte `offset` into the content.
+    pub fn at_offset(offset: usize, msg: impl fmt::Display) -> Self {
+        Self {
+            msg: msg.to_string(),
+            at: Position::Offset(offset),
+        }
+    }
+
+    /// Creates an error at a 1-based `line` and `column` of the content, where
+    /// columns count characters, as most parsers of structured data report.
+    pub fn at_line_column(line: usize, column: usize, msg: impl fmt::Display) -> Self {
+        Self {
+            msg: msg.to_string(),
+            at: Position::LineColumn(line, column),
+        }
+    }
+
+    fn into_error(self, lit: &LitStr, content: &str) -> syn::Error {
+        let offset = match self.at {
+            Position::Unknown => {
+                return syn::Error::new(lit.span(), ErrorCode::InvalidValue.message(self.msg))
+            }
+            Position::Offset(offset) => offset.min(content.len()),
+            Position::LineColumn(line, column) => offset_of(content, line, column),
+        };
+        let mut offset = offset;
+        while !content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let (line, column) = line_column_of(content, offset);
+        let span = token_offset(&lit.token().to_string(), offset)
+            .and_then(|start| {
+                let len = content[offset..].chars().next().map_or(0, char::len_utf8);
+                lit.token().subspan(start..start + len.max(1))
+            })
+            .unwrap_or_else(|| lit.span());
+        syn::Error::new(
+            span,
+            ErrorCode::InvalidValue.message(format_args!(
+                "{}, at line {}, column {}",
+                self.msg, line, column
+            )),
+        )
+    }
+}
+
+impl fmt::Display for EmbeddedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+/// Returns the byte offset of a 1-based `line` and `column` in `content`,
+/// clamped to the end of the line.
+fn offset_of(content: &str, line: usize, column: usize) -> usize {
+    let mut offset = 0;
+    for (i, l) in content.split('\n').enumerate() {
+        if i + 1 == line.max(1) {
+            let skip = l.char_indices().nth(column.max(1) - 1);
+            return offset + skip.map_or(l.len(), |(j, _)| j);
+        }
+        offset += l.len() + 1;
+    }
+    content.len()
+}
+
+/// Returns the 1-based line and column of the byte `offset` in `content`.
+fn line_column_of(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[start..].chars().count() + 1)
+}
+
+/// Maps the byte `offset` into the content of a string literal to that into
+/// its `token`, i.e. how it is written, by skipping the prefix and escapes.
+fn token_offset(token: &str, offset: usize) -> Option<usize> {
+    if let Some(raw) = token.strip_prefix('r') {
+        // raw strings are written as is after `r#*"`
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return Some(1 + hashes + 1 + offset);
+    }
+    let bytes = token.as_bytes();
+    if bytes.first() != Some(&b'"') {
+        return None;
+    }
+    let (mut i, mut content) = (1, 0);
+    while content < offset {
+        let rest = token.get(i..)?;
+        let c = rest.chars().next()?;
+        if c != '\\' {
+            i += c.len_utf8();
+            content += c.len_utf8();
+            continue;
+        }
+        let (written, decoded) = match *bytes.get(i + 1)? {
+            b'x' => (4, 1),
+            b'u' => {
+                let end = rest.find('}')?;
+                let c = u32::from_str_radix(&rest[3..end], 16).ok();
+                (end + 1, c.and_then(char::from_u32)?.len_utf8())
+            }
+            // a line continuation skips the following whitespace
+            b'\n' | b'\r' => {
+                let after = rest[1..].trim_start();
+                (rest.len() - after.len(), 0)
+            }
+            _ => (2, 1),
+        };
+        i += written;
+        content += decoded;
+    }
+    Some(i)
+}
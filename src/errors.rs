@@ -1,18 +1,93 @@
+use std::cell::Cell;
 use std::fmt;
 
 use proc_macro2::Span;
 
+thread_local! {
+    static EMITTER: Cell<Option<fn(syn::Error)>> = Cell::new(None);
+}
+
 #[derive(Debug, Default)]
 pub struct Errors {
-    e: Option<syn::Error>,
+    // each error along with the argument it pertains to, if known
+    e: Vec<(Option<String>, syn::Error)>,
+    warnings: Vec<syn::Error>,
+    limit: Option<usize>,
+    count: usize,
+    // the span of the first error over the limit, and how many are dropped
+    dropped: Option<(Span, usize)>,
+    // errors passed to the emitter, each along with the argument it pertains
+    // to, which are kept only to fail results in their place
+    emitted: Vec<(Option<String>, Span)>,
 }
 
 impl Errors {
+    /// Sets a function to emit each error reported on the current thread as
+    /// soon as it is found, rather than combining them into the result.
+    ///
+    /// This lets macros using an emit-and-continue workflow, such as that of
+    /// `proc-macro-error2`, still expand to fallback code, see
+    /// [`emit_diagnostics`](Self::emit_diagnostics). Results still fail if
+    /// any error is emitted, but with a single error like `aborting due to 2
+    /// emitted errors` in place of those emitted, which can be ignored.
+    pub fn emit_with(f: Option<fn(syn::Error)>) {
+        EMITTER.with(|e| e.set(f));
+    }
+
+    /// Emits each error reported on the current thread through
+    /// `proc-macro-error2` as soon as it is found, see
+    /// [`emit_with`](Self::emit_with).
+    ///
+    /// Like other diagnostics of `proc-macro-error2`, this must be called
+    /// within a macro annotated with `#[proc_macro_error]`.
+    #[cfg(feature = "emit-errors")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "emit-errors")))]
+    pub fn emit_diagnostics() {
+        Self::emit_with(Some(|e| proc_macro_error2::Diagnostic::from(e).emit()));
+    }
+
+    /// Reports at most `limit` errors in detail, and then a summary of how
+    /// many more are found, so that a pathological input does not overwhelm
+    /// the output.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
     pub fn add(&mut self, err: syn::Error) {
-        if let Some(e) = &mut self.e {
-            e.combine(err);
+        self.add_to(None, err);
+    }
+
+    /// Adds an error pertaining to the argument `name`, which can be told
+    /// apart from the others in the [report](Self::report).
+    pub fn add_for(&mut self, name: &str, err: syn::Error) {
+        self.add_to(Some(name), err);
+    }
+
+    fn add_to(&mut self, name: Option<&str>, err: syn::Error) {
+        for err in err {
+            // errors emitted by nested parsers are only counted, since they
+            // are already reported
+            if let Some(n) = aborted_count(&err) {
+                let emitted = (name.map(str::to_owned), err.span());
+                self.emitted.extend(std::iter::repeat(emitted).take(n));
+                continue;
+            }
+            if self.limit.map_or(false, |limit| self.count >= limit) {
+                let dropped = self.dropped.get_or_insert((err.span(), 0));
+                dropped.1 += 1;
+                continue;
+            }
+            self.count += 1;
+            self.push(name.map(str::to_owned), err);
+        }
+    }
+
+    fn push(&mut self, name: Option<String>, err: syn::Error) {
+        if let Some(emit) = EMITTER.with(Cell::get) {
+            self.emitted.push((name, err.span()));
+            emit(err);
         } else {
-            self.e = Some(err);
+            self.e.push((name, err));
         }
     }
 
@@ -30,13 +105,237 @@ impl Errors {
         self.add(syn::Error::new(span, msg))
     }
 
+    /// Adds a finding which should not fail the parsing.
+    pub fn add_warning(&mut self, warning: syn::Error) {
+        self.warnings.push(warning);
+    }
+
+    pub fn add_warning_at(&mut self, span: Span, msg: impl fmt::Display) {
+        self.add_warning(syn::Error::new(span, msg))
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<syn::Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Returns the messages of the errors kept in detail so far.
+    pub(crate) fn messages(&self) -> impl '_ + Iterator<Item = String> {
+        self.e.iter().map(|(_, e)| e.to_string())
+    }
+
+    /// Discards all errors and warnings, keeping the limit.
+    pub fn clear(&mut self) {
+        self.e.clear();
+        self.warnings.clear();
+        self.count = 0;
+        self.dropped = None;
+        self.emitted.clear();
+    }
+
+    /// Returns the combined errors, if any, including those
+    /// [emitted](Self::emit_with). Warnings are kept until taken.
     pub fn fail<T>(&mut self) -> syn::Result<T>
     where
         T: Default,
     {
-        match self.e.take() {
+        match self.report().into_result() {
+            Ok(()) => Ok(T::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Takes the errors, partitioned by the arguments they pertain to.
+    /// Warnings are kept until taken.
+    pub fn report(&mut self) -> ErrorReport {
+        self.count = 0;
+        if let Some((span, n)) = self.dropped.take() {
+            let s = if n == 1 { "" } else { "s" };
+            self.push(
+                None,
+                syn::Error::new(span, format!("and {} more error{}", n, s)),
+            );
+        }
+        ErrorReport {
+            errors: std::mem::take(&mut self.e),
+            emitted: std::mem::take(&mut self.emitted),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Errors partitioned by the arguments they pertain to, e.g. to fail on some
+/// arguments while degrading gracefully on others.
+///
+/// Errors on a single argument are attributed to its name, with the prefix of
+/// merged arguments, e.g. `db_url`. Those of rules on a group, such as a
+/// missing member, are attributed to the name of the group, or to its members
+/// like `fast | safe` if it is declared inline. Those on no argument in
+/// particular, such as unknown arguments, are attributed to none.
+///
+/// Errors [emitted](Errors::emit_with) as they are found are attributed the
+/// same way, but only stand for a single error like `aborting due to 1
+/// emitted error`, so that results still fail on them.
+///
+/// ```
+/// # use plap::{define_args, Arg, Args};
+/// # use syn::{LitBool, LitStr};
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         #[check(exclusive)]
+///         rename: Arg<LitStr>,
+///         #[arg(is_flag)]
+///         #[check(required)]
+///         skip: Arg<LitBool>,
+///     }
+/// }
+///
+/// let args = syn::parse::Parser::parse_str(MyArgs::parse, r#"rename = "a", rename = "b""#)?;
+/// let (args, report) = args.finish_report()?;
+/// # #[cfg(feature = "checking")] {
+/// assert!(report.for_arg("rename").is_some());
+/// assert_eq!(
+///     report.for_arg("skip").unwrap().to_string(),
+///     "`skip` is required"
+/// );
+/// // fail on anything but `rename`, which falls back to its first value
+/// let err = report.fail_except(&["rename"]).unwrap_err();
+/// assert_eq!(err.to_string(), "`skip` is required");
+/// # }
+/// assert_eq!(args.rename.entries()[0].value.value(), "a");
+/// # syn::Result::Ok(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ErrorReport {
+    errors: Vec<(Option<String>, syn::Error)>,
+    emitted: Vec<(Option<String>, Span)>,
+    warnings: Vec<syn::Error>,
+}
+
+impl ErrorReport {
+    #[cfg(feature = "checking")]
+    pub(crate) fn with_warnings(mut self, warnings: Vec<syn::Error>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Returns `true` if there are no errors, regardless of warnings.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.emitted.is_empty()
+    }
+
+    /// Returns the findings of soft rules, such as those declared with
+    /// `#[warn(...)]`, which never fail the report.
+    pub fn warnings(&self) -> &[syn::Error] {
+        &self.warnings
+    }
+
+    /// Returns the names of the arguments with errors, including those
+    /// [emitted](Errors::emit_with), in the order their first errors are
+    /// found.
+    pub fn args(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let errors = self.errors.iter().map(|(name, _)| name);
+        let emitted = self.emitted.iter().map(|(name, _)| name);
+        for name in errors.chain(emitted).filter_map(|name| name.as_deref()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Returns the combined errors pertaining to the argument `name`, if any.
+    pub fn for_arg(&self, name: &str) -> Option<syn::Error> {
+        self.combine(|n| n == Some(name))
+    }
+
+    /// Returns the combined errors pertaining to no argument in particular,
+    /// if any.
+    pub fn unattributed(&self) -> Option<syn::Error> {
+        self.combine(|n| n.is_none())
+    }
+
+    /// Returns the combined errors, except those pertaining to any of `names`,
+    /// if any.
+    pub fn fail_except(&self, names: &[&str]) -> syn::Result<()> {
+        match self.combine(|n| n.map_or(true, |n| !names.contains(&n))) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns all errors combined, if any.
+    pub fn into_result(self) -> syn::Result<()> {
+        match self.combine(|_| true) {
             Some(e) => Err(e),
-            None => Ok(T::default()),
+            None => Ok(()),
+        }
+    }
+
+    // combines the errors pertaining to the arguments matching `f`, followed
+    // by one in place of those emitted
+    fn combine(&self, f: impl Fn(Option<&str>) -> bool) -> Option<syn::Error> {
+        let mut emitted = self.emitted.iter().filter(|(n, _)| f(n.as_deref()));
+        let aborted = emitted
+            .next()
+            .map(|(_, span)| aborted(*span, 1 + emitted.count()));
+        let mut errors = self
+            .errors
+            .iter()
+            .filter(|(n, _)| f(n.as_deref()))
+            .map(|(_, e)| e.clone())
+            .chain(aborted);
+        let mut first = errors.next()?;
+        first.extend(errors);
+        Some(first)
+    }
+
+    /// Emits each error through `proc-macro-error2`, e.g. to continue with
+    /// fallback code after checking arguments, see
+    /// [`Errors::emit_diagnostics`].
+    #[cfg(feature = "emit-errors")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "emit-errors")))]
+    pub fn emit(self) {
+        for (_, e) in self.errors {
+            proc_macro_error2::Diagnostic::from(e).emit();
         }
     }
 }
+
+// stands for errors already emitted, which are not reported again, see
+// `aborted_count`
+fn aborted(span: Span, n: usize) -> syn::Error {
+    let s = if n == 1 { "" } else { "s" };
+    syn::Error::new(span, format!("aborting due to {} emitted error{}", n, s))
+}
+
+// returns the number of errors `err` stands for if it is made by `aborted`,
+// which is only while errors are emitted
+fn aborted_count(err: &syn::Error) -> Option<usize> {
+    EMITTER.with(Cell::get)?;
+    let msg = err.to_string();
+    let rest = msg.strip_prefix("aborting due to ")?;
+    let (n, rest) = rest.split_once(' ')?;
+    let n = n.parse::<usize>().ok()?;
+    let expected = if n == 1 {
+        "emitted error"
+    } else {
+        "emitted errors"
+    };
+    (rest == expected).then(|| n)
+}
+
+/// Returns the byte range and message of each error in `err`.
+///
+/// This is intended for grammars parsed outside of proc-macros, such as from
+/// strings in build scripts, where byte ranges index into the parsed source.
+#[cfg(feature = "span-locations")]
+#[cfg_attr(docsrs, doc(cfg(feature = "span-locations")))]
+pub fn byte_ranges(err: &syn::Error) -> Vec<(std::ops::Range<usize>, String)> {
+    err.clone()
+        .into_iter()
+        .map(|e| (e.span().byte_range(), e.to_string()))
+        .collect()
+}
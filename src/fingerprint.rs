@@ -0,0 +1,95 @@
+use std::fmt::{self, Write};
+
+use proc_macro2::TokenStream;
+use syn::buffer::Cursor;
+
+/// A 64-bit FNV-1a hasher, whose output does not vary across platforms and
+/// compilations, unlike that of [`DefaultHasher`].
+///
+/// [`DefaultHasher`]: std::collections::hash_map::DefaultHasher
+#[derive(Clone, Copy, Debug)]
+pub struct Fingerprint(u64);
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fingerprint {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_str(&mut self, s: &str) {
+        // a terminator keeps `"ab", "c"` apart from `"a", "bc"`
+        self.write(s.as_bytes());
+        self.write(&[0xff]);
+    }
+
+    pub fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hashes the text of the tokens from `begin` up to `end`, after skipping the
+/// first `skip` ones, regardless of their spans and surrounding whitespace.
+pub(crate) fn of_tokens(begin: Cursor, end: Cursor, skip: usize) -> u64 {
+    // values implied by their keys, e.g. of flags, have no tokens at all
+    if begin == end {
+        return of_text("");
+    }
+    // tokens are hashed as they are written, which avoids collecting them into
+    // a string on the hot path of parsing
+    let mut h = HashWriter(Fingerprint::default());
+    let mut cursor = begin;
+    let mut i = 0;
+    while cursor != end {
+        let (tt, next) = match cursor.token_tree() {
+            Some(t) => t,
+            None => break,
+        };
+        if i > skip {
+            h.0.write(b" ");
+        }
+        if i >= skip {
+            write!(h, "{}", tt).unwrap();
+        }
+        i += 1;
+        cursor = next;
+    }
+    h.0.write(&[0xff]);
+    h.0.finish()
+}
+
+/// Hashes the text of the tokens in `s`, or `s` itself if it is not tokens.
+pub(crate) fn of_str(s: &str) -> u64 {
+    match s.parse::<TokenStream>() {
+        Ok(tokens) => of_text(&tokens.to_string()),
+        Err(_) => of_text(s),
+    }
+}
+
+fn of_text(s: &str) -> u64 {
+    let mut h = Fingerprint::default();
+    h.write_str(s);
+    h.finish()
+}
+
+/// Hashes the output written to it, no matter how it is split into pieces,
+/// unlike [`Fingerprint::write_str`].
+pub(crate) struct HashWriter(pub Fingerprint);
+
+impl fmt::Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+/// A compact alternative syntax of [`define_args!`], for grammars where an
+/// attribute per field is noisy.
+///
+/// Each argument is declared as `name: Type [options];`, whose values are
+/// parsed as expressions and supplied at most once unless declared otherwise:
+///
+/// - `flag` in place of the type declares a flag.
+/// - `required` and `multiple` tighten or relax the number of values.
+/// - Other options are passed to [`ArgAttrs`](crate::ArgAttrs), e.g.
+///   `is_token_tree` or `optional`.
+///
+/// Groups are declared as `group name [options] { a | b }`, where `exclusive`
+/// and `required` expand to `exclusive_group` and `required_any`, and other
+/// options name container checks taking the group.
+///
+/// ```
+/// # use syn::{Expr, Type};
+/// plap::grammar! {
+///     #[::derive(Debug)]
+///     pub MyAttr {
+///         arg1: Expr [required, multiple];
+///         arg2: flag;
+///         arg3: Type [is_token_tree];
+///         group grp1 [exclusive] { arg2 | arg3 }
+///     }
+/// }
+///
+/// # use plap::Args;
+/// let args = syn::parse::Parser::parse_str(MyAttr::parse, "arg1 = 1, arg1 = 2, arg2")?;
+/// assert_eq!(args.arg1.len(), 2);
+/// # syn::Result::Ok(())
+/// ```
+#[macro_export]
+macro_rules! grammar {
+    ($(#[::$attr:meta])* $vis:vis $name:ident { $($body:tt)* }) => {
+        $crate::grammar!(@munch [$(#[::$attr])*] [$vis $name] [] [] [] $($body)*);
+    };
+    // all declarations are collected,
+    (@munch [$($attr:tt)*] [$vis:vis $name:ident] [$($group:tt)*] [$($check:tt)*] [$({
+        [$($f_doc:literal)*] $f_name:ident [$($arg:ident)*] [$($f_check:ident)*] [$f_ty:ty]
+    })*]) => {
+        $crate::define_args! {
+            $($attr)*
+            #[group($($group)*)]
+            #[check($($check)*)]
+            $vis struct $name {$(
+                $(#[doc = $f_doc])*
+                #[arg($($arg),*)]
+                #[check($($f_check),*)]
+                $f_name: $crate::private::Arg<$f_ty>,
+            )*}
+        }
+    };
+    // so we turn groups into #[group(...)] and #[check(...)],
+    (@munch $attr:tt $head:tt [$($group:tt)*] $checks:tt $fields:tt
+        group $g:ident $([$($opt:ident),* $(,)?])? { $($member:ident)|* } $($rest:tt)*
+    ) => {
+        $crate::grammar!(@group $attr $head [$($group)* $g = [$($member),*],] $checks $fields
+            $g [$($($opt)*)?] $($rest)*);
+    };
+    (@group $attr:tt $head:tt $groups:tt [$($check:tt)*] $fields:tt
+        $g:ident [exclusive $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@group $attr $head $groups [$($check)* exclusive_group = $g,] $fields
+            $g [$($opt)*] $($rest)*);
+    };
+    (@group $attr:tt $head:tt $groups:tt [$($check:tt)*] $fields:tt
+        $g:ident [required $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@group $attr $head $groups [$($check)* required_any = $g,] $fields
+            $g [$($opt)*] $($rest)*);
+    };
+    (@group $attr:tt $head:tt $groups:tt [$($check:tt)*] $fields:tt
+        $g:ident [$other:ident $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@group $attr $head $groups [$($check)* $other = $g,] $fields
+            $g [$($opt)*] $($rest)*);
+    };
+    (@group $attr:tt $head:tt $groups:tt $checks:tt $fields:tt $g:ident [] $($rest:tt)*) => {
+        $crate::grammar!(@munch $attr $head $groups $checks $fields $($rest)*);
+    };
+    // and arguments into fields,
+    (@munch $attr:tt $head:tt $groups:tt $checks:tt $fields:tt
+        $(#[doc = $f_doc:literal])*
+        $f_name:ident: flag $([$($opt:ident),* $(,)?])?; $($rest:tt)*
+    ) => {
+        $crate::grammar!(@field $attr $head $groups $checks $fields
+            [$($f_doc)*] $f_name [is_flag] [] [exclusive] [$crate::private::syn::LitBool]
+            [$($($opt)*)?] $($rest)*);
+    };
+    (@munch $attr:tt $head:tt $groups:tt $checks:tt $fields:tt
+        $(#[doc = $f_doc:literal])*
+        $f_name:ident: $f_ty:ty $([$($opt:ident),* $(,)?])?; $($rest:tt)*
+    ) => {
+        $crate::grammar!(@field $attr $head $groups $checks $fields
+            [$($f_doc)*] $f_name [is_expr] [] [exclusive] [$f_ty]
+            [$($($opt)*)?] $($rest)*);
+    };
+    // whose options are checks or argument attributes
+    (@field $attr:tt $head:tt $groups:tt $checks:tt $fields:tt
+        $docs:tt $f_name:ident $args:tt [$($f_check:ident)*] $arity:tt $f_ty:tt
+        [required $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@field $attr $head $groups $checks $fields
+            $docs $f_name $args [$($f_check)* required] $arity $f_ty [$($opt)*] $($rest)*);
+    };
+    (@field $attr:tt $head:tt $groups:tt $checks:tt $fields:tt
+        $docs:tt $f_name:ident $args:tt $f_checks:tt $arity:tt $f_ty:tt
+        [multiple $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@field $attr $head $groups $checks $fields
+            $docs $f_name $args $f_checks [] $f_ty [$($opt)*] $($rest)*);
+    };
+    (@field $attr:tt $head:tt $groups:tt $checks:tt $fields:tt
+        $docs:tt $f_name:ident [$($arg:ident)*] $f_checks:tt $arity:tt $f_ty:tt
+        [$other:ident $($opt:ident)*] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@field $attr $head $groups $checks $fields
+            $docs $f_name [$($arg)* $other] $f_checks $arity $f_ty [$($opt)*] $($rest)*);
+    };
+    (@field $attr:tt $head:tt $groups:tt $checks:tt [$($field:tt)*]
+        $docs:tt $f_name:ident $args:tt [$($f_check:ident)*] [$($arity:ident)?] $f_ty:tt
+        [] $($rest:tt)*
+    ) => {
+        $crate::grammar!(@munch $attr $head $groups $checks [$($field)* {
+            $docs $f_name $args [$($arity)? $($f_check)*] $f_ty
+        }] $($rest)*);
+    };
+}
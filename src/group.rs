@@ -1,4 +1,70 @@
+use std::fmt;
+use std::marker::PhantomData;
+
 #[macro_export]
 macro_rules! group {
     ($($member:expr),* $(,)?) => ([$($member as &dyn ::plap::AnyArg,)*]);
 }
+
+/// A handle of a group declared with `#[group(...)]` on `A`, which is
+/// generated as an associated constant named after the group, e.g.
+/// `MyArgs::grp1`, so that misspelled or renamed groups fail to compile.
+///
+/// ```
+/// # use plap::{define_args, Arg, Args};
+/// # use syn::LitBool;
+/// define_args! {
+///     #[group(modes = [fast, safe])]
+///     #[check(exclusive_group = modes)]
+///     struct MyArgs {
+///         #[arg(is_flag)]
+///         fast: Arg<LitBool>,
+///         #[arg(is_flag)]
+///         safe: Arg<LitBool>,
+///     }
+/// }
+///
+/// assert_eq!(MyArgs::modes.name(), "modes");
+/// # #[cfg(feature = "checking")]
+/// assert_eq!(MyArgs::init().group_members(MyArgs::modes).len(), 2);
+/// ```
+pub struct GroupRef<A> {
+    name: &'static str,
+    index: usize,
+    marker: PhantomData<*const A>,
+}
+
+impl<A> GroupRef<A> {
+    /// **NOT PUBLIC APIS**
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, index: usize) -> Self {
+        Self {
+            name,
+            index,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the position of the group among those declared on `A`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<A> Clone for GroupRef<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for GroupRef<A> {}
+
+impl<A> fmt::Debug for GroupRef<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GroupRef").field(&self.name).finish()
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Write;
+
+use crate::arg::ArgAttrs;
+use crate::target::FmtPlacement;
+
+struct HelpEntry {
+    order: usize,
+    heading: Option<&'static str>,
+    usage: String,
+    doc: String,
+}
+
+#[derive(Default)]
+pub(crate) struct HelpWriter {
+    entries: Vec<HelpEntry>,
+}
+
+impl HelpWriter {
+    pub fn arg(&mut self, name: &str, attrs: &ArgAttrs, doc: &[&str]) {
+        let mut doc = doc.first().map(|s| s.trim().to_owned()).unwrap_or_default();
+        if let Some(targets) = attrs.get_only_on() {
+            if !doc.is_empty() {
+                doc.push(' ');
+            }
+            write!(doc, "({})", FmtPlacement(targets)).unwrap();
+        }
+        if let Some(gate) = attrs.get_requires_feature().filter(|g| !g.is_enabled()) {
+            if !doc.is_empty() {
+                doc.push(' ');
+            }
+            write!(doc, "(requires the `{}` feature)", gate.name()).unwrap();
+        }
+        self.entries.push(HelpEntry {
+            // unordered arguments follow ordered ones in declaration order
+            order: attrs.get_display_order().unwrap_or(usize::MAX),
+            heading: attrs.get_heading(),
+            usage: attrs.usage(name),
+            doc,
+        });
+    }
+
+    pub fn finish(mut self) -> String {
+        // the sort is stable, which keeps the declaration order of ties
+        self.entries.sort_by_key(|e| e.order);
+        let mut headings = Vec::<Option<&str>>::new();
+        for e in self.entries.iter() {
+            if !headings.contains(&e.heading) {
+                headings.push(e.heading);
+            }
+        }
+        // arguments without a heading always come first
+        headings.sort_by_key(|h| h.is_some());
+
+        let width = self.entries.iter().map(|e| e.usage.len()).max();
+        let mut s = String::new();
+        for heading in headings {
+            if let Some(heading) = heading {
+                if !s.is_empty() {
+                    s.push('\n');
+                }
+                writeln!(s, "{}:", heading).unwrap();
+            }
+            for e in self.entries.iter().filter(|e| e.heading == heading) {
+                if e.doc.is_empty() {
+                    writeln!(s, "  {}", e.usage).unwrap();
+                } else {
+                    let width = width.unwrap_or_default();
+                    writeln!(s, "  {:width$}  {}", e.usage, e.doc, width = width).unwrap();
+                }
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+
+use crate::code::ErrorCode;
+use crate::resolve::FromValue;
+
+/// A closed set of keywords accepted as values, usually declared with
+/// [`keywords!`](crate::keywords).
+pub trait Keywords: Copy + Sized + 'static {
+    /// Returns all keywords in declaration order.
+    fn all() -> &'static [Self];
+
+    /// Returns how the keyword is written, e.g. `static`.
+    fn name(self) -> &'static str;
+
+    /// Returns the keyword written as `name`.
+    fn from_name(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|k| k.name() == name)
+    }
+
+    /// Returns an error at `span` listing the possible values, where `found`
+    /// is what is written instead.
+    fn expected(span: Span, found: &str) -> syn::Error {
+        let mut msg = String::from("expected ");
+        let all = Self::all();
+        if all.len() > 1 {
+            msg.push_str("one of ");
+        }
+        for (i, k) in all.iter().enumerate() {
+            if i > 0 {
+                msg.push_str(if i + 1 == all.len() { " or " } else { ", " });
+            }
+            msg.push('`');
+            msg.push_str(k.name());
+            msg.push('`');
+        }
+        if !found.is_empty() {
+            msg.push_str(", found `");
+            msg.push_str(found);
+            msg.push('`');
+        }
+        syn::Error::new(span, ErrorCode::InvalidValue.message(msg))
+    }
+}
+
+/// A keyword of `K` which remembers where it is written, e.g. the value of
+/// `linkage = static`.
+///
+/// Values are compared without their spans.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyword<K> {
+    value: K,
+    span: Span,
+}
+
+impl<K: Keywords> Keyword<K> {
+    pub fn new(value: K, span: Span) -> Self {
+        Self { value, span }
+    }
+
+    pub fn value(&self) -> K {
+        self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the keyword with its span, which may be a Rust keyword, e.g.
+    /// `static`.
+    pub fn to_token_stream(&self) -> TokenStream {
+        TokenTree::from(Ident::new(self.value.name(), self.span)).into()
+    }
+}
+
+impl<K: Keywords> Parse for Keyword<K> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let ident = match Ident::parse_any(input) {
+            Ok(ident) => ident,
+            Err(_) => return Err(K::expected(span, "")),
+        };
+        let name = ident.to_string();
+        match K::from_name(&name) {
+            Some(value) => Ok(Self::new(value, ident.span())),
+            None => Err(K::expected(ident.span(), &name)),
+        }
+    }
+}
+
+impl<K: Keywords> fmt::Display for Keyword<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.value.name())
+    }
+}
+
+impl<K: PartialEq> PartialEq for Keyword<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<K: Eq> Eq for Keyword<K> {}
+
+impl<K: Keywords> FromValue<Keyword<K>> for K {
+    fn from_value(value: Keyword<K>) -> syn::Result<Self> {
+        Ok(value.value)
+    }
+}
+
+#[cfg(feature = "printing")]
+impl<K: Keywords> quote::ToTokens for Keyword<K> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.to_token_stream());
+    }
+}
+
+/// **NOT PUBLIC APIS**
+#[doc(hidden)]
+pub fn keyword_name(variant: &'static str) -> &'static str {
+    let name = variant.strip_prefix("r#").unwrap_or(variant);
+    name.strip_suffix('_').unwrap_or(name)
+}
+
+/// Declares an enum of keywords accepted as values, which implements
+/// [`Keywords`](crate::Keywords), [`Parse`](syn::parse::Parse), `Display` and
+/// `FromStr`.
+///
+/// Variants are written as their names, where a trailing `_` or a leading
+/// `r#` is stripped to spell Rust keywords. Values are parsed as
+/// [`Keyword`](crate::Keyword)s to keep their spans, and converted to the
+/// enum by [`FromValue`](crate::FromValue).
+///
+/// ```
+/// # use plap::{define_args, Arg, Args, Keyword};
+/// plap::keywords! {
+///     /// How a library is linked.
+///     pub Linkage { static_, dynamic }
+/// }
+///
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         linkage: Arg<Keyword<Linkage>>,
+///     }
+/// }
+///
+/// let args = syn::parse::Parser::parse_str(MyArgs::parse, "linkage = static")?;
+/// assert_eq!(args.linkage.entries()[0].value.value(), Linkage::static_);
+/// assert_eq!("dynamic".parse::<Linkage>()?.to_string(), "dynamic");
+///
+/// let err = syn::parse::Parser::parse_str(MyArgs::parse, "linkage = weak").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "expected one of `static` or `dynamic`, found `weak`",
+/// );
+/// # syn::Result::Ok(())
+/// ```
+#[macro_export]
+macro_rules! keywords {
+    ($(#[doc = $doc:literal])*
+    $(#[::$attr:meta])*
+    $vis:vis $name:ident {$(
+        $(#[doc = $v_doc:literal])*
+        $variant:ident
+    ),* $(,)?}) => {
+        $(#[doc = $doc])*
+        $(#[$attr])*
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        $vis enum $name {$(
+            $(#[doc = $v_doc])*
+            $variant,
+        )*}
+
+        impl $crate::private::Keywords for $name {
+            fn all() -> &'static [$name] {
+                &[$($name::$variant),*]
+            }
+
+            fn name(self) -> &'static str {
+                match self {$(
+                    $name::$variant => $crate::private::keyword_name(stringify!($variant)),
+                )*}
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str($crate::private::Keywords::name(*self))
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = $crate::private::syn::Error;
+
+            fn from_str(s: &str) -> $crate::private::syn::Result<$name> {
+                <$name as $crate::private::Keywords>::from_name(s).ok_or_else(|| {
+                    <$name as $crate::private::Keywords>::expected(
+                        $crate::private::Span::call_site(),
+                        s,
+                    )
+                })
+            }
+        }
+
+        impl $crate::private::syn::parse::Parse for $name {
+            fn parse(
+                input: $crate::private::syn::parse::ParseStream,
+            ) -> $crate::private::syn::Result<$name> {
+                input
+                    .parse::<$crate::private::Keyword<$name>>()
+                    .map(|k| k.value())
+            }
+        }
+    };
+}
@@ -1,29 +1,140 @@
+//! An argument parser for proc-macros.
+//!
+//! Parsing, schemas and help are always available, while checks are run by
+//! one of two engines selected by cargo features:
+//!
+//! - The slim engine, by default, only parses arguments, where [`Args::finish`]
+//!   resolves defaults and normalizes values.
+//! - The full engine, with the `full` feature, also runs the declared checks in
+//!   [`Args::finish`], and exports the `Checker` they run on.
+//!
+//! Code calling [`Args::finish`] builds against either engine unchanged.
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod arg;
+mod code;
+mod collector;
+#[cfg(feature = "checking")]
+mod constraints;
+#[cfg(feature = "attributes")]
+mod context;
 #[macro_use]
 mod define_args;
 #[cfg(feature = "checking")]
 mod checker;
+mod embedded;
 mod errors;
+mod fingerprint;
+#[macro_use]
+mod grammar;
 #[macro_use]
 mod group;
+mod help;
+#[macro_use]
+mod keyword;
+mod marker;
+#[cfg(feature = "perf-metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "perf-metrics")))]
+pub mod metrics;
+#[cfg(not(feature = "perf-metrics"))]
+mod metrics {
+    // a stand-in of the public module which measures nothing
+    pub(crate) enum Phase {
+        Parse,
+        Check,
+    }
+
+    pub(crate) fn measure<T>(
+        _: &'static str,
+        _: Phase,
+        _: proc_macro2::Span,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        f()
+    }
+}
+mod middleware;
+mod migration;
+#[cfg(feature = "attributes")]
+mod nested;
+pub mod normalize;
 mod parser;
+mod registry;
+mod resolve;
+mod schema;
+mod small_vec;
+mod spanned;
 #[cfg(feature = "string")]
 mod str;
+mod target;
+#[cfg(feature = "ui-gen")]
+mod ui_gen;
+pub mod validators;
 
-pub use arg::{Arg, ArgAttrs, ArgKind};
+pub use arg::{
+    Arg, ArgAttrs, ArgDelimiter, ArgKind, Case, Entry, FeatureGate, Flag, Keys, Number, Presence,
+    RangeBound, Validator, ValueSource,
+};
 #[cfg(feature = "checking")]
 pub use checker::{AnyArg, Checker};
+pub use code::{CodesShown, ErrorCode, ReportTarget};
+pub use collector::Collector;
+#[cfg(feature = "checking")]
+pub use constraints::{Constraints, Violation};
+#[cfg(feature = "attributes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "attributes")))]
+pub use context::{AttrMatch, ParseContext};
+#[cfg(feature = "string-parse")]
+pub use define_args::from_str;
 pub use define_args::{ArgEnum, Args};
-pub use errors::Errors;
-pub use parser::{Optional, Parser};
+pub use embedded::{Embedded, EmbeddedError, FromEmbedded};
+#[cfg(feature = "span-locations")]
+pub use errors::byte_ranges;
+pub use errors::{ErrorReport, Errors};
+pub use group::GroupRef;
+pub use keyword::{Keyword, Keywords};
+pub use marker::{is_parsed, mark_parsed};
+pub use middleware::{Middleware, Next, ParsedValue};
+pub use migration::{Migration, Suggestion};
+pub use parser::{Nested, Optional, Parser, PeekedKey, Separator};
+pub use registry::Registry;
+pub use resolve::{FromArg, FromValue};
+pub use schema::{assert_snapshot, ArgChange, Schema, SchemaDiff, Snippet};
+pub use spanned::{SpannedBool, SpannedIdent, SpannedPath};
+pub use target::{check_target, Target};
+#[cfg(feature = "ui-gen")]
+pub use ui_gen::{ui_cases, UiCase};
 
 pub type OptionalArg<T> = Arg<Optional<T>>;
 
+/// Re-exports the types and macros needed to define and consume arguments.
+///
+/// ```
+/// use plap::prelude::*;
+/// ```
+pub mod prelude {
+    #[cfg(feature = "attributes")]
+    pub use crate::{attr_name, AttrMatch, ParseContext};
+    pub use crate::{
+        define_args, feature, grammar, keywords, Arg, ArgAttrs, ArgDelimiter, ArgEnum, ArgKind,
+        Args, Collector, Embedded, EmbeddedError, Entry, ErrorCode, Errors, FeatureGate, Flag,
+        FromArg, FromEmbedded, FromValue, Keyword, Keywords, Middleware, Nested, Next, Optional,
+        OptionalArg, ParsedValue, Parser, PeekedKey, Presence, Registry, ReportTarget, Separator,
+        SpannedBool, SpannedIdent, SpannedPath, Target, ValueSource,
+    };
+    #[cfg(feature = "checking")]
+    pub use crate::{group, AnyArg, Checker, Constraints};
+}
+
 /// **NOT PUBLIC APIS**
 #[doc(hidden)]
 pub mod private {
+    pub use proc_macro2::{Ident, Span};
+    pub use syn;
+
+    pub use crate::fingerprint::Fingerprint;
+    pub use crate::keyword::keyword_name;
+    pub use crate::schema::SchemaWriter;
     pub use crate::*;
 
     pub mod arg {
@@ -44,8 +155,157 @@ pub mod private {
             parser.peek_key()
         }
 
-        pub fn is_key(key: &Ident, expected: &str) -> bool {
-            key == expected
+        pub fn key_name(attrs: &ArgAttrs, name: &'static str) -> &'static str {
+            attrs.get_rename().unwrap_or(name)
+        }
+
+        /// Finds the schema of `Arg<Nested<A>>` with
+        /// `(&probe).nested_schema()`, which resolves to `NestedSchema`
+        /// where it applies, and falls back to `NoNestedSchema` by
+        /// autoref otherwise.
+        pub struct Probe<T>(pub std::marker::PhantomData<T>);
+
+        pub trait NestedSchema {
+            fn nested_schema(&self) -> Option<String>;
+        }
+
+        impl<A: Args> NestedSchema for Probe<Arg<Nested<A>>> {
+            fn nested_schema(&self) -> Option<String> {
+                Some(A::schema())
+            }
+        }
+
+        pub trait NoNestedSchema {
+            fn nested_schema(&self) -> Option<String> {
+                None
+            }
+        }
+
+        impl<T> NoNestedSchema for &Probe<T> {}
+
+        pub fn new_arg<T>(attrs: &ArgAttrs, name: &'static str) -> Arg<T> {
+            let mut a = Arg::new(key_name(attrs, name));
+            a.set_docs_url(attrs.get_docs_url());
+            a.set_family(attrs.get_family(), 0);
+            a
+        }
+
+        fn variant_key(
+            attrs: &ArgAttrs,
+            name: &'static str,
+            rename_all: Option<Case>,
+        ) -> std::borrow::Cow<'static, str> {
+            match (attrs.get_rename(), rename_all) {
+                (Some(name), _) => name.into(),
+                (None, Some(case)) => crate::arg::to_case(name, case).into(),
+                _ => name.into(),
+            }
+        }
+
+        /// Returns the key of a field or variant as a name of [`Arg`], where
+        /// cased names are interned to live as long as the declared ones.
+        pub fn cased_name(
+            attrs: &ArgAttrs,
+            name: &'static str,
+            rename_all: Option<Case>,
+        ) -> &'static str {
+            thread_local! {
+                static NAMES: std::cell::RefCell<Vec<&'static str>> = Default::default();
+            }
+            match variant_key(attrs, name, rename_all) {
+                std::borrow::Cow::Borrowed(name) => name,
+                std::borrow::Cow::Owned(name) => NAMES.with(|names| {
+                    let mut names = names.borrow_mut();
+                    match names.iter().find(|n| **n == name) {
+                        Some(n) => n,
+                        None => {
+                            let n: &'static str = Box::leak(name.into_boxed_str());
+                            names.push(n);
+                            n
+                        }
+                    }
+                }),
+            }
+        }
+
+        /// The keys of the arguments of a type, built once per thread, so that
+        /// each key is looked up with a binary search rather than by building
+        /// the attributes of every argument and comparing them in turn, see
+        /// `benches/key_lookup.rs`.
+        ///
+        /// Keys are computed from attributes at runtime, e.g. by `rename`, so
+        /// the table cannot be a sorted `static`.
+        /// The keys of a schema, which `define_args!` builds lazily once per
+        /// thread rather than as a static table, since attributes are set by
+        /// builder calls and keys depend on `#[rename_all(...)]`, neither of
+        /// which a declarative macro can evaluate, let alone sort.
+        pub struct KeyTable {
+            attrs: Vec<ArgAttrs>,
+            // sorted by keys, where the first declared wins on duplicates
+            keys: Vec<(&'static str, usize)>,
+            // prefixes of families, in declaration order
+            families: Vec<(&'static str, usize)>,
+            // keys declared more than once, along with the indices of their
+            // arguments
+            duplicates: Vec<(&'static str, usize, usize)>,
+        }
+
+        impl KeyTable {
+            pub fn new(args: Vec<(&'static str, ArgAttrs)>) -> Self {
+                let mut keys = Vec::with_capacity(args.len());
+                let mut families = Vec::new();
+                let mut table = Vec::with_capacity(args.len());
+                for (i, (name, attrs)) in args.into_iter().enumerate() {
+                    match attrs.get_family() {
+                        Some(prefix) => families.push((prefix, i)),
+                        None => keys.push((key_name(&attrs, name), i)),
+                    }
+                    table.push(attrs);
+                }
+                // stable, so that the first declared comes first
+                keys.sort_by_key(|(key, _)| *key);
+                let duplicates = keys
+                    .windows(2)
+                    .filter(|w| w[0].0 == w[1].0)
+                    .map(|w| (w[0].0, w[0].1, w[1].1))
+                    .collect();
+                keys.dedup_by_key(|(key, _)| *key);
+                Self {
+                    attrs: table,
+                    keys,
+                    families,
+                    duplicates,
+                }
+            }
+
+            /// Returns a mistake for each key declared by more than one
+            /// argument, all but the first of which are never parsed, citing
+            /// them by their `fields`.
+            pub fn mistakes<'a>(&'a self, fields: &'a [&str]) -> impl 'a + Iterator<Item = String> {
+                self.duplicates.iter().map(move |&(key, first, other)| {
+                    format!(
+                        "`{}` is the key of both `{}` and `{}`, which is never parsed",
+                        key, fields[first], fields[other]
+                    )
+                })
+            }
+
+            /// Returns the index and attributes of the argument named by the
+            /// key cached by `parse_key`, where exact keys take precedence over
+            /// families wherever declared, see [`ArgAttrs::family`].
+            pub fn find(&self, parser: &Parser) -> Option<(usize, &ArgAttrs)> {
+                let key = parser.unprefixed_key()?;
+                let i = match self.keys.binary_search_by_key(&key, |(k, _)| *k) {
+                    Ok(i) => self.keys[i].1,
+                    Err(_) => {
+                        self.families
+                            .iter()
+                            .find(|(prefix, _)| parser.is_family_key(prefix))?
+                            .1
+                    }
+                };
+                Some((i, &self.attrs[i]))
+            }
         }
 
         pub fn parse_add_value<T>(
@@ -57,12 +317,79 @@ pub mod private {
         where
             T: syn::parse::Parse,
         {
+            // each key of a family is supplied at most once
+            if attrs.get_family().is_some() && a.entries().iter().any(|e| e.key == key) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    ErrorCode::TooManyValues
+                        .message(format_args!("`{}` is supplied more than once", key)),
+                ));
+            }
+            if attrs.get_family().is_some() {
+                a.set_family(attrs.get_family(), parser.prefix().len());
+            }
             // now we can move the cursor
             let span = parser.consume_next()?.unwrap();
-            a.add(key, parser.next_value(attrs)?);
+            let (delimiter, value) = parser.next_delimited_value_with(attrs, T::parse)?;
+            a.push(Entry {
+                delimiter,
+                value_span: parser.value_span().unwrap_or_else(|| key.span()),
+                fingerprint: parser.value_fingerprint().unwrap_or_default(),
+                position: parser.position(),
+                ..Entry::new(key, value)
+            });
             Ok(Some(span))
         }
 
+        /// Fails if whether `T` parses empty input disagrees with
+        /// [`ArgAttrs::accepts_empty`], which otherwise shows up as values
+        /// silently taking nothing, or keys failing without values.
+        pub fn check_empty<T: syn::parse::Parse>(
+            attrs: &ArgAttrs,
+            name: &str,
+        ) -> Result<(), String> {
+            let kind = attrs.get_kind();
+            if kind != ArgKind::Expr || attrs.get_optional() {
+                return Ok(());
+            }
+            let empty = syn::parse::Parser::parse2(T::parse, Default::default()).is_ok();
+            if empty && !attrs.get_accepts_empty() {
+                Err(format!(
+                    "`{}` parses empty values, declare it with `accepts_empty`",
+                    name
+                ))
+            } else if !empty && attrs.get_accepts_empty() {
+                Err(format!(
+                    "`{}` is declared with `accepts_empty`, but does not parse empty values",
+                    name
+                ))
+            } else {
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "checking")]
+        pub fn validate_rules<A: Args>(args: &A) -> Result<(), String> {
+            crate::checker::Checker::validate_rules(args)
+        }
+
+        pub fn join_mistakes(mut mistakes: Vec<String>) -> Result<(), String> {
+            // rules of merged arguments are also validated along with others
+            let mut i = 0;
+            while i < mistakes.len() {
+                if mistakes[..i].contains(&mistakes[i]) {
+                    mistakes.remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            if mistakes.is_empty() {
+                Ok(())
+            } else {
+                Err(mistakes.join("\n"))
+            }
+        }
+
         pub fn parse_value_into<T, U>(
             parser: &mut Parser,
             attrs: &ArgAttrs,
@@ -77,9 +404,134 @@ pub mod private {
             Ok(Some((key, variant(value))))
         }
 
+        pub fn parse_enum<E: ArgEnum>(input: syn::parse::ParseStream) -> syn::Result<E> {
+            let mut parser = Parser::new(input);
+            match E::parse_next(&mut parser)? {
+                Some((_, value)) => Ok(value),
+                None => Err(syn::Error::new(
+                    parser.span(),
+                    ErrorCode::UnknownArgument.message("unknown variant"),
+                )),
+            }
+        }
+
+        pub type BuildResult<T> = syn::Result<T>;
+
+        pub trait ArgValue {
+            type Value;
+        }
+
+        impl<T> ArgValue for Arg<T> {
+            type Value = T;
+        }
+
+        pub trait IntoValue<T> {
+            fn into_value(self) -> T;
+        }
+
+        impl<T> IntoValue<T> for T {
+            fn into_value(self) -> T {
+                self
+            }
+        }
+
+        impl IntoValue<syn::LitBool> for bool {
+            fn into_value(self) -> syn::LitBool {
+                syn::LitBool::new(self, Span::call_site())
+            }
+        }
+
+        pub trait FlagValue {
+            fn flag_value(&self) -> bool;
+        }
+
+        impl FlagValue for syn::LitBool {
+            fn flag_value(&self) -> bool {
+                self.value()
+            }
+        }
+
+        impl FlagValue for crate::SpannedBool {
+            fn flag_value(&self) -> bool {
+                self.value()
+            }
+        }
+
+        pub fn flag_value<T: FlagValue>(a: &Arg<T>) -> bool {
+            a.entries().last().map_or(false, |e| e.value.flag_value())
+        }
+
+        pub fn add_value<T>(a: &mut Arg<T>, value: impl IntoValue<T>) {
+            let key = Ident::new(a.name(), Span::call_site());
+            a.add(key, value.into_value());
+        }
+
+        pub fn env_fallback<T>(a: &mut Arg<T>, attrs: &ArgAttrs, span: Span) -> BuildResult<()>
+        where
+            T: syn::parse::Parse,
+        {
+            let var = match attrs.get_env() {
+                Some(var) if a.is_empty() => var,
+                _ => return Ok(()),
+            };
+            if let Ok(s) = std::env::var(var) {
+                let value = syn::parse_str(&s).map_err(|e| {
+                    syn::Error::new(
+                        span,
+                        format!("invalid value of environment variable `{}`: {}", var, e),
+                    )
+                })?;
+                let key = Ident::new(a.name(), Span::call_site());
+                a.push(Entry {
+                    source: ValueSource::Env(var),
+                    fingerprint: crate::fingerprint::of_str(&s),
+                    ..Entry::new(key, value)
+                });
+            }
+            Ok(())
+        }
+
+        pub fn check_placement<T>(
+            errors: &mut Errors,
+            a: &Arg<T>,
+            attrs: &ArgAttrs,
+            found: Target,
+        ) {
+            let allowed = match attrs.get_only_on() {
+                Some(allowed) if !allowed.contains(&found) => allowed,
+                _ => return,
+            };
+            let msg = crate::target::msg_not_allowed_on(a.name(), found, allowed);
+            let msg = ErrorCode::NotAllowed.message(msg);
+            for entry in a.entries() {
+                if entry.source == ValueSource::Supplied {
+                    errors.add_at(entry.span(), &msg);
+                }
+            }
+        }
+
         pub fn unknown_argument<T>(_key: Ident) -> ParseResult<T> {
             Ok(None)
         }
+
+        /// Adds `member` to `group` unless it is already a member, which would
+        /// duplicate diagnostics and is a mistake of the schema.
+        #[cfg(feature = "checking")]
+        pub fn join_group<'a>(
+            group: &mut Vec<&'a dyn AnyArg>,
+            name: &str,
+            member: &'a dyn AnyArg,
+        ) -> Result<(), String> {
+            if group.iter().any(|a| a.name() == member.name()) {
+                return Err(format!(
+                    "`{}` is a member of group `{}` more than once",
+                    member.name(),
+                    name
+                ));
+            }
+            group.push(member);
+            Ok(())
+        }
     }
 }
 
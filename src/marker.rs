@@ -0,0 +1,32 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use syn::parse::ParseStream;
+
+const MARKER: &str = "__plap_parsed";
+
+/// Marks the arguments of an attribute as parsed, for attribute macros which
+/// re-emit their own attribute, e.g. during partial expansion.
+///
+/// Arguments so marked are skipped by [`Parser::parse_all`], and are not
+/// checked by [`Args::finish`], so that the second expansion does not report
+/// the same errors again, nor any missing argument. Macros should also check
+/// [`is_parsed`] to leave the item untouched.
+///
+/// [`Parser::parse_all`]: crate::Parser::parse_all
+/// [`Args::finish`]: crate::Args::finish
+pub fn mark_parsed(args: TokenStream) -> TokenStream {
+    let mut tokens = TokenStream::from(TokenTree::from(Ident::new(MARKER, Span::call_site())));
+    tokens.extend(args);
+    tokens
+}
+
+/// Checks whether the arguments of an attribute are marked by [`mark_parsed`].
+pub fn is_parsed(args: &TokenStream) -> bool {
+    match args.clone().into_iter().next() {
+        Some(TokenTree::Ident(i)) => i == MARKER,
+        _ => false,
+    }
+}
+
+pub(crate) fn is_parsed_input(input: ParseStream) -> bool {
+    matches!(input.cursor().ident(), Some((i, _)) if i == MARKER)
+}
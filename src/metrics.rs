@@ -0,0 +1,151 @@
+//! Time spent parsing and checking arguments, to attribute compile-time
+//! regressions to specific attribute surfaces.
+//!
+//! Each call of [`Args::parse`] and [`Args::finish_at`] on the current thread,
+//! as well as each attribute parsed and checked by a [`Registry`], is recorded
+//! as a [`Sample`], which is passed to the [callback](set_callback) if any,
+//! printed to stderr if `PLAP_METRICS` is set, and aggregated per schema in
+//! the [`summary`].
+//!
+//! ```
+//! # use plap::{define_args, metrics, Arg, Args};
+//! # use syn::LitStr;
+//! define_args! {
+//!     struct MyArgs {
+//!         #[arg(is_expr)]
+//!         name: Arg<LitStr>,
+//!     }
+//! }
+//!
+//! metrics::reset();
+//! syn::parse::Parser::parse_str(MyArgs::parse, r#"name = "a""#)?.finish()?;
+//! let summary = metrics::summary();
+//! assert!(summary[0].schema.ends_with("MyArgs"));
+//! assert_eq!(summary[0].phase, metrics::Phase::Parse);
+//! assert_eq!(summary[0].count, 1);
+//! # syn::Result::Ok(())
+//! ```
+//!
+//! [`Args::parse`]: crate::Args::parse
+//! [`Args::finish_at`]: crate::Args::finish_at
+//! [`Registry`]: crate::Registry
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use proc_macro2::Span;
+
+thread_local! {
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics {
+        callback: None,
+        print: std::env::var_os("PLAP_METRICS").is_some(),
+        summary: Vec::new(),
+    });
+}
+
+struct Metrics {
+    callback: Option<fn(&Sample)>,
+    print: bool,
+    summary: Vec<Summary>,
+}
+
+/// What is measured of a sample.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Phase {
+    /// Parsing the tokens of an attribute.
+    Parse,
+    /// Resolving defaults, normalizing values and running checks.
+    Check,
+}
+
+/// A single measurement at an attribute site.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    /// The type name of the arguments, e.g. `my_crate::MyArgs`.
+    pub schema: &'static str,
+    pub phase: Phase,
+    /// Where the attribute is written, which for checks is the span given to
+    /// [`Args::finish_at`](crate::Args::finish_at), i.e. the call site for
+    /// [`Args::finish`](crate::Args::finish).
+    pub span: Span,
+    pub elapsed: Duration,
+}
+
+/// The samples of a schema and phase on the current thread, aggregated.
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub schema: &'static str,
+    pub phase: Phase,
+    pub count: usize,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// Sets a callback receiving each sample taken on the current thread.
+pub fn set_callback(f: Option<fn(&Sample)>) {
+    METRICS.with(|m| m.borrow_mut().callback = f);
+}
+
+/// Returns the samples taken on the current thread, aggregated per schema
+/// and phase, in the order first taken.
+pub fn summary() -> Vec<Summary> {
+    METRICS.with(|m| m.borrow().summary.clone())
+}
+
+/// Clears the [`summary`] of the current thread.
+pub fn reset() {
+    METRICS.with(|m| m.borrow_mut().summary.clear());
+}
+
+/// Runs `f`, recording how long it takes as a sample of `schema`.
+pub(crate) fn measure<T>(
+    schema: &'static str,
+    phase: Phase,
+    span: Span,
+    f: impl FnOnce() -> T,
+) -> T {
+    let start = Instant::now();
+    let output = f();
+    record(Sample {
+        schema,
+        phase,
+        span,
+        elapsed: start.elapsed(),
+    });
+    output
+}
+
+fn record(sample: Sample) {
+    let (print, callback) = METRICS.with(|m| {
+        let mut m = m.borrow_mut();
+        match m
+            .summary
+            .iter_mut()
+            .find(|m| m.schema == sample.schema && m.phase == sample.phase)
+        {
+            Some(m) => {
+                m.count += 1;
+                m.total += sample.elapsed;
+                m.max = m.max.max(sample.elapsed);
+            }
+            None => m.summary.push(Summary {
+                schema: sample.schema,
+                phase: sample.phase,
+                count: 1,
+                total: sample.elapsed,
+                max: sample.elapsed,
+            }),
+        }
+        (m.print, m.callback)
+    });
+    if print {
+        eprintln!(
+            "plap: {:?} `{}` at {:?} in {:?}",
+            sample.phase, sample.schema, sample.span, sample.elapsed
+        );
+    }
+    // called once the state is released, since it may read the summary
+    if let Some(f) = callback {
+        f(&sample);
+    }
+}
@@ -0,0 +1,109 @@
+use proc_macro2::Span;
+
+use crate::arg::{ArgAttrs, ArgDelimiter};
+use crate::parser::Parser;
+
+/// Hooks into a [`Parser`] to handle cross-cutting concerns, such as logging,
+/// renaming deprecated keys or timing arguments, without changing the
+/// arguments themselves, see [`Parser::with_middleware`].
+///
+/// Middlewares are stacked in the order added, where the first one added
+/// sees each key first and wraps all the others. They apply to arguments
+/// parsed by the parser they are added to, but not to
+/// [nested](crate::Nested) values, which are parsed on their own.
+///
+/// ```
+/// # use plap::{define_args, Arg, Args, Middleware, Parser};
+/// # use syn::LitStr;
+/// define_args! {
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         rename: Arg<LitStr>,
+///     }
+/// }
+///
+/// // accepts `name` as written by older versions
+/// struct Deprecated;
+///
+/// impl Middleware for Deprecated {
+///     fn map_key(&self, key: &mut String, _: proc_macro2::Span) {
+///         if key == "name" {
+///             *key = "rename".to_owned();
+///         }
+///     }
+/// }
+///
+/// let args = syn::parse::Parser::parse_str(
+///     |input: syn::parse::ParseStream| {
+///         let mut args = MyArgs::init();
+///         Parser::new(input)
+///             .with_middleware(Deprecated)
+///             .parse_all(&mut args)?;
+///         Ok(args)
+///     },
+///     r#"name = "a""#,
+/// )?;
+/// assert_eq!(args.rename.entries()[0].value.value(), "a");
+/// assert_eq!(args.rename.entries()[0].key, "name");
+/// # syn::Result::Ok(())
+/// ```
+pub trait Middleware {
+    /// Maps each key before it is matched against arguments, e.g. a
+    /// deprecated spelling to its replacement. `key` is as written, or as
+    /// mapped by earlier middlewares, and `span` is where it is written.
+    ///
+    /// Arguments still record the keys as written, so that errors point at
+    /// what users wrote.
+    fn map_key(&self, key: &mut String, span: Span) {
+        let _ = (key, span);
+    }
+
+    /// Wraps parsing each argument, which is done by running `next`, e.g. to
+    /// log or time it. The key has not been read yet.
+    fn parse_next<'a>(
+        &self,
+        parser: &mut Parser<'a>,
+        next: Next<'_, 'a, Option<Span>>,
+    ) -> syn::Result<Option<Span>> {
+        next.run(parser)
+    }
+
+    /// Wraps parsing the value of an argument whose key is just read, which is
+    /// done by running `next`. Once it returns,
+    /// [`Parser::value_span`] refers to the value.
+    fn next_value<'a>(
+        &self,
+        parser: &mut Parser<'a>,
+        attrs: &ArgAttrs,
+        next: Next<'_, 'a, ParsedValue>,
+    ) -> syn::Result<ParsedValue> {
+        let _ = attrs;
+        next.run(parser)
+    }
+}
+
+/// The rest of the middlewares and the parser itself, which are run at most
+/// once.
+pub struct Next<'n, 'a, R> {
+    pub(crate) f: &'n mut dyn FnMut(&mut Parser<'a>) -> syn::Result<R>,
+}
+
+impl<'a, R> Next<'_, 'a, R> {
+    pub fn run(self, parser: &mut Parser<'a>) -> syn::Result<R> {
+        (self.f)(parser)
+    }
+}
+
+/// A value parsed by [`Next::run`] in [`Middleware::next_value`], which cannot
+/// be created otherwise, so that middlewares cannot skip parsing values.
+#[derive(Debug)]
+pub struct ParsedValue {
+    pub(crate) delimiter: ArgDelimiter,
+}
+
+impl ParsedValue {
+    /// Returns how the value is delimited from its key.
+    pub fn delimiter(&self) -> ArgDelimiter {
+        self.delimiter
+    }
+}
@@ -0,0 +1,112 @@
+use proc_macro2::Span;
+use syn::parse::ParseStream;
+use syn::Token;
+
+use crate::arg::{ArgAttrs, ArgKind};
+use crate::define_args::Args;
+use crate::parser::Parser;
+
+/// A machine-applicable fix, which replaces the tokens from `span` through
+/// `end` with `replacement`.
+#[derive(Clone, Debug)]
+pub struct Suggestion {
+    pub span: Span,
+    pub end: Span,
+    pub message: String,
+    pub replacement: String,
+}
+
+/// Suggests how to upgrade arguments written for an older version of an
+/// [`Args`] type.
+///
+/// ```
+/// # use plap::{define_args, Arg, Migration};
+/// # use syn::{LitBool, LitStr};
+/// define_args! {
+///     struct NewArgs {
+///         #[arg(is_expr)]
+///         color: Arg<LitStr>,
+///         #[arg(is_flag)]
+///         quiet: Arg<LitBool>,
+///     }
+/// }
+///
+/// let migration = Migration::new().rename("colour", "color");
+/// let suggestions = syn::parse::Parser::parse_str(
+///     |input: syn::parse::ParseStream| migration.suggest::<NewArgs>(input),
+///     r#"colour = "red", quiet = true"#,
+/// )?;
+/// let messages = suggestions.iter().map(|s| &s.message).collect::<Vec<_>>();
+/// assert_eq!(
+///     messages,
+///     [
+///         "rename `colour` to `color`",
+///         "replace `quiet = true` with `quiet`"
+///     ],
+/// );
+/// # syn::Result::Ok(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Migration {
+    renames: Vec<(&'static str, &'static str)>,
+}
+
+impl Migration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that the argument `old` is renamed to `new`.
+    pub fn rename(mut self, old: &'static str, new: &'static str) -> Self {
+        self.renames.push((old, new));
+        self
+    }
+
+    /// Scans `input` without validating it, and returns suggestions to make it
+    /// conform to `A`. Values are skipped up to the next `,`.
+    pub fn suggest<A: Args>(&self, input: ParseStream) -> syn::Result<Vec<Suggestion>> {
+        let mut flags = Vec::new();
+        A::describe_args(&mut |name, attrs: &ArgAttrs, _| {
+            if attrs.get_kind() == ArgKind::Flag {
+                flags.push(name.to_owned());
+            }
+        });
+
+        let mut suggestions = Vec::new();
+        let mut parser = Parser::new(input);
+        while !parser.is_empty() {
+            let key = parser.next_key()?;
+            let mut name = key.to_string();
+            if let Some(&(old, new)) = self.renames.iter().find(|(old, _)| key == old) {
+                suggestions.push(Suggestion {
+                    span: key.span(),
+                    end: key.span(),
+                    message: format!("rename `{}` to `{}`", old, new),
+                    replacement: new.to_owned(),
+                });
+                name = new.to_owned();
+            }
+
+            if flags.contains(&name) && input.peek(Token![=]) && input.peek2(syn::LitBool) {
+                let fork = input.fork();
+                fork.parse::<Token![=]>()?;
+                let value = fork.parse::<syn::LitBool>()?;
+                if value.value() && (fork.is_empty() || fork.peek(Token![,])) {
+                    suggestions.push(Suggestion {
+                        span: key.span(),
+                        end: value.span,
+                        message: format!("replace `{} = true` with `{}`", key, name),
+                        replacement: name,
+                    });
+                }
+            }
+
+            // skip the value, which is to be validated once migrated
+            while !parser.is_empty() && !input.peek(Token![,]) {
+                parser.consume_next()?;
+            }
+            parser.consume_next()?;
+        }
+        Ok(suggestions)
+    }
+}
@@ -0,0 +1,35 @@
+use proc_macro2::{TokenStream, TokenTree};
+use syn::meta::ParseNestedMeta;
+use syn::parse::ParseStream;
+
+use crate::define_args::Args;
+use crate::parser::Parser;
+
+/// Parses the argument named by `meta` into `args`, consuming its value up to
+/// the next `,` as [`parse_nested_meta`](syn::Attribute::parse_nested_meta)
+/// expects.
+pub(crate) fn parse_nested_meta<A: Args>(args: &mut A, meta: ParseNestedMeta) -> syn::Result<()> {
+    let key = meta
+        .path
+        .get_ident()
+        .ok_or_else(|| meta.error("expected an identifier"))?;
+    let rest = meta.input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = TokenStream::new();
+        while let Some((tt, next)) = rest.token_tree() {
+            match &tt {
+                TokenTree::Punct(p) if p.as_char() == ',' => break,
+                _ => tokens.extend(Some(tt)),
+            }
+            rest = next;
+        }
+        Ok((tokens, rest))
+    })?;
+
+    let mut tokens = TokenStream::from(TokenTree::Ident(key.clone()));
+    tokens.extend(rest);
+    syn::parse::Parser::parse2(
+        |input: ParseStream| Parser::new(input).parse_all(args),
+        tokens,
+    )
+}
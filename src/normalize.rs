@@ -0,0 +1,156 @@
+//! Ready-made passes which [normalize](crate::Args::normalize) the values of an
+//! argument once parsed, declared as `#[normalize(...)]` on fields of
+//! [`define_args!`](crate::define_args).
+//!
+//! Passes run in declaration order after defaults are resolved and before
+//! checks, so that checks see the normalized values.
+//!
+//! ```
+//! # use plap::{define_args, Arg};
+//! # use syn::LitStr;
+//! define_args! {
+//!     #[::derive(Debug)]
+//!     struct MyArgs {
+//!         #[arg(is_expr)]
+//!         #[normalize(lowercase, dedup, sort_by_key = LitStr::value)]
+//!         name: Arg<LitStr>,
+//!     }
+//! }
+//!
+//! # use plap::Args;
+//! let args =
+//!     syn::parse::Parser::parse_str(MyArgs::parse, r#"name = "b", name = "A", name = "a""#)?
+//!         .finish()?;
+//! let names = args
+//!     .name
+//!     .entries()
+//!     .iter()
+//!     .map(|e| e.value.value())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(names, ["a", "b"]);
+//! # syn::Result::Ok(())
+//! ```
+
+use proc_macro2::Ident;
+use syn::LitStr;
+
+use crate::arg::{Arg, Entry};
+use crate::spanned::SpannedIdent;
+
+/// Drops values equal to an earlier value, keeping the first one. Values
+/// without a [fingerprint](crate::Entry::fingerprint), e.g. defaults, are
+/// always kept.
+///
+/// Fingerprints only rule out values quickly, which are then compared with
+/// [`PartialEq`], so that `syn` types need its `extra-traits` feature.
+pub fn dedup<T: PartialEq>(arg: &mut Arg<T>) {
+    arg.map_entries(|entries| {
+        let mut kept = Vec::<Entry<T>>::with_capacity(entries.len());
+        for e in entries {
+            let seen = e.fingerprint != 0
+                && kept
+                    .iter()
+                    .any(|k| k.fingerprint == e.fingerprint && k.value == e.value);
+            if !seen {
+                kept.push(e);
+            }
+        }
+        kept
+    });
+}
+
+/// Sorts values in ascending order, keeping equal values in the order they are
+/// supplied.
+pub fn sort<T: Ord>(arg: &mut Arg<T>) {
+    arg.map_entries(|mut entries| {
+        entries.sort_by(|a, b| a.value.cmp(&b.value));
+        entries
+    });
+}
+
+/// Like [`sort`], but compares the keys returned by `key`, e.g.
+/// `sort_by_key = LitStr::value`.
+pub fn sort_by_key<T, K: Ord>(arg: &mut Arg<T>, key: fn(&T) -> K) {
+    arg.map_entries(|mut entries| {
+        entries.sort_by_key(|e| key(&e.value));
+        entries
+    });
+}
+
+/// Converts values to lowercase, keeping their spans. Fingerprints are updated
+/// to match, so that [`dedup`] afterwards ignores case.
+pub fn lowercase<T: Lowercase>(arg: &mut Arg<T>) {
+    arg.map_entries(|entries| {
+        entries
+            .into_iter()
+            .map(|mut e| {
+                e.value = e.value.to_lowercase();
+                if e.fingerprint != 0 {
+                    e.fingerprint = crate::fingerprint::of_str(&e.value.text());
+                }
+                e
+            })
+            .collect()
+    });
+}
+
+/// Runs a custom pass, e.g. `with = my_pass`.
+pub fn with<T>(arg: &mut Arg<T>, f: fn(&mut Arg<T>)) {
+    f(arg);
+}
+
+/// Values which can be converted to lowercase by [`lowercase`].
+pub trait Lowercase {
+    fn to_lowercase(&self) -> Self;
+
+    /// Returns how the value is written, e.g. `"a"` for a string literal.
+    fn text(&self) -> String;
+}
+
+impl Lowercase for String {
+    fn to_lowercase(&self) -> Self {
+        str::to_lowercase(self)
+    }
+
+    fn text(&self) -> String {
+        self.clone()
+    }
+}
+
+impl Lowercase for LitStr {
+    fn to_lowercase(&self) -> Self {
+        LitStr::new(&self.value().to_lowercase(), self.span())
+    }
+
+    fn text(&self) -> String {
+        self.token().to_string()
+    }
+}
+
+impl Lowercase for Ident {
+    fn to_lowercase(&self) -> Self {
+        let name = self.to_string();
+        match name.strip_prefix("r#").map(str::to_lowercase) {
+            // keywords which cannot be raw, e.g. `r#Crate` to `crate`
+            Some(name) if matches!(name.as_str(), "crate" | "self" | "super") => {
+                Ident::new(&name, self.span())
+            }
+            Some(name) => Ident::new_raw(&name, self.span()),
+            None => Ident::new(&name.to_lowercase(), self.span()),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Lowercase for SpannedIdent {
+    fn to_lowercase(&self) -> Self {
+        SpannedIdent::new(self.ident().to_lowercase())
+    }
+
+    fn text(&self) -> String {
+        self.to_string()
+    }
+}
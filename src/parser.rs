@@ -1,18 +1,195 @@
-use std::fmt;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::ops::RangeBounds;
+use std::rc::Rc;
 
-use proc_macro2::{Ident, Span};
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use syn::buffer::Cursor;
+use syn::parse::{Lookahead1, Parse, ParseStream, Peek};
+use syn::punctuated::Punctuated;
 use syn::{parenthesized, LitStr, Token};
 
-use crate::arg::{ArgAttrs, ArgKind};
+use crate::arg::{ArgAttrs, ArgDelimiter, ArgKind, FmtRange, Number};
+use crate::code::ErrorCode;
+use crate::middleware::{Middleware, Next, ParsedValue};
+
+const DEFAULT_MAX_DEPTH: usize = 128;
 
 pub struct Parser<'a> {
     input: ParseStream<'a>,
+    prefix: String,
+    // the last seen key, reused to avoid allocations when comparing keys
+    key: String,
+    key_span: Option<Span>,
+    value_span: Option<Span>,
+    value_fingerprint: Option<u64>,
+    separator: Separator,
+    error_limit: Option<usize>,
+    fail_fast: bool,
+    max_depth: usize,
+    // whether codes are prefixed to messages, as set on the thread by default
+    codes: Option<bool>,
+    context: HashMap<TypeId, Box<dyn Any>>,
+    // features opted in with `unstable(...)`
+    unstable: Vec<String>,
+    // features declared with `ArgAttrs::unstable`, if known
+    unstable_features: Option<Vec<&'static str>>,
+    // keywords tried at the current argument, see `next_keyword`
+    lookahead: Option<Lookahead1<'a>>,
+    parsed_count: usize,
+    // where each argument parsed so far begins, along with its position, only
+    // to be debugged
+    parsed: Vec<(Cursor<'a>, usize)>,
+    // errors recovered from so far by `parse_all_with`
+    errors: crate::errors::Errors,
+    // the index of the argument being parsed
+    position: usize,
+    stopped_at: Option<Span>,
+    // whether the input is marked parsed, see `mark_parsed`
+    skipped: bool,
+    // shared to be called while the parser is borrowed by one of them
+    middlewares: Vec<Rc<dyn Middleware>>,
+}
+
+/// Separates arguments from each other.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Separator {
+    /// A punctuation character, such as `,` (the default) or `;`.
+    Punct(char),
+    /// Only whitespace, where each argument begins with a key. Verbatim values
+    /// then take a single token tree.
+    Whitespace,
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Separator::Punct(',')
+    }
+}
+
+/// Shows the state of parsing, including the keys parsed along with their
+/// positions, the errors recovered from so far and the tokens yet to be
+/// parsed, without moving the cursor.
+impl fmt::Debug for Parser<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parsed = self
+            .parsed
+            .iter()
+            .map(|&(cursor, position)| {
+                let key = cursor.token_tree().map(|(tt, _)| tt.to_string());
+                (key.unwrap_or_default(), position)
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("Parser")
+            .field("prefix", &self.prefix)
+            .field("last_key", &self.key)
+            .field("parsed", &parsed)
+            .field("parsed_count", &self.parsed_count)
+            .field("stopped_at", &self.stopped_at)
+            .field("unstable_features", &self.unstable_features)
+            .field("errors", &self.errors.messages().collect::<Vec<_>>())
+            .field("remaining", &self.input.cursor().token_stream().to_string())
+            .finish()
+    }
 }
 
 impl<'a> Parser<'a> {
     pub fn new(input: ParseStream<'a>) -> Self {
-        Self { input }
+        Self {
+            input,
+            prefix: String::new(),
+            key: String::new(),
+            key_span: None,
+            value_span: None,
+            value_fingerprint: None,
+            separator: Separator::default(),
+            error_limit: None,
+            fail_fast: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            codes: None,
+            context: HashMap::new(),
+            unstable: Vec::new(),
+            unstable_features: None,
+            lookahead: None,
+            parsed_count: 0,
+            parsed: Vec::new(),
+            errors: crate::errors::Errors::default(),
+            position: 0,
+            stopped_at: None,
+            skipped: false,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Stores `value` for custom parsing logic to read with
+    /// [`context`](Self::context), replacing any value of the same type.
+    pub fn insert_context<T: Any>(&mut self, value: T) -> &mut Self {
+        self.context.insert(TypeId::of::<T>(), Box::new(value));
+        self
+    }
+
+    /// Returns the value of type `T` stored by
+    /// [`insert_context`](Self::insert_context).
+    pub fn context<T: Any>(&self) -> Option<&T> {
+        self.context
+            .get(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_ref())
+    }
+
+    pub fn context_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.context
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_mut())
+    }
+
+    /// Separates arguments with `separator` instead of `,`.
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Reports at most `limit` errors in detail when recovering from errors in
+    /// [`parse_all_with`](Self::parse_all_with), followed by a summary like
+    /// `and 3 more errors`.
+    pub fn with_error_limit(mut self, limit: usize) -> Self {
+        self.error_limit = Some(limit);
+        self
+    }
+
+    /// Stops at the first error in [`parse_all_with`](Self::parse_all_with)
+    /// instead of recovering from it, and skips validating the ranges and
+    /// suffixes of literals, e.g. for a quick yes or no in speculative parsing.
+    pub fn with_fail_fast(mut self, yes: bool) -> Self {
+        self.fail_fast = yes;
+        self
+    }
+
+    /// Rejects values with groups nested deeper than `depth`, which is 128 by
+    /// default, before they are parsed, so that untrusted input cannot
+    /// overflow the stack of recursive parsers such as that of `syn::Expr`.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets whether codes are prefixed to the messages of errors reported
+    /// while parsing arguments, which otherwise follows
+    /// [`ErrorCode::is_shown`].
+    pub fn with_codes(mut self, yes: bool) -> Self {
+        self.codes = Some(yes);
+        self
+    }
+
+    /// Stacks `middleware` on the middlewares added before, see
+    /// [`Middleware`].
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Rc::new(middleware));
+        self
+    }
+
+    pub fn separator(&self) -> Separator {
+        self.separator
     }
 
     pub fn input(&self) -> ParseStream<'a> {
@@ -27,23 +204,176 @@ impl<'a> Parser<'a> {
         self.input.is_empty()
     }
 
+    /// Checks whether the cursor is at the end of an argument, i.e. the
+    /// [separator](Self::separator), or the next key if it is whitespace.
     pub fn is_eoa(&self) -> bool {
-        self.input.peek(Token![,]) || self.input.is_empty()
+        match self.separator {
+            Separator::Punct(_) => self.is_separator() || self.is_empty(),
+            Separator::Whitespace => self.input.cursor().ident().is_some() || self.is_empty(),
+        }
+    }
+
+    fn is_separator(&self) -> bool {
+        match self.separator {
+            Separator::Punct(ch) => matches!(
+                self.input.cursor().punct(),
+                Some((p, _)) if p.as_char() == ch
+            ),
+            Separator::Whitespace => false,
+        }
+    }
+
+    fn expected_separator(&self) -> syn::Error {
+        match self.separator {
+            Separator::Punct(ch) => self.input.error(format!("expected a `{}`", ch)),
+            Separator::Whitespace => self.input.error("expected an identifier"),
+        }
     }
 
     pub fn next_key(&mut self) -> syn::Result<Ident> {
-        self.input.parse::<Option<Ident>>().and_then(|i| match i {
+        let key = self.input.parse::<Option<Ident>>().and_then(|i| match i {
             Some(i) => Ok(i),
             None => Err(self.input.error("expected an identifier")),
-        })
+        })?;
+        self.set_key(&key);
+        Ok(key)
+    }
+
+    /// Parses the next key if it is `keyword`, as declared by
+    /// [`syn::custom_keyword!`], to reuse existing keyword modules as keys.
+    ///
+    /// Keywords tried but not matched are listed in the error if the argument
+    /// turns out unknown, e.g. ``expected `rename` or `skip` ``.
+    ///
+    /// ```
+    /// # use plap::Parser;
+    /// mod kw {
+    ///     syn::custom_keyword!(rename);
+    ///     syn::custom_keyword!(skip);
+    /// }
+    ///
+    /// let err = syn::parse::Parser::parse_str(
+    ///     |input: syn::parse::ParseStream| {
+    ///         Parser::new(input).parse_all_with(|parser| {
+    ///             if let Some(key) = parser.next_keyword(kw::skip)? {
+    ///                 return Ok(Some(key.span()));
+    ///             }
+    ///             Ok(parser.next_keyword(kw::rename)?.map(|key| key.span()))
+    ///         })
+    ///     },
+    ///     "skip, other",
+    /// )
+    /// .unwrap_err();
+    /// assert_eq!(err.to_string(), "expected `skip` or `rename`");
+    /// ```
+    pub fn next_keyword<K: Peek>(&mut self, keyword: K) -> syn::Result<Option<Ident>> {
+        let input = self.input;
+        let lookahead = self.lookahead.get_or_insert_with(|| input.lookahead1());
+        if !lookahead.peek(keyword) {
+            return Ok(None);
+        }
+        self.lookahead = None;
+        self.next_key().map(Some)
     }
 
+    /// Returns the next key without moving the cursor.
+    ///
+    /// The key must then be either consumed with
+    /// [`consume_next`](Self::consume_next) or left to the caller, otherwise
+    /// parsing goes out of sync. Prefer [`peek_next`](Self::peek_next), which
+    /// enforces this.
     pub fn peek_key(&mut self) -> syn::Result<Ident> {
-        self.input
+        let (key, _) = self
+            .input
             .cursor()
             .ident()
-            .ok_or_else(|| self.input.error("expected an identifier"))
-            .map(|(i, _)| i)
+            .ok_or_else(|| self.input.error("expected an identifier"))?;
+        self.set_key(&key);
+        Ok(key)
+    }
+
+    /// Peeks the next key, which must be either
+    /// [accepted](PeekedKey::accept) or [rejected](PeekedKey::reject).
+    ///
+    /// ```
+    /// # use plap::{ArgAttrs, Parser};
+    /// # use syn::LitInt;
+    /// # fn parse_next(parser: &mut Parser) -> syn::Result<Option<LitInt>> {
+    /// let peeked = parser.peek_next()?;
+    /// if !peeked.is("width") {
+    ///     peeked.reject();
+    ///     return Ok(None);
+    /// }
+    /// let (_, parser) = peeked.accept()?;
+    /// parser.next_value(&ArgAttrs::default()).map(Some)
+    /// # }
+    /// ```
+    pub fn peek_next<'p>(&'p mut self) -> syn::Result<PeekedKey<'p, 'a>> {
+        let key = self.peek_key()?;
+        Ok(PeekedKey {
+            parser: Some(self),
+            key,
+        })
+    }
+
+    fn set_key(&mut self, key: &Ident) {
+        self.key.clear();
+        write!(self.key, "{}", key).unwrap();
+        self.key_span = Some(key.span());
+        for m in self.middlewares.iter() {
+            m.map_key(&mut self.key, key.span());
+        }
+    }
+
+    /// Returns the prefix stripped from keys before they are dispatched to a
+    /// merged argument set, see
+    /// [`parse_next_prefixed`](Self::parse_next_prefixed).
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the last [peeked key](Self::peek_key) with the current
+    /// [`prefix`](Self::prefix) removed, or `None` if it lacks the prefix.
+    pub(crate) fn unprefixed_key(&self) -> Option<&str> {
+        self.key.strip_prefix(self.prefix.as_str())
+    }
+
+    /// Checks whether the last [peeked key](Self::peek_key) names the argument
+    /// `expected` once the current [`prefix`](Self::prefix) is removed.
+    pub fn is_key(&self, expected: &str) -> bool {
+        self.unprefixed_key() == Some(expected)
+    }
+
+    /// Checks whether the last [peeked key](Self::peek_key) belongs to the
+    /// family of keys starting with `prefix`, e.g. `on_create` to `on_`, once
+    /// the current [`prefix`](Self::prefix) is removed.
+    pub fn is_family_key(&self, prefix: &str) -> bool {
+        self.key
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|key| key.strip_prefix(prefix))
+            .map_or(false, is_ident_start)
+    }
+
+    /// Parses the next argument into `args`, which only sees keys starting
+    /// with `prefix`. The prefix is stripped before keys are matched, so that
+    /// argument sets with overlapping names can be composed safely.
+    pub fn parse_next_prefixed<A>(
+        &mut self,
+        prefix: &str,
+        args: &mut A,
+    ) -> syn::Result<Option<Span>>
+    where
+        A: crate::define_args::Args,
+    {
+        self.peek_key()?;
+        let len = self.prefix.len();
+        self.prefix.push_str(prefix);
+        let res = match self.key.strip_prefix(self.prefix.as_str()) {
+            Some(rest) if is_ident_start(rest) => args.parse_next(self),
+            _ => Ok(None),
+        };
+        self.prefix.truncate(len);
+        res
     }
 
     pub fn next_value<T: Parse>(&mut self, attrs: &ArgAttrs) -> syn::Result<T> {
@@ -55,113 +385,850 @@ impl<'a> Parser<'a> {
         attrs: &ArgAttrs,
         f: impl FnOnce(ParseStream) -> syn::Result<T>,
     ) -> syn::Result<T> {
+        self.next_delimited_value_with(attrs, f).map(|(_, v)| v)
+    }
+
+    /// Parses the next value like [`next_value_with`](Self::next_value_with),
+    /// and also returns how the value was delimited from its key.
+    pub fn next_delimited_value_with<T>(
+        &mut self,
+        attrs: &ArgAttrs,
+        f: impl FnOnce(ParseStream) -> syn::Result<T>,
+    ) -> syn::Result<(ArgDelimiter, T)> {
+        if self.middlewares.is_empty() {
+            return self.next_delimited_value_unwrapped(attrs, f);
+        }
+        let mut f = Some(f);
+        let mut value = None;
+        let parsed = self.next_value_through(0, attrs, &mut |parser| {
+            // each `Next` runs at most once, and so does this
+            let (delimiter, v) = parser.next_delimited_value_unwrapped(attrs, f.take().unwrap())?;
+            value = Some(v);
+            Ok(ParsedValue { delimiter })
+        })?;
+        let value = value.expect("a middleware returned a value not parsed by `next`");
+        Ok((parsed.delimiter, value))
+    }
+
+    /// Parses the next value with `f` through the middlewares from the `i`-th
+    /// one.
+    fn next_value_through(
+        &mut self,
+        i: usize,
+        attrs: &ArgAttrs,
+        f: &mut dyn FnMut(&mut Self) -> syn::Result<ParsedValue>,
+    ) -> syn::Result<ParsedValue> {
+        match self.middlewares.get(i).cloned() {
+            Some(m) => m.next_value(
+                self,
+                attrs,
+                Next {
+                    f: &mut |parser| parser.next_value_through(i + 1, attrs, f),
+                },
+            ),
+            None => f(self),
+        }
+    }
+
+    fn next_delimited_value_unwrapped<T>(
+        &mut self,
+        attrs: &ArgAttrs,
+        f: impl FnOnce(ParseStream) -> syn::Result<T>,
+    ) -> syn::Result<(ArgDelimiter, T)> {
+        let begin = self.input.cursor();
+        let (delimiter, value) = self
+            .parse_delimited_value(attrs, f)
+            .map_err(|e| crate::code::error_with_docs(e, attrs.get_docs_url()))?;
+        // the span of `=` is not a part of the value
+        let skip = usize::from(delimiter == ArgDelimiter::Eq);
+        self.value_span = match join_spans(begin, self.input.cursor(), skip) {
+            Some(span) => Some(span),
+            None => Some(self.implied_span()),
+        };
+        self.value_fingerprint = Some(crate::fingerprint::of_tokens(
+            begin,
+            self.input.cursor(),
+            skip,
+        ));
+        Ok((delimiter, value))
+    }
+
+    /// Returns the span of the last parsed value, or of its key if the value
+    /// is implied, e.g. by a flag.
+    ///
+    /// The span covers all tokens of the value where spans can be joined, i.e.
+    /// on nightly compilers and outside of proc-macros, and falls back to the
+    /// first token elsewhere.
+    pub fn value_span(&self) -> Option<Span> {
+        self.value_span
+    }
+
+    /// Returns a hash of the token text of the last parsed value, which only
+    /// changes with the tokens rather than their spans or whitespace, see
+    /// [`Args::fingerprint`](crate::Args::fingerprint).
+    pub fn value_fingerprint(&self) -> Option<u64> {
+        self.value_fingerprint
+    }
+
+    fn implied_span(&self) -> Span {
+        self.key_span.unwrap_or_else(|| self.span())
+    }
+
+    fn parse_delimited_value<T>(
+        &mut self,
+        attrs: &ArgAttrs,
+        f: impl FnOnce(ParseStream) -> syn::Result<T>,
+    ) -> syn::Result<(ArgDelimiter, T)> {
+        if let Some(gate) = attrs.get_requires_feature().filter(|g| !g.is_enabled()) {
+            return Err(syn::Error::new(
+                self.implied_span(),
+                ErrorCode::FeatureDisabled.message(format_args!(
+                    "`{}` requires the `{}` feature of `{}`, enable it to use `{}`",
+                    self.key,
+                    gate.name(),
+                    gate.krate(),
+                    self.key
+                )),
+            ));
+        }
+        if let Some(feature) = attrs.get_unstable() {
+            if !self.unstable.iter().any(|f| f == feature) {
+                return Err(syn::Error::new(
+                    self.implied_span(),
+                    ErrorCode::Unstable.message(format_args!(
+                        "`{}` is unstable, opt in with `unstable({})` before it",
+                        self.key, feature
+                    )),
+                ));
+            }
+        }
+
         let input = self.input;
         let kind = attrs.get_kind();
+        let fail_fast = self.fail_fast;
+        let (separator, max_depth) = (self.separator, self.max_depth);
+        // values after `=` end at the next separator, and others at the end of
+        // their tokens
+        let f = |input: ParseStream, after_eq: bool| {
+            check_depth(input, Some(separator).filter(|_| after_eq), max_depth)?;
+            if !fail_fast {
+                check_literal(attrs, input)?;
+            }
+            if let Some(validate) = attrs.get_validator() {
+                validate(&input.fork())?;
+            }
+            f(input)
+        };
 
         if self.is_eoa() {
+            // implied values are spanned by their keys
+            let span = self.implied_span();
+            let empty = attrs.get_optional() || attrs.get_accepts_empty();
+            let implied = matches!(kind, ArgKind::Flag) || empty;
+            if implied && attrs.get_explicit() && kind != ArgKind::Help {
+                let example = match kind {
+                    ArgKind::Flag => format!("{} = true", self.key),
+                    _ => attrs
+                        .usage(&self.key)
+                        .replace("[ = ", " = ")
+                        .replace(']', ""),
+                };
+                return Err(syn::Error::new(
+                    span,
+                    ErrorCode::MissingValue.message(format_args!(
+                        "`{}` requires an explicit value, e.g. `{}`",
+                        self.key, example
+                    )),
+                ));
+            }
             match kind {
-                ArgKind::Expr | ArgKind::TokenTree => {
-                    if attrs.get_optional() {
-                        return parse_value_from_str("", f);
-                    }
+                ArgKind::Expr | ArgKind::TokenTree | ArgKind::Verbatim if empty => {
+                    return parse_implied_value(span, false, |i| f(i, false))
+                        .map(|v| (ArgDelimiter::Omitted, v));
+                }
+                ArgKind::Flag => {
+                    return parse_implied_value(span, true, |i| f(i, false))
+                        .map(|v| (ArgDelimiter::Omitted, v));
                 }
-                ArgKind::Flag => return parse_value_from_str("true", f),
                 _ => {}
             }
         }
 
         match kind {
             ArgKind::Expr | ArgKind::Flag => {
-                if input.parse::<Option<Token![=]>>()?.is_some() && !self.is_eoa() {
-                    f(input)
+                if let Some(eq) = self.next_eq(attrs)? {
+                    check_delimiter(attrs, ArgDelimiter::Eq, eq)?;
+                    let begin = input.cursor();
+                    let value = f(input, true)?;
+                    // types parsing empty input, e.g. `Visibility`, may take
+                    // nothing here and leave the value to be read as a key
+                    if input.cursor() == begin {
+                        return Err(input.error(ErrorCode::MissingValue.message(format_args!(
+                            "expected a value for `{}` after `=`",
+                            self.key
+                        ))));
+                    }
+                    Ok((ArgDelimiter::Eq, value))
                 } else if input.peek(syn::token::Paren) {
+                    check_delimiter(attrs, ArgDelimiter::Paren, input.span())?;
                     let content;
                     parenthesized!(content in input);
-                    f(&content)
+                    f(&content, false).map(|v| (ArgDelimiter::Paren, v))
                 } else {
-                    Err(input.error("expected `= <value>` or `(<value>)`"))
+                    Err(input.error(
+                        ErrorCode::MissingValue.message("expected `= <value>` or `(<value>)`"),
+                    ))
                 }
             }
             ArgKind::TokenTree => {
-                if input.parse::<Option<Token![=]>>()?.is_some() {
+                if let Some(eq) = self.next_eq(attrs)? {
+                    check_delimiter(attrs, ArgDelimiter::Eq, eq)?;
+                    if !input.peek(syn::LitStr) {
+                        return self
+                            .parse_unquoted_value(|i| f(i, false))
+                            .map(|v| (ArgDelimiter::Eq, v));
+                    }
                     let content = input.parse::<syn::LitStr>()?;
-                    parse_value_from_literal(content, f)
+                    parse_value_from_literal(content, |i| f(i, false))
+                        .map(|v| (ArgDelimiter::Eq, v))
+                } else if input.peek(syn::token::Paren) {
+                    check_delimiter(attrs, ArgDelimiter::Paren, input.span())?;
+                    let content;
+                    parenthesized!(content in input);
+                    f(&content, false).map(|v| (ArgDelimiter::Paren, v))
+                } else {
+                    Err(input.error(
+                        ErrorCode::MissingValue.message("expected `= \"<value>\"` or `(<value>)`"),
+                    ))
+                }
+            }
+            ArgKind::Help => parse_implied_value(self.implied_span(), false, |i| f(i, false))
+                .map(|v| (ArgDelimiter::Omitted, v)),
+            ArgKind::Verbatim => {
+                if let Some(eq) = self.next_eq(attrs)? {
+                    check_delimiter(attrs, ArgDelimiter::Eq, eq)?;
+                    let content = next_verbatim(input, self.separator)?;
+                    syn::parse::Parser::parse2(|i: ParseStream| f(i, false), content)
+                        .map(|v| (ArgDelimiter::Eq, v))
                 } else if input.peek(syn::token::Paren) {
+                    check_delimiter(attrs, ArgDelimiter::Paren, input.span())?;
                     let content;
                     parenthesized!(content in input);
-                    f(&content)
+                    f(&content, false).map(|v| (ArgDelimiter::Paren, v))
                 } else {
-                    Err(input.error("expected `= \"<value>\"` or `(<value>)`"))
+                    Err(input.error(
+                        ErrorCode::MissingValue.message("expected `= <tokens>` or `(<tokens>)`"),
+                    ))
                 }
             }
-            ArgKind::Help => parse_value_from_str("", f),
         }
     }
 
+    /// Parses a value of a token tree argument written after `=` without
+    /// quotes, e.g. `ty = Vec<String>`, which is accepted if the tokens up to
+    /// the next separator form a whole value. Otherwise, e.g. `ty = Map<K, V>`,
+    /// the error suggests wrapping the value.
+    fn parse_unquoted_value<T>(
+        &mut self,
+        f: impl FnOnce(ParseStream) -> syn::Result<T>,
+    ) -> syn::Result<T> {
+        let begin = self.input.cursor();
+        let tokens = next_verbatim(self.input, self.separator)?;
+        let end = self.input.cursor();
+        syn::parse::Parser::parse2(f, tokens).map_err(|e| {
+            let span = join_spans(begin, end, 0).unwrap_or_else(|| self.implied_span());
+            let mut err = syn::Error::new(
+                span,
+                ErrorCode::UnexpectedDelimiter.message(format_args!(
+                    "wrap the value of `{}` in quotes or parentheses, e.g. `{} = \"<value>\"` or \
+                     `{}(<value>)`",
+                    self.key, self.key, self.key
+                )),
+            );
+            err.combine(e);
+            err
+        })
+    }
+
+    /// Parses a `=`, which must be followed by a value.
+    fn next_eq(&mut self, attrs: &ArgAttrs) -> syn::Result<Option<Span>> {
+        let eq = match self.input.parse::<Option<Token![=]>>()? {
+            Some(eq) => eq,
+            None => return Ok(None),
+        };
+        // unlike `is_eoa`, a key may well be the value here
+        if self.is_separator() || self.is_empty() {
+            let value = match attrs.get_value_name() {
+                Some(name) => format!("<{}>", name),
+                None => "a value".to_owned(),
+            };
+            return Err(match self.key_span {
+                Some(span) => syn::Error::new(
+                    span,
+                    ErrorCode::MissingValue.message(format_args!(
+                        "expected {} for `{}` after `=`",
+                        value, self.key
+                    )),
+                ),
+                None => syn::Error::new(
+                    eq.span,
+                    ErrorCode::MissingValue.message(format_args!("expected {} after `=`", value)),
+                ),
+            });
+        }
+        Ok(Some(eq.span))
+    }
+
+    /// Consumes the [separator](Self::separator) and returns its span, or the
+    /// span of the next key if it is whitespace. If it reaches EOF, [`None`]
+    /// is returned.
     pub fn next_eoa(&mut self) -> syn::Result<Option<Span>> {
-        if let Some(c) = self.input.parse::<Option<Token![,]>>()? {
-            Ok(Some(c.span))
-        } else if self.is_empty() {
+        if self.is_empty() {
             Ok(None)
+        } else if self.is_separator() {
+            self.consume_next()
+        } else if self.separator == Separator::Whitespace && self.is_eoa() {
+            Ok(Some(self.span()))
         } else {
-            Err(self.input.error("expected a `,`"))
+            Err(self.expected_separator())
         }
     }
 
     /// Consumes the next token and returns its span. If it reaches
     /// [`EOF`](Self::is_eof), [`None`] is returned.
     pub fn consume_next(&mut self) -> syn::Result<Option<Span>> {
-        self.input
-            .parse::<Option<proc_macro2::TokenTree>>()
-            .map(|t| t.map(|t| t.span()))
+        self.input.step(|cursor| match next_token(*cursor) {
+            Some((span, next)) => Ok((Some(span), next)),
+            None => Ok((None, *cursor)),
+        })
     }
 
     pub fn parse_all_with(
         &mut self,
         mut f: impl FnMut(&mut Self) -> syn::Result<Option<Span>>,
     ) -> syn::Result<()> {
-        let mut errors = crate::errors::Errors::default();
+        if crate::marker::is_parsed_input(self.input) {
+            self.remaining();
+            self.skipped = true;
+            return Ok(());
+        }
+        let _codes = self.codes.map(ErrorCode::show);
+        // kept on the parser to be debugged, and restored once done in case
+        // of nested calls
+        let outer = std::mem::take(&mut self.errors);
+        self.errors.set_limit(self.error_limit);
+        let res = self.parse_all_recovering(&mut f);
+        let mut errors = std::mem::replace(&mut self.errors, outer);
+        res?;
+        errors.fail()
+    }
+
+    fn parse_all_recovering(
+        &mut self,
+        f: &mut dyn FnMut(&mut Self) -> syn::Result<Option<Span>>,
+    ) -> syn::Result<()> {
         loop {
             if self.is_empty() {
                 break;
             }
 
-            match f(self) {
+            self.lookahead = None;
+            let start = self.input.cursor();
+            let res = match self.parse_next_through(0, f) {
+                Ok(None) => self.next_unstable(),
+                res => res,
+            };
+            self.position += 1;
+            if self.fail_fast {
+                let res = match res {
+                    Ok(Some(_)) => self.next_eoa().map(|_| ()),
+                    Ok(None) => Err(self.unknown_argument()),
+                    Err(e) => Err(e),
+                };
+                match res {
+                    Ok(()) => self.add_parsed(start),
+                    Err(e) => return Err(self.stop_at(e)),
+                }
+                continue;
+            }
+            match res {
                 Ok(Some(_)) => {
-                    if errors.add_result(self.next_eoa()).is_some() {
+                    let res = self.next_eoa();
+                    if self.errors.add_result(res).is_some() {
+                        self.add_parsed(start);
                         continue;
                     }
                 }
-                Ok(None) => errors.add_at(self.span(), "unknown argument"),
-                Err(e) => errors.add(e),
+                Ok(None) => {
+                    let err = self.unknown_argument();
+                    self.errors.add(err);
+                }
+                Err(e) => self.errors.add(e),
             }
 
             // eat all unexpected tokens
             loop {
-                if self.is_eoa() {
+                if self.is_separator() {
                     self.consume_next()?;
                     break;
                 }
-                self.consume_next()?;
+                if self.consume_next()?.is_none() {
+                    break;
+                }
+                if self.separator == Separator::Whitespace && self.is_eoa() {
+                    break;
+                }
             }
         }
-        errors.fail()
+        Ok(())
+    }
+
+    /// Parses the next argument with `f` through the middlewares from the
+    /// `i`-th one.
+    fn parse_next_through(
+        &mut self,
+        i: usize,
+        f: &mut dyn FnMut(&mut Self) -> syn::Result<Option<Span>>,
+    ) -> syn::Result<Option<Span>> {
+        match self.middlewares.get(i).cloned() {
+            Some(m) => m.parse_next(
+                self,
+                Next {
+                    f: &mut |parser| parser.parse_next_through(i + 1, f),
+                },
+            ),
+            None => f(self),
+        }
+    }
+
+    fn unknown_argument(&mut self) -> syn::Error {
+        let (span, msg) = match self.lookahead.take() {
+            Some(lookahead) => {
+                let err = lookahead.error();
+                (err.span(), err.to_string())
+            }
+            None => (self.span(), "unknown argument".to_owned()),
+        };
+        syn::Error::new(span, ErrorCode::UnknownArgument.message(msg))
+    }
+
+    /// Parses `unstable(feature, ...)`, which opts in to the arguments marked
+    /// with [`ArgAttrs::unstable`] in the rest of the input, unless no
+    /// features are [declared](Self::with_unstable_features), where it is an
+    /// unknown argument.
+    fn next_unstable(&mut self) -> syn::Result<Option<Span>> {
+        if self.unstable_features.is_none() {
+            return Ok(None);
+        }
+        match self.input.cursor().ident() {
+            Some((i, rest)) if i == "unstable" && rest.group(Delimiter::Parenthesis).is_some() => {}
+            _ => return Ok(None),
+        }
+        let key = self.next_key()?;
+        let content;
+        parenthesized!(content in self.input);
+        let features = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+        let mut errors = crate::errors::Errors::default();
+        for feature in features {
+            let name = feature.to_string();
+            let declared = self.unstable_features.as_deref().unwrap_or_default();
+            if declared.iter().any(|f| *f == name) {
+                self.unstable.push(name);
+            } else {
+                errors.add(unknown_feature(&feature, declared));
+            }
+        }
+        errors.fail::<()>()?;
+        Ok(Some(key.span()))
+    }
+
+    /// Sets the features arguments may be marked unstable with, see
+    /// [`ArgAttrs::unstable`], so that others are rejected in
+    /// `unstable(...)`.
+    ///
+    /// They are declared by [`parse_all`](Self::parse_all) and
+    /// [`Collector`](crate::Collector), but must be set for other custom
+    /// parsing to accept `unstable(...)` at all.
+    pub fn with_unstable_features(
+        &mut self,
+        features: impl IntoIterator<Item = &'static str>,
+    ) -> &mut Self {
+        self.unstable_features = Some(features.into_iter().collect());
+        self
     }
 
     pub fn parse_all<A>(&mut self, args: &mut A) -> syn::Result<()>
     where
         A: crate::define_args::Args,
     {
-        self.parse_all_with(|parser| A::parse_next(args, parser))
+        if self.unstable_features.is_none() {
+            let mut features = Vec::new();
+            A::describe_args(&mut |_, attrs, _| {
+                if let Some(feature) = attrs.get_unstable() {
+                    if !features.contains(&feature) {
+                        features.push(feature);
+                    }
+                }
+            });
+            self.unstable_features = Some(features);
+        }
+        self.parse_all_with(|parser| A::parse_next(args, parser))?;
+        if self.skipped {
+            args.set_skipped();
+        }
+        Ok(())
+    }
+
+    /// Parses arguments until `stop` returns `true` at the start of an
+    /// argument, leaving the rest of the input for the caller, see
+    /// [`remaining`](Self::remaining).
+    ///
+    /// Unlike [`parse_all_with`](Self::parse_all_with), this does not try to
+    /// recover from errors and returns the first one.
+    pub fn parse_until_with(
+        &mut self,
+        mut stop: impl FnMut(ParseStream) -> bool,
+        mut f: impl FnMut(&mut Self) -> syn::Result<Option<Span>>,
+    ) -> syn::Result<()> {
+        let _codes = self.codes.map(ErrorCode::show);
+        while !self.is_empty() && !stop(self.input) {
+            self.lookahead = None;
+            let start = self.input.cursor();
+            let res = self.parse_next_until(&mut stop, &mut f);
+            self.position += 1;
+            match res {
+                Ok(()) => self.add_parsed(start),
+                Err(e) => return Err(self.stop_at(e)),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_next_until(
+        &mut self,
+        stop: &mut impl FnMut(ParseStream) -> bool,
+        f: &mut impl FnMut(&mut Self) -> syn::Result<Option<Span>>,
+    ) -> syn::Result<()> {
+        if self.parse_next_through(0, f)?.is_none() && self.next_unstable()?.is_none() {
+            return Err(self.unknown_argument());
+        }
+        if self.is_separator() {
+            self.consume_next()?;
+        } else if !self.is_eoa() && !stop(self.input) {
+            return Err(self.expected_separator());
+        }
+        Ok(())
+    }
+
+    fn add_parsed(&mut self, start: Cursor<'a>) {
+        self.parsed_count += 1;
+        self.parsed.push((start, self.position - 1));
+    }
+
+    fn stop_at(&mut self, err: syn::Error) -> syn::Error {
+        self.stopped_at = Some(err.span());
+        err
+    }
+
+    /// Returns the index of the argument being parsed among all arguments of
+    /// the input, including those failed to parse, which is stored in the
+    /// [entries](crate::Entry::position) it adds.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns how many arguments are parsed successfully so far.
+    pub fn parsed_count(&self) -> usize {
+        self.parsed_count
+    }
+
+    /// Returns where parsing stopped at an error it does not recover from,
+    /// i.e. in [`parse_until_with`](Self::parse_until_with) or in
+    /// [fail-fast](Self::with_fail_fast) mode, so that macros can expand
+    /// partially up to there, e.g. to keep completions working in attributes
+    /// being typed.
+    pub fn stopped_at(&self) -> Option<Span> {
+        self.stopped_at
+    }
+
+    /// Returns whether the input is skipped as it is marked by
+    /// [`mark_parsed`](crate::mark_parsed), in which case arguments parsed
+    /// with [`parse_all`](Self::parse_all) are never
+    /// [finished](crate::Args::finish) either.
+    pub fn is_skipped(&self) -> bool {
+        self.skipped
+    }
+
+    pub fn parse_until<A>(
+        &mut self,
+        args: &mut A,
+        stop: impl FnMut(ParseStream) -> bool,
+    ) -> syn::Result<()>
+    where
+        A: crate::define_args::Args,
+    {
+        self.parse_until_with(stop, |parser| A::parse_next(args, parser))
+    }
+
+    /// Consumes and returns all tokens not parsed yet.
+    pub fn remaining(&mut self) -> TokenStream {
+        // parsing a token stream never fails
+        self.input.parse().unwrap()
+    }
+}
+
+/// Collects tokens up to the next separator which is not enclosed by any
+/// delimiter.
+fn next_verbatim(input: ParseStream, separator: Separator) -> syn::Result<TokenStream> {
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut tokens = TokenStream::new();
+        while let Some((tt, next)) = rest.token_tree() {
+            match (&tt, separator) {
+                (TokenTree::Punct(p), Separator::Punct(ch)) if p.as_char() == ch => break,
+                _ => tokens.extend(std::iter::once(tt)),
+            }
+            rest = next;
+            if separator == Separator::Whitespace {
+                break;
+            }
+        }
+        Ok((tokens, rest))
+    })
+}
+
+/// Returns an error at the first group of the next value nested deeper than
+/// `max`. The value ends at the next `separator` outside any group, if given,
+/// or the end of `input` otherwise.
+fn check_depth(input: ParseStream, separator: Option<Separator>, max: usize) -> syn::Result<()> {
+    let mut cursor = input.cursor();
+    // where to continue after each enclosing group, which avoids recursion
+    let mut stack = Vec::new();
+    let mut first = true;
+    loop {
+        if cursor.eof() {
+            match stack.pop() {
+                Some(next) => cursor = next,
+                None => return Ok(()),
+            }
+            continue;
+        }
+        if stack.is_empty() {
+            let end = match separator {
+                Some(Separator::Punct(ch)) => {
+                    matches!(cursor.punct(), Some((p, _)) if p.as_char() == ch)
+                }
+                // the value itself may be an identifier
+                Some(Separator::Whitespace) => !first && cursor.ident().is_some(),
+                None => false,
+            };
+            if end {
+                return Ok(());
+            }
+        }
+        first = false;
+        if let Some((inside, _, span, next)) = cursor.any_group() {
+            if stack.len() >= max {
+                return Err(syn::Error::new(
+                    span.open(),
+                    ErrorCode::TooDeep.message(format_args!(
+                        "value is nested too deeply, at most {} levels are allowed",
+                        max
+                    )),
+                ));
+            }
+            stack.push(next);
+            cursor = inside;
+        } else if let Some((_, next)) = cursor.token_tree() {
+            cursor = next;
+        }
     }
 }
 
-fn parse_value_from_str<T>(
-    input: &str,
+fn check_delimiter(attrs: &ArgAttrs, found: ArgDelimiter, span: Span) -> syn::Result<()> {
+    match attrs.get_delimiter() {
+        Some(expected) if expected != found => Err(syn::Error::new(
+            span,
+            ErrorCode::UnexpectedDelimiter.message(match expected {
+                ArgDelimiter::Eq => "expected `= <value>`, parentheses are not allowed here",
+                ArgDelimiter::Paren => "expected `(<value>)`, `=` is not allowed here",
+                ArgDelimiter::Omitted => "unexpected value, this argument takes no value",
+            }),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_literal(attrs: &ArgAttrs, input: ParseStream) -> syn::Result<()> {
+    let (range, suffix) = (attrs.get_range(), attrs.get_suffix());
+    if range.is_none() && suffix.is_none() {
+        return Ok(());
+    }
+
+    let fork = input.fork();
+    let negative = fork.parse::<Option<Token![-]>>()?.is_some();
+    let lit = fork.parse::<syn::Lit>();
+    // integers are compared as such, which a `f64` cannot hold above 2^53
+    let (span, lit_suffix, value) = match &lit {
+        Ok(syn::Lit::Int(i)) => {
+            let value = match i.base10_parse::<u128>() {
+                Ok(n) => Number::from_magnitude(negative, n),
+                Err(_) => {
+                    let value = i.base10_parse::<f64>()?;
+                    Number::Float(if negative { -value } else { value })
+                }
+            };
+            (i.span(), i.suffix(), value)
+        }
+        Ok(syn::Lit::Float(f)) => {
+            let value = f.base10_parse::<f64>()?;
+            let value = Number::Float(if negative { -value } else { value });
+            (f.span(), f.suffix(), value)
+        }
+        // leave other values to the value parser
+        _ => return Ok(()),
+    };
+
+    if let Some(suffix) = suffix {
+        if suffix != lit_suffix {
+            return Err(syn::Error::new(
+                span,
+                ErrorCode::InvalidLiteral.message(if suffix.is_empty() {
+                    "expected an unsuffixed literal".to_owned()
+                } else {
+                    format!("expected a literal with suffix `{}`", suffix)
+                }),
+            ));
+        }
+    }
+    if let Some((start, end)) = range {
+        if !(start, end).contains(&value) {
+            return Err(syn::Error::new(
+                span,
+                ErrorCode::InvalidLiteral.message(format_args!(
+                    "value must be in range `{}`",
+                    FmtRange(start, end)
+                )),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn unknown_feature(feature: &Ident, declared: &[&str]) -> syn::Error {
+    let msg = match declared {
+        [] => format!("unknown unstable feature `{}`, none is declared", feature),
+        _ => format!(
+            "unknown unstable feature `{}`, expected one of `{}`",
+            feature,
+            declared.join("`, `")
+        ),
+    };
+    syn::Error::new(feature.span(), ErrorCode::Unstable.message(msg))
+}
+
+/// A key returned by [`Parser::peek_next`].
+///
+/// Dropping it without calling [`accept`](Self::accept) or
+/// [`reject`](Self::reject) panics in debug builds.
+#[must_use = "a peeked key must be either accepted or rejected"]
+pub struct PeekedKey<'p, 'a> {
+    parser: Option<&'p mut Parser<'a>>,
+    key: Ident,
+}
+
+impl<'p, 'a> PeekedKey<'p, 'a> {
+    pub fn key(&self) -> &Ident {
+        &self.key
+    }
+
+    /// Checks whether the key names the argument `expected`, see
+    /// [`Parser::is_key`].
+    pub fn is(&self, expected: &str) -> bool {
+        self.parser.as_ref().unwrap().is_key(expected)
+    }
+
+    /// Consumes the key, after which its value can be parsed with the returned
+    /// parser.
+    pub fn accept(mut self) -> syn::Result<(Ident, &'p mut Parser<'a>)> {
+        let parser = self.parser.take().unwrap();
+        parser.consume_next()?;
+        Ok((self.key.clone(), parser))
+    }
+
+    /// Leaves the key to be parsed by others.
+    pub fn reject(mut self) -> Ident {
+        self.parser = None;
+        self.key.clone()
+    }
+}
+
+impl Drop for PeekedKey<'_, '_> {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.parser.is_none() || std::thread::panicking(),
+            "peeked key `{}` is neither accepted nor rejected",
+            self.key,
+        );
+    }
+}
+
+/// Joins the spans of the tokens from `begin` up to `end`, after skipping the
+/// first `skip` ones.
+fn join_spans(begin: Cursor, end: Cursor, skip: usize) -> Option<Span> {
+    let mut cursor = begin;
+    let mut spans = None::<(Span, Span)>;
+    let mut i = 0;
+    while cursor != end {
+        let (span, next) = next_token(cursor)?;
+        if i >= skip {
+            let first = spans.map_or(span, |(first, _)| first);
+            spans = Some((first, span));
+        }
+        i += 1;
+        cursor = next;
+    }
+    let (first, last) = spans?;
+    Some(first.join(last).unwrap_or(first))
+}
+
+/// Returns the span of the next token and the cursor after it. Unlike
+/// [`Cursor::token_tree`], this descends into None-delimited groups, which wrap
+/// fragments such as `$meta` interpolated by `macro_rules!`, so that a key
+/// inside one is consumed alone.
+fn next_token(cursor: Cursor) -> Option<(Span, Cursor)> {
+    if let Some((ident, next)) = cursor.ident() {
+        Some((ident.span(), next))
+    } else if let Some((punct, next)) = cursor.punct() {
+        Some((punct.span(), next))
+    } else if let Some((lit, next)) = cursor.literal() {
+        Some((lit.span(), next))
+    } else if let Some((lifetime, next)) = cursor.lifetime() {
+        Some((lifetime.span(), next))
+    } else {
+        cursor.token_tree().map(|(tt, next)| (tt.span(), next))
+    }
+}
+
+fn is_ident_start(s: &str) -> bool {
+    s.starts_with(|c: char| c == '_' || c.is_alphabetic())
+}
+
+/// Parses a value implied by its key, i.e. `true` for flags or nothing
+/// otherwise, directly from tokens spanned by `span` rather than a string to
+/// be lexed, which is the hot path of flags.
+fn parse_implied_value<T>(
+    span: Span,
+    flag: bool,
     f: impl FnOnce(ParseStream) -> syn::Result<T>,
 ) -> syn::Result<T> {
-    let input = LitStr::new(input, Span::call_site());
-    parse_value_from_literal(input, f)
+    if flag {
+        let value = TokenTree::Ident(Ident::new("true", span));
+        syn::parse::Parser::parse2(f, value.into())
+    } else {
+        // errors at the end of nothing are reported on the key
+        syn::parse::Parser::parse2(f, TokenStream::new()).map_err(|e| syn::Error::new(span, e))
+    }
 }
 
 fn parse_value_from_literal<T>(
@@ -188,3 +1255,64 @@ impl<T: Parse> Parse for Optional<T> {
         }
     }
 }
+
+/// A nested set of arguments supplied as a value, e.g. `index(name = "x",
+/// unique)`, which is parsed and [finished](crate::Args::finish) by `A`.
+///
+/// Arguments of this type may be supplied repeatedly, and are collected as
+/// `Vec<A>` by [`FromArg`](crate::FromArg). Their schemas are nested in that
+/// of the containing type.
+///
+/// ```
+/// # use plap::{define_args, Arg, Args, Nested};
+/// # use syn::{LitBool, LitStr};
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct IndexArgs {
+///         #[arg(is_expr)]
+///         name: Arg<LitStr>,
+///         #[arg(is_flag)]
+///         unique: Arg<LitBool>,
+///     }
+/// }
+///
+/// define_args! {
+///     #[::derive(Debug)]
+///     struct TableArgs {
+///         #[arg(is_token_tree)]
+///         index: Arg<Nested<IndexArgs>>,
+///     }
+/// }
+///
+/// let args = syn::parse::Parser::parse_str(
+///     TableArgs::parse,
+///     r#"index(name = "a", unique), index(name = "b")"#,
+/// )?;
+/// let indexes = args.index.take_any();
+/// assert_eq!(indexes[1].name.entries()[0].value.value(), "b");
+/// assert!(!indexes[0].unique.is_empty());
+/// # syn::Result::Ok(())
+/// ```
+pub struct Nested<A>(pub A);
+
+impl<A: fmt::Debug> fmt::Debug for Nested<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<A: crate::define_args::Args> Parse for Nested<A> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // missing arguments are reported where the values start
+        let span = input.span();
+        A::parse(input)?.finish_at(span).map(Self)
+    }
+}
+
+impl<A> std::ops::Deref for Nested<A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
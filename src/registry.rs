@@ -0,0 +1,173 @@
+use std::any::Any;
+
+use proc_macro2::{Span, TokenStream};
+use syn::parse::ParseStream;
+
+use crate::define_args::Args;
+use crate::metrics::{measure, Phase};
+use crate::parser::Parser;
+
+// each call is measured at `span`, the attribute the arguments are parsed from
+trait Entry {
+    fn parse(&mut self, parser: &mut Parser, span: Span) -> syn::Result<()>;
+
+    #[cfg(feature = "checking")]
+    fn check(&self, checker: &mut crate::checker::Checker, span: Span);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<A: Args + 'static> Entry for A {
+    fn parse(&mut self, parser: &mut Parser, span: Span) -> syn::Result<()> {
+        measure(std::any::type_name::<A>(), Phase::Parse, span, || {
+            parser.parse_all(self)
+        })
+    }
+
+    #[cfg(feature = "checking")]
+    fn check(&self, checker: &mut crate::checker::Checker, span: Span) {
+        measure(std::any::type_name::<A>(), Phase::Check, span, || {
+            Args::check(self, checker)
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Maps attribute names to [`Args`] types, so that macros accepting several
+/// attribute namespaces parse all of them in a single pass.
+///
+/// ```
+/// # use plap::{define_args, Arg, Registry};
+/// # use syn::LitStr;
+/// define_args! {
+///     struct SerdeArgs {
+///         #[arg(is_expr)]
+///         rename: Arg<LitStr>,
+///     }
+/// }
+///
+/// let mut registry = Registry::new().register::<SerdeArgs>("serde");
+/// registry.parse_tokens("serde", r#"rename = "a""#.parse().unwrap())?;
+/// assert!(registry.parse_tokens("other", Default::default()).is_ok());
+/// let args = registry.take::<SerdeArgs>("serde").unwrap();
+/// assert_eq!(args.rename.entries()[0].value.value(), "a");
+/// # syn::Result::Ok(())
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<(&'static str, Box<dyn Entry>)>,
+    // paths of the attributes parsed into each entry
+    sources: Vec<Vec<Span>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses attributes named `path` into `A`, where `path` may have several
+    /// segments, e.g. `my::attr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is already registered.
+    pub fn register<A: Args + 'static>(mut self, path: &'static str) -> Self {
+        if self.entries.iter().any(|(p, _)| *p == path) {
+            panic!("attribute `{}` is already registered", path);
+        }
+        self.entries.push((path, Box::new(A::init())));
+        self.sources.push(Vec::new());
+        self
+    }
+
+    /// Parses the arguments of the attribute `path` into the registered type,
+    /// returning `false` if none is registered.
+    pub fn parse_tokens(&mut self, path: &str, tokens: TokenStream) -> syn::Result<bool> {
+        match self.entries.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, entry)) => {
+                syn::parse::Parser::parse2(
+                    |input: ParseStream| entry.parse(&mut Parser::new(input), input.span()),
+                    tokens,
+                )?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Parses each registered attribute in `attrs` and skips the others, such
+    /// as doc comments. Errors of all attributes are combined.
+    ///
+    /// The [`ParseContext`](crate::ParseContext) of each attribute is stored
+    /// in the context of its parser.
+    #[cfg(feature = "attributes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "attributes")))]
+    pub fn parse_attrs(&mut self, attrs: &[syn::Attribute]) -> syn::Result<()> {
+        let mut errors = crate::errors::Errors::default();
+        for attr in attrs {
+            let ctx = crate::context::ParseContext::of(attr);
+            if let Some(i) = self.entries.iter().position(|(p, _)| ctx.is(p)) {
+                let span = crate::context::path_span(attr);
+                self.sources[i].push(span);
+                let entry = &mut self.entries[i].1;
+                let res = attr.meta.require_list().and_then(|list| {
+                    list.parse_args_with(|input: ParseStream| {
+                        entry.parse(Parser::new(input).insert_context(ctx), span)
+                    })
+                });
+                errors.add_result(res);
+            }
+        }
+        errors.fail()
+    }
+
+    /// Runs the checks of all registered types.
+    ///
+    /// Errors without a span of their own, such as a missing required
+    /// argument, are reported on the paths of the attributes parsed by
+    /// [`parse_attrs`](Self::parse_attrs), or on `item`, usually the ident of
+    /// the annotated item, if there are none.
+    #[cfg(feature = "checking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "checking")))]
+    pub fn check(&self, item: Span) -> syn::Result<()> {
+        let mut checker = crate::checker::Checker::default();
+        let mut errors = crate::errors::Errors::default();
+        for ((_, entry), sources) in self.entries.iter().zip(self.sources.iter()) {
+            if sources.is_empty() {
+                checker.with_source(item);
+            }
+            for &span in sources {
+                checker.with_source(span);
+            }
+            entry.check(&mut checker, sources.first().copied().unwrap_or(item));
+            errors.add_result(checker.finish());
+        }
+        errors.fail()
+    }
+
+    /// Returns the arguments parsed for `path`, if it is registered as `A`.
+    pub fn get<A: Args + 'static>(&self, path: &str) -> Option<&A> {
+        self.entries
+            .iter()
+            .find(|(p, _)| *p == path)
+            .and_then(|(_, entry)| entry.as_any().downcast_ref())
+    }
+
+    /// Takes the arguments parsed for `path`, if it is registered as `A`.
+    pub fn take<A: Args + 'static>(&mut self, path: &str) -> Option<A> {
+        let i = self.entries.iter().position(|(p, _)| *p == path)?;
+        self.entries[i].1.as_any().downcast_ref::<A>()?;
+        let (_, entry) = self.entries.remove(i);
+        self.sources.remove(i);
+        entry.into_any().downcast().ok().map(|a| *a)
+    }
+}
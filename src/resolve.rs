@@ -0,0 +1,153 @@
+use proc_macro2::{Ident, Span};
+use syn::{LitBool, LitChar, LitFloat, LitInt, LitStr};
+
+use crate::arg::Arg;
+use crate::code::ErrorCode;
+use crate::define_args::Args;
+use crate::embedded::{Embedded, FromEmbedded};
+use crate::parser::Nested;
+use crate::spanned::{SpannedBool, SpannedIdent, SpannedPath};
+
+/// Converts a single value into plain data.
+pub trait FromValue<T>: Sized {
+    fn from_value(value: T) -> syn::Result<Self>;
+}
+
+impl<T> FromValue<T> for T {
+    fn from_value(value: T) -> syn::Result<Self> {
+        Ok(value)
+    }
+}
+
+impl<A: Args> FromValue<Nested<A>> for A {
+    fn from_value(value: Nested<A>) -> syn::Result<Self> {
+        Ok(value.0)
+    }
+}
+
+impl<T: FromEmbedded> FromValue<Embedded<T>> for T {
+    fn from_value(value: Embedded<T>) -> syn::Result<Self> {
+        Ok(value.into_value())
+    }
+}
+
+impl FromValue<LitStr> for String {
+    fn from_value(value: LitStr) -> syn::Result<Self> {
+        Ok(value.value())
+    }
+}
+
+impl FromValue<LitChar> for char {
+    fn from_value(value: LitChar) -> syn::Result<Self> {
+        Ok(value.value())
+    }
+}
+
+impl FromValue<LitBool> for bool {
+    fn from_value(value: LitBool) -> syn::Result<Self> {
+        Ok(value.value())
+    }
+}
+
+impl FromValue<SpannedBool> for bool {
+    fn from_value(value: SpannedBool) -> syn::Result<Self> {
+        Ok(value.value())
+    }
+}
+
+/// Converts all values of an argument into plain data, see
+/// [`define_args!`](crate::define_args).
+///
+/// - `Vec<U>` takes all values, and `Option<U>` the last one.
+/// - `bool` reads a flag, which is `false` if absent.
+/// - `Arg<T>` keeps the argument as is.
+/// - Strings, numbers and other values take the last value, which is required.
+pub trait FromArg<T>: Sized {
+    fn from_arg(arg: Arg<T>) -> syn::Result<Self>;
+
+    /// Like [`from_arg`](Self::from_arg), but reports a missing value at
+    /// `span`, see [`Args::finish_at`].
+    fn from_arg_at(arg: Arg<T>, span: Span) -> syn::Result<Self> {
+        let _ = span;
+        Self::from_arg(arg)
+    }
+}
+
+impl<T> FromArg<T> for Arg<T> {
+    fn from_arg(arg: Arg<T>) -> syn::Result<Self> {
+        Ok(arg)
+    }
+}
+
+impl<T, U: FromValue<T>> FromArg<T> for Vec<U> {
+    fn from_arg(arg: Arg<T>) -> syn::Result<Self> {
+        arg.take_any().into_iter().map(U::from_value).collect()
+    }
+}
+
+impl<T, U: FromValue<T>> FromArg<T> for Option<U> {
+    fn from_arg(arg: Arg<T>) -> syn::Result<Self> {
+        arg.take_last().map(U::from_value).transpose()
+    }
+}
+
+impl FromArg<LitBool> for bool {
+    fn from_arg(arg: Arg<LitBool>) -> syn::Result<Self> {
+        Ok(arg.take_flag())
+    }
+}
+
+impl FromArg<SpannedBool> for bool {
+    fn from_arg(arg: Arg<SpannedBool>) -> syn::Result<Self> {
+        Ok(arg.take_last().map_or(false, |b| b.value()))
+    }
+}
+
+fn required<T>(arg: Arg<T>, span: Span) -> syn::Result<T> {
+    let name = arg.name().to_owned();
+    arg.take_last().ok_or_else(|| {
+        syn::Error::new(
+            span,
+            ErrorCode::Required.message(format_args!("`{}` is required", name)),
+        )
+    })
+}
+
+macro_rules! impl_required {
+    ($($from:ty => $($to:ty),*;)*) => {$($(
+        impl FromArg<$from> for $to {
+            fn from_arg(arg: Arg<$from>) -> syn::Result<Self> {
+                Self::from_arg_at(arg, Span::call_site())
+            }
+
+            fn from_arg_at(arg: Arg<$from>, span: Span) -> syn::Result<Self> {
+                required(arg, span).and_then(<$to>::from_value)
+            }
+        }
+    )*)*};
+}
+
+impl_required! {
+    LitStr => LitStr, String;
+    LitChar => LitChar, char;
+    LitInt => LitInt;
+    LitFloat => LitFloat;
+    Ident => Ident;
+    SpannedIdent => SpannedIdent;
+    SpannedPath => SpannedPath;
+}
+
+macro_rules! impl_number {
+    ($lit:ty => $($to:ty),*) => {$(
+        impl FromValue<$lit> for $to {
+            fn from_value(value: $lit) -> syn::Result<Self> {
+                value.base10_parse()
+            }
+        }
+
+        impl_required!($lit => $to;);
+    )*};
+}
+
+impl_number!(LitInt => i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_number!(LitFloat => f32, f64);
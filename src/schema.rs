@@ -0,0 +1,483 @@
+use std::fmt::{self, Write};
+use std::path::Path;
+
+use crate::arg::ArgAttrs;
+use crate::define_args::Args;
+
+/// **NOT PUBLIC APIS**
+#[doc(hidden)]
+pub struct SchemaWriter(String);
+
+impl SchemaWriter {
+    pub fn new(name: &str) -> Self {
+        Self(format!("struct {}\n", name))
+    }
+
+    pub fn line(&mut self, indent: usize, kind: &str, text: &str) {
+        writeln!(
+            self.0,
+            "{:indent$}{} {}",
+            "",
+            kind,
+            text,
+            indent = indent * 2
+        )
+        .unwrap();
+    }
+
+    pub fn arg(&mut self, name: &str, attrs: &ArgAttrs) {
+        writeln!(self.0, "arg {}: {}", name, attrs).unwrap();
+    }
+
+    pub fn nested(&mut self, schema: Option<String>) {
+        if let Some(schema) = schema {
+            writeln!(self.0, "  nested").unwrap();
+            for line in schema.lines() {
+                writeln!(self.0, "    {}", line).unwrap();
+            }
+        }
+    }
+
+    pub fn merge(&mut self, name: &str, prefix: &str, schema: &str) {
+        writeln!(self.0, "merge {} prefix = {:?}", name, prefix).unwrap();
+        for line in schema.lines() {
+            writeln!(self.0, "  {}", line).unwrap();
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.0
+    }
+}
+
+/// The attribute surface of an [`Args`] type, read from its
+/// [schema](Args::schema), to tell breaking changes apart from compatible
+/// ones.
+///
+/// ```
+/// # use plap::{define_args, Arg, Schema};
+/// # use syn::{Expr, LitBool};
+/// define_args! {
+///     struct NewArgs {
+///         #[arg(is_expr)]
+///         #[check(required)]
+///         name: Arg<Expr>,
+///         #[arg(is_flag)]
+///         quiet: Arg<LitBool>,
+///     }
+/// }
+///
+/// // usually read from a checked-in snapshot
+/// let old = Schema::parse("struct NewArgs\narg name: expr\narg verbose: flag\n");
+/// let diff = Schema::of::<NewArgs>().diff(&old);
+/// assert!(diff.is_breaking());
+/// assert_eq!(
+///     diff.to_string(),
+///     "added `quiet`\nremoved `verbose`\ntightened `name: check required`\n",
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Schema {
+    /// Arguments with their kind and key attributes, in schema order.
+    args: Vec<(String, String)>,
+    /// Rules which reject some input, e.g. `a: check required`.
+    rules: Vec<String>,
+    /// Attributes which accept more input, e.g. `a: optional`.
+    allowances: Vec<String>,
+    snippets: Vec<Snippet>,
+}
+
+struct Scope {
+    indent: usize,
+    label: String,
+    prefix: String,
+    arg: Option<String>,
+}
+
+impl Schema {
+    /// Returns the schema of `A`.
+    pub fn of<A: Args>() -> Self {
+        Self::parse(&A::schema())
+    }
+
+    /// Reads a schema in the format of [`Args::schema`], where arguments of
+    /// merged types are named with their prefixes, and those of
+    /// [nested](crate::Nested) types after their parents, e.g. `index.name`.
+    /// Unknown lines are ignored.
+    pub fn parse(text: &str) -> Self {
+        let mut schema = Self::default();
+        let mut scopes = vec![Scope {
+            indent: 0,
+            label: String::new(),
+            prefix: String::new(),
+            arg: None,
+        }];
+        for line in text.lines() {
+            let content = line.trim_start();
+            let indent = (line.len() - content.len()) / 2;
+            while scopes.len() > 1 && indent < scopes.last().unwrap().indent {
+                scopes.pop();
+            }
+            let scope = scopes.last_mut().unwrap();
+            let (kind, text) = content.split_at(content.find(' ').unwrap_or(content.len()));
+            let text = text.trim_start();
+
+            if indent > scope.indent {
+                // a line under the last argument
+                let arg = match &scope.arg {
+                    Some(arg) => arg,
+                    None => continue,
+                };
+                if kind == "check" || kind == "group" {
+                    schema.rules.push(format!("{}: {} {}", arg, kind, text));
+                } else if kind == "nested" {
+                    let scope = Scope {
+                        indent: indent + 1,
+                        label: format!("{}(..): ", arg),
+                        prefix: format!("{}.", arg),
+                        arg: None,
+                    };
+                    scopes.push(scope);
+                }
+                continue;
+            }
+            match kind {
+                "arg" => {
+                    let (name, attrs) = text.split_at(text.find(':').unwrap_or(text.len()));
+                    let attrs = attrs.trim_start_matches(':').trim_start();
+                    // nested arguments are written in the values of others
+                    if !scope.prefix.ends_with('.') {
+                        schema.snippets.push(snippet(&scope.prefix, name, attrs));
+                    }
+                    let name = format!("{}{}", scope.prefix, name);
+                    schema.add_arg(&name, attrs);
+                    scope.arg = Some(name);
+                }
+                "merge" => {
+                    let name = text.split(' ').next().unwrap_or_default();
+                    let prefix = text.split(" prefix = ").nth(1).unwrap_or_default();
+                    let scope = Scope {
+                        indent: indent + 1,
+                        label: format!("{}{}: ", scope.label, name),
+                        prefix: format!("{}{}", scope.prefix, prefix.trim_matches('"')),
+                        arg: None,
+                    };
+                    scopes.push(scope);
+                }
+                "check" | "group" | "only_on" => {
+                    let rule = format!("{}{} {}", scope.label, kind, text);
+                    schema.rules.push(rule);
+                }
+                _ => {}
+            }
+        }
+        schema
+    }
+
+    fn add_arg(&mut self, name: &str, attrs: &str) {
+        let mut signature = String::new();
+        for (i, attr) in split_attrs(attrs).enumerate() {
+            let key = attr.split(" = ").next().unwrap_or_default();
+            match key {
+                _ if i == 0 => signature.push_str(attr),
+                "rename" | "family" => write!(signature, ", {}", attr).unwrap(),
+                "explicit" | "delimiter" | "range" | "suffix" | "unstable" | "requires_feature"
+                | "only_on" | "validate" => self.rules.push(format!("{}: {}", name, attr)),
+                "optional" | "accepts_empty" | "env" => {
+                    self.allowances.push(format!("{}: {}", name, attr))
+                }
+                // help texts only
+                _ => {}
+            }
+        }
+        self.args.push((name.to_owned(), signature));
+    }
+
+    /// Returns a snippet of each argument in schema order, skipping those of
+    /// nested types.
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+
+    /// Compares this schema against an `old` one.
+    pub fn diff(&self, old: &Schema) -> SchemaDiff {
+        let get = |schema: &Schema, name: &str| {
+            schema
+                .args
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, s)| s.clone())
+        };
+        let missing = |a: &[String], b: &[String]| {
+            a.iter()
+                .filter(|r| !b.contains(r))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut diff = SchemaDiff::default();
+        for (name, signature) in self.args.iter() {
+            match get(old, name) {
+                None => diff.added.push(name.clone()),
+                Some(old) if old != *signature => diff.changed.push(ArgChange {
+                    name: name.clone(),
+                    old,
+                    new: signature.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, _) in old.args.iter() {
+            if get(self, name).is_none() {
+                diff.removed.push(name.clone());
+            }
+        }
+        diff.tightened = missing(&self.rules, &old.rules);
+        diff.tightened
+            .extend(missing(&old.allowances, &self.allowances));
+        diff.relaxed = missing(&old.rules, &self.rules);
+        diff.relaxed
+            .extend(missing(&self.allowances, &old.allowances));
+        diff
+    }
+}
+
+/// A snippet in the format of rust-analyzer and VS Code, which completes an
+/// argument with placeholders of its key and value, e.g.
+/// `rename = ${1:name}`, see [`Schema::snippets`].
+///
+/// ```
+/// # use plap::Schema;
+/// let schema =
+///     Schema::parse("struct MyArgs\narg rename: expr, value_name = \"name\"\narg skip: flag\n");
+/// let snippets = schema.snippets();
+/// assert_eq!(snippets[0].body, "rename = ${1:name}");
+/// assert_eq!(snippets[1].body, "skip");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snippet {
+    /// The name of the argument, prefixed like in [`SchemaDiff`].
+    pub name: String,
+    pub body: String,
+}
+
+fn snippet(prefix: &str, name: &str, attrs: &str) -> Snippet {
+    let mut kind = "";
+    let mut key = name.to_owned();
+    let mut value = "value";
+    for (i, attr) in split_attrs(attrs).enumerate() {
+        let (attr, val) = match attr.find(" = ") {
+            Some(at) => (&attr[..at], attr[at + 3..].trim_matches('"')),
+            None => (attr, ""),
+        };
+        match attr {
+            _ if i == 0 => kind = attr,
+            "rename" => key = val.to_owned(),
+            "family" => key = format!("{}${{1:suffix}}", val),
+            "value_name" => value = val,
+            _ => {}
+        }
+    }
+    // the key of a family takes the first placeholder
+    let n = if key.contains("${1:") { 2 } else { 1 };
+    let body = match kind {
+        "flag" | "help" => format!("{}{}", prefix, key),
+        "token_tree" => format!("{}{}(${{{}:{}}})", prefix, key, n, value),
+        _ => format!("{}{} = ${{{}:{}}}", prefix, key, n, value),
+    };
+    Snippet {
+        name: format!("{}{}", prefix, name),
+        body,
+    }
+}
+
+/// Splits `a, b = "x, y", c = [d, e]` into `a`, `b = "x, y"` and
+/// `c = [d, e]`.
+fn split_attrs(attrs: &str) -> impl Iterator<Item = &str> {
+    let mut rest = attrs;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let (mut quoted, mut escaped, mut depth) = (false, false, 0);
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = quoted,
+                    '"' => quoted = !quoted,
+                    '[' if !quoted => depth += 1,
+                    ']' if !quoted => depth -= 1,
+                    ',' => return !quoted && depth == 0,
+                    _ => {}
+                }
+                false
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let attr = &rest[..end];
+        rest = rest[end..].trim_start_matches(',').trim_start();
+        Some(attr)
+    })
+}
+
+/// The differences between two [`Schema`]s.
+///
+/// Changed kinds, removed arguments and tightened rules reject some input
+/// accepted before, so they are [breaking](Self::is_breaking).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SchemaDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Arguments whose kind or key attributes (`rename` and `family`) change.
+    pub changed: Vec<ArgChange>,
+    /// Rules new to the schema, e.g. `a: check required`, or allowances
+    /// removed from it, e.g. `a: optional`.
+    pub tightened: Vec<String>,
+    /// The opposite of [`tightened`](Self::tightened).
+    pub relaxed: Vec<String>,
+}
+
+/// An argument whose kind or key attributes change, see [`SchemaDiff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArgChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.tightened.is_empty()
+            && self.relaxed.is_empty()
+    }
+
+    /// Returns whether some input accepted by the old schema may be rejected
+    /// by the new one.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.changed.is_empty() || !self.tightened.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in self.added.iter() {
+            writeln!(f, "added `{}`", name)?;
+        }
+        for name in self.removed.iter() {
+            writeln!(f, "removed `{}`", name)?;
+        }
+        for change in self.changed.iter() {
+            writeln!(
+                f,
+                "changed `{}`: {} -> {}",
+                change.name, change.old, change.new
+            )?;
+        }
+        for rule in self.tightened.iter() {
+            writeln!(f, "tightened `{}`", rule)?;
+        }
+        for rule in self.relaxed.iter() {
+            writeln!(f, "relaxed `{}`", rule)?;
+        }
+        Ok(())
+    }
+}
+
+/// Asserts that `actual` equals the content of the file at `path`.
+///
+/// The file is (re)written instead if the environment variable
+/// `PLAP_UPDATE_SNAPSHOTS` is set. Otherwise, a missing file fails the
+/// assertion, so that snapshots lost or never committed are not silently
+/// recreated, e.g. on CI.
+pub fn assert_snapshot(actual: &str, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    if std::env::var_os("PLAP_UPDATE_SNAPSHOTS").is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(path, actual).unwrap();
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "cannot read snapshot `{}` ({}), set `PLAP_UPDATE_SNAPSHOTS` to create it",
+            path.display(),
+            e,
+        ),
+    };
+    if expected != actual {
+        let mut diff = String::new();
+        let (mut exp, mut act) = (expected.lines(), actual.lines());
+        loop {
+            match (exp.next(), act.next()) {
+                (None, None) => break,
+                (e, a) if e == a => writeln!(diff, " {}", e.unwrap()).unwrap(),
+                (e, a) => {
+                    if let Some(e) = e {
+                        writeln!(diff, "-{}", e).unwrap();
+                    }
+                    if let Some(a) = a {
+                        writeln!(diff, "+{}", a).unwrap();
+                    }
+                }
+            }
+        }
+        panic!(
+            "snapshot `{}` does not match, set `PLAP_UPDATE_SNAPSHOTS` to update it\n{}",
+            path.display(),
+            diff,
+        );
+    }
+}
+
+/// Panics with the mistakes in the declaration of an [`Args`](crate::Args)
+/// type, if any, when debug assertions are enabled, see
+/// [`Args::validate_schema`](crate::Args::validate_schema).
+///
+/// This is meant to be run by macro authors, e.g. in a test, since parsing
+/// the input of users never fails on such mistakes.
+///
+/// ```
+/// # use plap::{define_args, Arg};
+/// define_args! {
+///     struct MyArgs {
+///         #[arg(is_expr, accepts_empty)]
+///         vis: Arg<syn::Visibility>,
+///     }
+/// }
+///
+/// plap::debug_assert_schema!(MyArgs);
+/// ```
+#[macro_export]
+macro_rules! debug_assert_schema {
+    ($ty:ty $(,)?) => {
+        if ::std::cfg!(debug_assertions) {
+            if let ::std::result::Result::Err(e) = <$ty as $crate::private::Args>::validate_schema()
+            {
+                ::std::panic!("misdeclared arguments of `{}`:\n{}", stringify!($ty), e);
+            }
+        }
+    };
+}
+
+/// Asserts that the [schema](crate::Args::schema) of an [`Args`](crate::Args)
+/// type matches a snapshot file, relative to the crate root.
+///
+/// ```ignore
+/// plap::assert_schema_snapshot!(MyArgs, "tests/snapshots/my_args.txt");
+/// ```
+#[macro_export]
+macro_rules! assert_schema_snapshot {
+    ($ty:ty, $path:expr $(,)?) => {
+        $crate::assert_snapshot(
+            &<$ty as $crate::private::Args>::schema(),
+            ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join($path),
+        )
+    };
+}
@@ -0,0 +1,82 @@
+use std::{fmt, ops, slice};
+
+/// A vector which stores up to one element inline.
+///
+/// Most arguments are supplied exactly once, so this avoids a heap allocation
+/// per argument in the common case.
+#[derive(Clone)]
+pub(crate) enum SmallVec<T> {
+    Inline(Option<T>),
+    Heap(Vec<T>),
+}
+
+impl<T: fmt::Debug> fmt::Debug for SmallVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T> Default for SmallVec<T> {
+    fn default() -> Self {
+        Self::Inline(None)
+    }
+}
+
+impl<T> SmallVec<T> {
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            Self::Inline(Some(t)) => slice::from_ref(t),
+            Self::Inline(None) => &[],
+            Self::Heap(v) => v,
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        match self {
+            Self::Inline(slot @ None) => *slot = Some(value),
+            Self::Inline(slot) => {
+                let first = slot.take().unwrap();
+                *self = Self::Heap(vec![first, value]);
+            }
+            Self::Heap(v) => v.push(value),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match self {
+            Self::Inline(slot) => slot.take(),
+            Self::Heap(v) => v.pop(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::Inline(slot) => *slot = None,
+            Self::Heap(v) => v.clear(),
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        for t in other {
+            self.push(t.clone());
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::Inline(slot) => slot.into_iter().collect(),
+            Self::Heap(v) => v,
+        }
+    }
+}
+
+impl<T> ops::Deref for SmallVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
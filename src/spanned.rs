@@ -0,0 +1,206 @@
+use std::fmt;
+
+use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// A `bool` literal which remembers where it is written.
+///
+/// Values are compared without their spans.
+#[derive(Clone, Copy, Debug)]
+pub struct SpannedBool {
+    value: bool,
+    span: Span,
+}
+
+impl SpannedBool {
+    pub fn new(value: bool, span: Span) -> Self {
+        Self { value, span }
+    }
+
+    pub fn value(&self) -> bool {
+        self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns `true` or `false` with the span of the literal.
+    pub fn to_token_stream(&self) -> TokenStream {
+        let s = if self.value { "true" } else { "false" };
+        TokenTree::from(Ident::new(s, self.span)).into()
+    }
+}
+
+impl Parse for SpannedBool {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let b = input.parse::<syn::LitBool>()?;
+        Ok(Self::new(b.value(), b.span))
+    }
+}
+
+impl PartialEq for SpannedBool {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for SpannedBool {}
+
+/// An identifier which remembers where it is written.
+///
+/// Unlike [`Ident`], this can be compared with strings in both directions and
+/// does not accept keywords.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpannedIdent {
+    ident: Ident,
+}
+
+impl SpannedIdent {
+    pub fn new(ident: Ident) -> Self {
+        Self { ident }
+    }
+
+    pub fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    pub fn span(&self) -> Span {
+        self.ident.span()
+    }
+
+    pub fn to_token_stream(&self) -> TokenStream {
+        TokenTree::from(self.ident.clone()).into()
+    }
+}
+
+impl Parse for SpannedIdent {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse().map(Self::new)
+    }
+}
+
+impl fmt::Display for SpannedIdent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ident.fmt(f)
+    }
+}
+
+impl PartialEq<str> for SpannedIdent {
+    fn eq(&self, other: &str) -> bool {
+        self.ident == other
+    }
+}
+
+impl PartialEq<SpannedIdent> for str {
+    fn eq(&self, other: &SpannedIdent) -> bool {
+        other.ident == self
+    }
+}
+
+/// A path of identifiers, such as `std::fmt::Debug`, whose tokens all carry
+/// the span of the path when printed.
+///
+/// Generic arguments are not supported.
+#[derive(Clone, Debug)]
+pub struct SpannedPath {
+    leading_colon: bool,
+    segments: Vec<Ident>,
+    span: Span,
+}
+
+impl SpannedPath {
+    pub fn leading_colon(&self) -> bool {
+        self.leading_colon
+    }
+
+    pub fn segments(&self) -> &[Ident] {
+        &self.segments
+    }
+
+    /// Returns the span of the first token of the path.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn to_token_stream(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        let colon = |tokens: &mut TokenStream| {
+            let mut first = Punct::new(':', Spacing::Joint);
+            let mut second = Punct::new(':', Spacing::Alone);
+            first.set_span(self.span);
+            second.set_span(self.span);
+            tokens.extend([TokenTree::from(first), TokenTree::from(second)]);
+        };
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 || self.leading_colon {
+                colon(&mut tokens);
+            }
+            let mut segment = segment.clone();
+            segment.set_span(self.span);
+            tokens.extend([TokenTree::from(segment)]);
+        }
+        tokens
+    }
+}
+
+impl Parse for SpannedPath {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let leading_colon = input.parse::<Option<Token![::]>>()?.is_some();
+        let mut segments = vec![input.parse::<Ident>()?];
+        while input.peek(Token![::]) {
+            input.parse::<Token![::]>()?;
+            segments.push(input.parse()?);
+        }
+        Ok(Self {
+            leading_colon,
+            segments,
+            span,
+        })
+    }
+}
+
+impl fmt::Display for SpannedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 || self.leading_colon {
+                f.write_str("::")?;
+            }
+            segment.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for SpannedPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.leading_colon == other.leading_colon && self.segments == other.segments
+    }
+}
+
+impl Eq for SpannedPath {}
+
+#[cfg(feature = "printing")]
+mod printing {
+    use super::*;
+
+    impl quote::ToTokens for SpannedBool {
+        fn to_tokens(&self, tokens: &mut TokenStream) {
+            tokens.extend(self.to_token_stream());
+        }
+    }
+
+    impl quote::ToTokens for SpannedIdent {
+        fn to_tokens(&self, tokens: &mut TokenStream) {
+            tokens.extend(self.to_token_stream());
+        }
+    }
+
+    impl quote::ToTokens for SpannedPath {
+        fn to_tokens(&self, tokens: &mut TokenStream) {
+            tokens.extend(self.to_token_stream());
+        }
+    }
+}
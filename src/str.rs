@@ -1,5 +1,6 @@
 use std::{fmt, ops};
 
+#[derive(Clone)]
 pub(crate) enum Str {
     Static(&'static str),
     Owned(Box<str>),
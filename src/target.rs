@@ -0,0 +1,90 @@
+use std::fmt;
+
+use proc_macro2::Span;
+
+use crate::code::ErrorCode;
+
+/// The kind of item an attribute is placed on.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Target {
+    Struct,
+    Enum,
+    Union,
+    EnumVariant,
+    Field,
+    Fn,
+    Impl,
+    Trait,
+    Mod,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Struct => "structs",
+            Self::Enum => "enums",
+            Self::Union => "unions",
+            Self::EnumVariant => "enum variants",
+            Self::Field => "fields",
+            Self::Fn => "functions",
+            Self::Impl => "impl blocks",
+            Self::Trait => "traits",
+            Self::Mod => "modules",
+        })
+    }
+}
+
+impl Target {
+    fn singular(self) -> &'static str {
+        match self {
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::Union => "union",
+            Self::EnumVariant => "enum variant",
+            Self::Field => "field",
+            Self::Fn => "function",
+            Self::Impl => "impl block",
+            Self::Trait => "trait",
+            Self::Mod => "module",
+        }
+    }
+}
+
+/// Formats where an argument may appear, e.g. `struct or field only`.
+pub(crate) struct FmtPlacement(pub &'static [Target]);
+
+impl fmt::Display for FmtPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, t) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(if i + 1 == self.0.len() { " or " } else { ", " })?;
+            }
+            f.write_str(t.singular())?;
+        }
+        f.write_str(" only")
+    }
+}
+
+/// Returns an error at `span`, usually the path of the attribute named `name`,
+/// if `found` is not one of `allowed`.
+pub fn check_target(span: Span, name: &str, found: Target, allowed: &[Target]) -> syn::Result<()> {
+    if allowed.contains(&found) {
+        return Ok(());
+    }
+    let msg = msg_not_allowed_on(name, found, allowed);
+    Err(syn::Error::new(span, ErrorCode::InvalidTarget.message(msg)))
+}
+
+pub(crate) fn msg_not_allowed_on(name: &str, found: Target, allowed: &[Target]) -> String {
+    let mut msg = format!("`{}` is not allowed on {}", name, found);
+    for (i, t) in allowed.iter().enumerate() {
+        let sep = match i {
+            0 => ", only on ",
+            _ if i + 1 == allowed.len() => " or ",
+            _ => ", ",
+        };
+        msg.push_str(sep);
+        msg.push_str(&t.to_string());
+    }
+    msg
+}
@@ -0,0 +1,188 @@
+use std::io;
+use std::path::Path;
+
+use crate::arg::ArgKind;
+use crate::checker::Checker;
+use crate::code::ErrorCode;
+use crate::define_args::Args;
+
+/// A compile-fail test case generated by [`ui_cases`].
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "ui-gen")))]
+pub struct UiCase {
+    /// Names the covered constraint, e.g. `conflict_a_b`, which is also the
+    /// file stem of the case.
+    pub name: String,
+    /// The arguments supplied to the attribute, e.g. `a = 1, b = 1`.
+    pub args: String,
+    /// The messages expected in the stderr of the case.
+    pub expected: Vec<String>,
+}
+
+impl UiCase {
+    /// Writes the case to `dir/<name>.rs`, where `{args}` in `template` is
+    /// replaced by the arguments, and the expected messages are listed in a
+    /// leading comment.
+    pub fn write(&self, dir: impl AsRef<Path>, template: &str) -> io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let mut content = String::new();
+        for msg in self.expected.iter() {
+            content.push_str("// expected: ");
+            content.push_str(msg);
+            content.push('\n');
+        }
+        content.push_str(&template.replace("{args}", &self.args));
+        std::fs::write(dir.join(format!("{}.rs", self.name)), content)
+    }
+
+    /// Returns whether `stderr` contains all expected messages.
+    pub fn matches(&self, stderr: &str) -> bool {
+        self.expected
+            .iter()
+            .all(|msg| stderr.contains(msg.as_str()))
+    }
+}
+
+/// Generates a compile-fail case for each constraint of `A`, i.e. a missing
+/// required argument, each pair of conflicting arguments, each argument
+/// supplied more than allowed, and an unknown key, to be written for
+/// [trybuild](https://docs.rs/trybuild).
+///
+/// Expected messages are found by parsing and checking the arguments, whose
+/// sample values are the first of `1`, `"value"`, `value`, `true` and `()`
+/// accepted by each argument. Arguments accepting none of them are skipped.
+///
+/// ```
+/// # use plap::{define_args, ui_cases, Arg};
+/// # use syn::{LitBool, LitStr};
+/// define_args! {
+///     struct MyArgs {
+///         #[arg(is_expr)]
+///         #[check(exclusive, required)]
+///         name: Arg<LitStr>,
+///         #[arg(is_flag)]
+///         #[check(conflicts_with = name)]
+///         anonymous: Arg<LitBool>,
+///     }
+/// }
+///
+/// let cases = ui_cases::<MyArgs>();
+/// let names = cases.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+/// assert_eq!(
+///     names,
+///     [
+///         "unknown_key",
+///         "missing_required",
+///         "duplicate_name",
+///         "conflict_name_anonymous"
+///     ],
+/// );
+/// assert_eq!(cases[3].args, r#"name = "value", anonymous"#);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "ui-gen")))]
+pub fn ui_cases<A: Args>() -> Vec<UiCase> {
+    // codes are shown to classify errors, and stripped from expected messages
+    let _codes = ErrorCode::show(true);
+    generate::<A>()
+}
+
+fn generate<A: Args>() -> Vec<UiCase> {
+    let mut samples = Vec::new();
+    A::describe_args(&mut |name, attrs, _| {
+        let sample = match attrs.get_kind() {
+            ArgKind::Help => return,
+            ArgKind::Flag => Some(name.to_owned()),
+            _ if attrs.get_optional() && !attrs.get_explicit() => Some(name.to_owned()),
+            _ => ["1", "\"value\"", "value", "true", "()"]
+                .iter()
+                .map(|v| format!("{} = {}", name, v))
+                .find(|s| syn::parse::Parser::parse_str(A::parse, s).is_ok()),
+        };
+        if let Some(sample) = sample {
+            samples.push((name.to_owned(), sample));
+        }
+    });
+
+    let mut cases = Vec::new();
+    let mut push = |name: String, args: String, errors: Vec<(Option<ErrorCode>, String)>| {
+        if !errors.is_empty() {
+            let expected = errors.into_iter().map(|(_, msg)| msg).collect();
+            cases.push(UiCase {
+                name,
+                args,
+                expected,
+            });
+        }
+    };
+
+    let args = "__unknown_key".to_owned();
+    push(
+        "unknown_key".to_owned(),
+        args.clone(),
+        errors_of::<A>(&args),
+    );
+    push(
+        "missing_required".to_owned(),
+        String::new(),
+        only(errors_of::<A>(""), ErrorCode::Required, &[]),
+    );
+    for (name, sample) in samples.iter() {
+        let single = errors_of::<A>(sample);
+        let args = format!("{}, {}", sample, sample);
+        push(
+            format!("duplicate_{}", name),
+            args.clone(),
+            only(errors_of::<A>(&args), ErrorCode::TooManyValues, &single),
+        );
+    }
+    for (i, (a, sample_a)) in samples.iter().enumerate() {
+        for (b, sample_b) in samples[i + 1..].iter() {
+            let mut single = errors_of::<A>(sample_a);
+            single.extend(errors_of::<A>(sample_b));
+            let args = format!("{}, {}", sample_a, sample_b);
+            push(
+                format!("conflict_{}_{}", a, b),
+                args.clone(),
+                only(errors_of::<A>(&args), ErrorCode::Conflict, &single),
+            );
+        }
+    }
+    cases
+}
+
+/// Returns the errors of parsing and checking `args`, without codes.
+fn errors_of<A: Args>(args: &str) -> Vec<(Option<ErrorCode>, String)> {
+    let res = syn::parse::Parser::parse_str(A::parse, args).and_then(|a| {
+        let mut checker = Checker::default();
+        a.check(&mut checker);
+        checker.finish()
+    });
+    let err = match res {
+        Ok(()) => return Vec::new(),
+        Err(e) => e,
+    };
+    err.into_iter()
+        .map(|e| {
+            let code = ErrorCode::of(&e);
+            let msg = e.to_string();
+            let msg = match msg.split_once("] ") {
+                Some((_, msg)) if code.is_some() => msg.to_owned(),
+                _ => msg,
+            };
+            (code, msg)
+        })
+        .collect()
+}
+
+/// Keeps the errors of `code` not found in `excluded`.
+fn only(
+    errors: Vec<(Option<ErrorCode>, String)>,
+    code: ErrorCode,
+    excluded: &[(Option<ErrorCode>, String)],
+) -> Vec<(Option<ErrorCode>, String)> {
+    errors
+        .into_iter()
+        .filter(|e| e.0 == Some(code) && !excluded.contains(e))
+        .collect()
+}
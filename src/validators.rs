@@ -0,0 +1,208 @@
+//! Ready-made [validators](crate::ArgAttrs::validate) of common value shapes.
+//!
+//! Each validator checks a single literal, or path for
+//! [`absolute_path`], and leaves values of other types to the parser.
+//! Errors are spanned by the offending literal.
+//!
+//! ```
+//! # use plap::{define_args, validators, Arg};
+//! # use syn::LitStr;
+//! define_args! {
+//!     #[::derive(Debug)]
+//!     struct MyArgs {
+//!         #[arg(is_expr, validate = validators::ident)]
+//!         name: Arg<LitStr>,
+//!     }
+//! }
+//!
+//! # use plap::Args;
+//! let err = syn::parse::Parser::parse_str(MyArgs::parse, r#"name = "a-b""#).unwrap_err();
+//! assert_eq!(err.to_string(), "expected a valid identifier, found `a-b`");
+//! ```
+
+use proc_macro2::{Ident, Span};
+use syn::ext::IdentExt;
+use syn::parse::ParseStream;
+use syn::{LitStr, Token};
+
+use crate::code::ErrorCode;
+
+fn lit_str(input: ParseStream) -> Option<LitStr> {
+    input.parse::<LitStr>().ok()
+}
+
+fn error(span: Span, msg: impl std::fmt::Display) -> syn::Error {
+    syn::Error::new(span, ErrorCode::InvalidValue.message(msg))
+}
+
+/// Rejects empty strings.
+pub fn non_empty(input: ParseStream) -> syn::Result<()> {
+    match lit_str(input) {
+        Some(s) if s.value().is_empty() => Err(error(s.span(), "expected a non-empty string")),
+        _ => Ok(()),
+    }
+}
+
+/// Requires strings to be valid identifiers, which are not keywords, e.g.
+/// `"my_fn"`.
+pub fn ident(input: ParseStream) -> syn::Result<()> {
+    match lit_str(input) {
+        Some(s) if syn::parse_str::<syn::Ident>(&s.value()).is_err() => Err(error(
+            s.span(),
+            format_args!("expected a valid identifier, found `{}`", s.value()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Requires paths, or strings of paths, to start with `::` or `crate`, e.g.
+/// `::serde` or `"crate::private"`.
+pub fn absolute_path(input: ParseStream) -> syn::Result<()> {
+    let (span, absolute) = if input.peek(LitStr) {
+        let s = input.parse::<LitStr>()?;
+        match s.parse_with(parse_path) {
+            Ok(absolute) => (s.span(), absolute),
+            Err(_) => return Err(error(s.span(), "expected a path, e.g. `::std::fmt`")),
+        }
+    } else if input.peek(Token![::]) || input.peek(Ident::peek_any) {
+        (input.span(), parse_path(input)?)
+    } else {
+        return Ok(());
+    };
+    if absolute {
+        Ok(())
+    } else {
+        Err(error(span, "expected an absolute path, e.g. `::std::fmt`"))
+    }
+}
+
+// returns whether the path is absolute
+fn parse_path(input: ParseStream) -> syn::Result<bool> {
+    let leading_colon = input.parse::<Option<Token![::]>>()?.is_some();
+    let first = input.call(Ident::parse_any)?;
+    while input.peek(Token![::]) {
+        input.parse::<Token![::]>()?;
+        input.call(Ident::parse_any)?;
+    }
+    Ok(leading_colon || first == "crate")
+}
+
+/// Requires strings to be URLs with a scheme, e.g. `"https://example.com"`.
+pub fn url(input: ParseStream) -> syn::Result<()> {
+    let s = match lit_str(input) {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+    let value = s.value();
+    let valid = match value.split_once("://") {
+        Some((scheme, rest)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+                && !rest.is_empty()
+                && !rest.contains(char::is_whitespace)
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(error(
+            s.span(),
+            "expected a URL, e.g. `https://example.com`",
+        ))
+    }
+}
+
+/// Requires strings to be semantic versions, e.g. `"1.0.0-alpha.1+build"`.
+pub fn semver(input: ParseStream) -> syn::Result<()> {
+    match lit_str(input) {
+        Some(s) if !is_semver(&s.value()) => {
+            Err(error(s.span(), "expected a semantic version, e.g. `1.0.0`"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn is_semver(s: &str) -> bool {
+    fn is_identifiers(s: &str, numeric: bool) -> bool {
+        s.split('.').all(|id| {
+            let is_num = !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+            if numeric || is_num {
+                // numbers have no leading zeros
+                is_num && (id == "0" || !id.starts_with('0'))
+            } else {
+                !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            }
+        })
+    }
+
+    let (s, build) = match s.split_once('+') {
+        Some((s, build)) => (s, Some(build)),
+        None => (s, None),
+    };
+    let (core, pre) = match s.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (s, None),
+    };
+    core.split('.').count() == 3
+        && is_identifiers(core, true)
+        && pre.map_or(true, |pre| is_identifiers(pre, false))
+        // build metadata may have leading zeros
+        && build.map_or(true, |build| {
+            build.split('.').all(|id| {
+                !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            })
+        })
+}
+
+/// Requires strings to be glob patterns with balanced `[...]` and `{...}`,
+/// e.g. `"src/**/*.{rs,toml}"`.
+pub fn glob(input: ParseStream) -> syn::Result<()> {
+    match lit_str(input) {
+        Some(s) => match check_glob(&s.value()) {
+            Ok(()) => Ok(()),
+            Err(msg) => Err(error(
+                s.span(),
+                format_args!("invalid glob pattern: {}", msg),
+            )),
+        },
+        None => Ok(()),
+    }
+}
+
+fn check_glob(s: &str) -> Result<(), &'static str> {
+    if s.is_empty() {
+        return Err("empty pattern");
+    }
+    let mut chars = s.chars();
+    let mut braces = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next().ok_or("trailing `\\`")?;
+            }
+            '[' => {
+                // `]` right after `[` or `[!` is a literal member
+                let mut members = 0;
+                loop {
+                    match chars.next() {
+                        None => return Err("unclosed `[`"),
+                        Some('!') | Some('^') if members == 0 => {}
+                        Some(']') if members > 0 => break,
+                        Some(_) => members += 1,
+                    }
+                }
+            }
+            '{' => braces += 1,
+            '}' => braces = braces.checked_sub(1).ok_or("unmatched `}`")?,
+            _ => {}
+        }
+    }
+    if braces > 0 {
+        Err("unclosed `{`")
+    } else {
+        Ok(())
+    }
+}
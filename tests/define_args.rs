@@ -33,3 +33,44 @@ struct UserInput {
     #[my_arg(arg3 = "Vec<String>")]
     another_field: i32,
 }
+
+// names are resolved once all arguments and groups are declared
+#[plap_macros::define_args {
+    #[check(exclusive_group = grp)]
+    #[group(grp = [later, last])]
+    struct ordered_arg {
+        #[arg(is_flag)]
+        #[check(requires = last)]
+        later: Arg<LitBool>,
+        #[arg(is_flag)]
+        last: Arg<LitBool>,
+    }
+}]
+struct OrderedInput {
+    #[ordered_arg(last)]
+    field: i32,
+}
+
+// container checks taking bounds are written like those of `plap::define_args!`
+#[plap_macros::define_args {
+    #[group(sizes = [len, length])]
+    #[group(styles = [bold, italic, underline])]
+    #[check(exclusive_aliases = sizes)]
+    #[check(max_members(styles, 2), members_between(styles, 0, 2))]
+    struct bounded_arg {
+        #[arg(is_expr)]
+        len: Arg<LitInt>,
+        #[arg(is_expr)]
+        length: Arg<LitInt>,
+        #[arg(is_flag)]
+        bold: Arg<LitBool>,
+        #[arg(is_flag)]
+        italic: Arg<LitBool>,
+        #[arg(is_flag)]
+        underline: Arg<LitBool>,
+    }
+}]
+struct BoundedInput {
+    #[bounded_arg(length = 1, bold, italic)]
+    field: i32,
+}
@@ -1,4 +1,7 @@
-use plap::{define_args, Arg};
+#![deny(warnings)]
+
+use plap::prelude::*;
+use plap::Schema;
 use syn::parse::Nothing;
 use syn::{Expr, LitBool, LitInt, Type};
 
@@ -56,3 +59,2822 @@ define_args! {
         help(Nothing),
     }
 }
+
+define_args! {
+    #[::derive(Clone, Debug, PartialEq)]
+    #[inherit(url)]
+    #[builder(CommonArgsBuilder)]
+    pub struct CommonArgs {
+        #[arg(is_expr)]
+        #[check(exclusive)]
+        url: Arg<Expr>,
+        #[arg(is_flag)]
+        verbose: Arg<LitBool>,
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[merge(pub db: CommonArgs, prefix = "db_")]
+    pub struct MergedArgs {
+        #[arg(is_expr)]
+        url: Arg<Expr>,
+    }
+}
+
+#[test]
+fn merge_with_prefix() {
+    let args: MergedArgs =
+        syn::parse::Parser::parse_str(MergedArgs::parse, r#"url = "a", db_url = "b", db_verbose"#)
+            .unwrap();
+    assert_eq!(args.url.len(), 1);
+    assert_eq!(args.db.url.len(), 1);
+    assert_eq!(args.db.url.entries()[0].key, "db_url");
+    assert_eq!(args.db.verbose.len(), 1);
+
+    let err = syn::parse::Parser::parse_str(MergedArgs::parse, "verbose").unwrap_err();
+    assert_eq!(err.to_string(), "unknown argument");
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct ConnArgs {
+        #[arg(is_expr)]
+        #[check(required)]
+        host: Arg<Expr>,
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[merge(pub db: ConnArgs, prefix = "db_")]
+    pub struct ServerArgs {
+        #[arg(is_expr)]
+        port: Arg<LitInt>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn check_merged() {
+    let check = |s| {
+        syn::parse::Parser::parse_str(ServerArgs::parse, s)
+            .unwrap()
+            .finish()
+            .map(|_| ())
+    };
+    assert!(check(r#"db_host = "a""#).is_ok());
+    let err = check("port = 1").unwrap_err();
+    assert_eq!(err.to_string(), "`db_host` is required");
+}
+
+#[test]
+#[cfg(all(feature = "checking", feature = "span-locations"))]
+fn finish_at_span() {
+    let attr = syn::parse_str::<syn::Ident>("server").unwrap().span();
+    let args = syn::parse::Parser::parse_str(ServerArgs::parse, "port = 1").unwrap();
+    let err = args.finish_at(attr).unwrap_err();
+    assert_eq!(err.to_string(), "`db_host` is required");
+    assert_eq!(err.span().byte_range(), attr.byte_range());
+
+    let args = syn::parse::Parser::parse_str(ConfigArgs::parse, "quiet").unwrap();
+    let err = args.resolve_at(attr).unwrap_err();
+    assert_eq!(err.to_string(), "`jobs` is required");
+    assert_eq!(err.span().byte_range(), attr.byte_range());
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[group(styles = [name, ty, level])]
+    #[check(max_members(styles, 2))]
+    #[only_on(Struct, Enum, Field)]
+    pub struct StyledArgs {
+        #[arg(is_expr, delimiter = ArgDelimiter::Eq)]
+        name: Arg<Expr>,
+        #[arg(is_token_tree, value_name = "TYPE")]
+        ty: Arg<Type>,
+        #[arg(is_expr, range = 1..=8, suffix = "")]
+        level: Arg<LitInt>,
+        #[arg(is_verbatim)]
+        raw: Arg<proc_macro2::TokenStream>,
+    }
+}
+
+#[test]
+fn delimiter_style() {
+    let args: StyledArgs =
+        syn::parse::Parser::parse_str(StyledArgs::parse, r#"name = "a", ty = "u8", ty(u16)"#)
+            .unwrap();
+    fn delimiters<T>(a: &Arg<T>) -> Vec<ArgDelimiter> {
+        a.entries().iter().map(|e| e.delimiter).collect()
+    }
+    assert_eq!(delimiters(&args.name), [ArgDelimiter::Eq]);
+    assert_eq!(
+        delimiters(&args.ty),
+        [ArgDelimiter::Eq, ArgDelimiter::Paren]
+    );
+
+    let err = syn::parse::Parser::parse_str(StyledArgs::parse, r#"name("a")"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected `= <value>`, parentheses are not allowed here"
+    );
+}
+
+#[test]
+fn literal_range() {
+    let parse = |s| syn::parse::Parser::parse_str(StyledArgs::parse, s);
+    assert!(parse("level = 8").is_ok());
+    let err = parse("level = 9").unwrap_err();
+    assert_eq!(err.to_string(), "value must be in range `1..=8`");
+    let err = parse("level = 1u8").unwrap_err();
+    assert_eq!(err.to_string(), "expected an unsuffixed literal");
+
+    // bounds of any primitive number
+    let parse = |s| syn::parse::Parser::parse_str(RangedArgs::parse, s);
+    assert!(parse("count = 0, ratio = 0.5, positive = 1").is_ok());
+    let err = parse("count = 4").unwrap_err();
+    assert_eq!(err.to_string(), "value must be in range `..4`");
+    let err = parse("ratio = 1.5").unwrap_err();
+    assert_eq!(err.to_string(), "value must be in range `0..=1`");
+    let err = parse("positive = 0").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value must be in range `(Excluded(0), Unbounded)`"
+    );
+
+    // integers are compared exactly beyond those a `f64` holds
+    assert!(
+        parse("large = 9007199254740992, huge = 340282366920938463463374607431768211455").is_ok()
+    );
+    let err = parse("large = 9007199254740993").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value must be in range `-9007199254740992..=9007199254740992`"
+    );
+    assert!(parse("large = -9007199254740992").is_ok());
+    assert!(parse("large = -9007199254740993").is_err());
+    let err = parse("huge = 340282366920938463463374607431768211454").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value must be in range `340282366920938463463374607431768211455..`"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    struct RangedArgs {
+        #[arg(is_expr, range = ..4usize)]
+        count: Arg<LitInt>,
+        #[arg(is_expr, range = 0.0..=1.0)]
+        ratio: Arg<syn::LitFloat>,
+        #[arg(is_expr, range = (std::ops::Bound::Excluded(0u64), std::ops::Bound::Unbounded))]
+        positive: Arg<LitInt>,
+        #[arg(is_expr, range = -9007199254740992i64..=9007199254740992)]
+        large: Arg<Expr>,
+        #[arg(is_expr, range = u128::MAX..)]
+        huge: Arg<LitInt>,
+    }
+}
+
+#[test]
+fn tri_state_flag() {
+    let parse = |s| syn::parse::Parser::parse_str(CommonArgs::parse, s).unwrap();
+    let args: CommonArgs = parse("");
+    assert!(!args.verbose.to_flag().is_set());
+    let args: CommonArgs = parse("verbose = false");
+    assert_eq!(args.verbose.to_flag().value(), Some(false));
+    let args: CommonArgs = parse("verbose");
+    assert_eq!(args.verbose.to_flag().value(), Some(true));
+}
+
+#[test]
+fn inherit_from_parent() {
+    let parse = |s| syn::parse::Parser::parse_str(CommonArgs::parse, s).unwrap();
+    let parent: CommonArgs = parse(r#"url = "a", verbose"#);
+    let mut child: CommonArgs = parse("");
+    child.inherit_from(&parent);
+    assert_eq!(child.url.len(), 1);
+    assert!(child.verbose.is_empty());
+
+    let mut child: CommonArgs = parse(r#"url = "b""#);
+    child.inherit_from(&parent);
+    let expected = syn::parse_str::<Expr>(r#""b""#).unwrap();
+    assert!(child.url.values().eq([&expected]));
+}
+
+#[test]
+fn verbatim_tokens() {
+    let args: StyledArgs =
+        syn::parse::Parser::parse_str(StyledArgs::parse, "raw = Vec<(u8, u16)> + 1, raw(a, b)")
+            .unwrap();
+    let raw = args.raw.values().map(|t| t.to_string()).collect::<Vec<_>>();
+    assert_eq!(raw, ["Vec < (u8 , u16) > + 1", "a , b"]);
+}
+
+#[test]
+fn unquoted_token_trees() {
+    let args: StyledArgs =
+        syn::parse::Parser::parse_str(StyledArgs::parse, "ty = Vec<String>, ty = (u8, u16)")
+            .unwrap();
+    assert!(args
+        .ty
+        .entries()
+        .iter()
+        .all(|e| e.delimiter == ArgDelimiter::Eq));
+    assert_eq!(
+        args.ty.entries()[0].value,
+        syn::parse_str::<Type>("Vec<String>").unwrap()
+    );
+    let err =
+        syn::parse::Parser::parse_str(StyledArgs::parse, "ty = HashMap<u8, u16>").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "wrap the value of `ty` in quotes or parentheses, e.g. `ty = \"<value>\"` or `ty(<value>)`"
+    );
+}
+
+struct Renamed(&'static str, &'static str);
+
+impl Middleware for Renamed {
+    fn map_key(&self, key: &mut String, _: proc_macro2::Span) {
+        if key == self.0 {
+            *key = self.1.to_owned();
+        }
+    }
+}
+
+struct Logged(&'static str, std::rc::Rc<std::cell::RefCell<Vec<String>>>);
+
+impl Middleware for Logged {
+    fn parse_next<'a>(
+        &self,
+        parser: &mut Parser<'a>,
+        next: Next<'_, 'a, Option<proc_macro2::Span>>,
+    ) -> syn::Result<Option<proc_macro2::Span>> {
+        self.1.borrow_mut().push(format!("{} arg", self.0));
+        let res = next.run(parser);
+        self.1.borrow_mut().push(format!("{} arg done", self.0));
+        res
+    }
+
+    fn next_value<'a>(
+        &self,
+        parser: &mut Parser<'a>,
+        _: &ArgAttrs,
+        next: Next<'_, 'a, ParsedValue>,
+    ) -> syn::Result<ParsedValue> {
+        let parsed = next.run(parser)?;
+        self.1
+            .borrow_mut()
+            .push(format!("{} {:?}", self.0, parsed.delimiter()));
+        Ok(parsed)
+    }
+}
+
+#[test]
+fn parser_middlewares() {
+    let log = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let args = syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut args = StyledArgs::init();
+            Parser::new(input)
+                .with_middleware(Logged("outer", log.clone()))
+                .with_middleware(Renamed("title", "name"))
+                .with_middleware(Logged("inner", log.clone()))
+                .parse_all(&mut args)?;
+            Ok(args)
+        },
+        r#"title = "a", ty(u8)"#,
+    )
+    .unwrap();
+    assert_eq!(args.name.entries()[0].key, "title");
+    assert_eq!(args.ty.len(), 1);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "outer arg",
+            "inner arg",
+            "inner Eq",
+            "outer Eq",
+            "inner arg done",
+            "outer arg done",
+            "outer arg",
+            "inner arg",
+            "inner Paren",
+            "outer Paren",
+            "inner arg done",
+            "outer arg done",
+        ]
+    );
+}
+
+#[test]
+fn compare_args() {
+    let parse = |s| syn::parse::Parser::parse_str(CommonArgs::parse, s).unwrap();
+    let args: CommonArgs = parse(r#"url = "a", verbose"#);
+    assert_eq!(args.clone(), args);
+    assert_eq!(args, parse(r#"verbose, url = "a""#));
+    assert_ne!(args, parse(r#"url = "b", verbose"#));
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    #[::derive(Debug)]
+    #[group(modes = [fast])]
+    #[check(exclusive_group = modes, required_any = modes)]
+    pub struct JoinedArgs {
+        #[arg(is_flag)]
+        fast: Arg<LitBool>,
+        #[arg(is_flag)]
+        #[group(modes)]
+        slow: Arg<LitBool>,
+        #[arg(is_flag)]
+        #[group(modes)]
+        lazy: Arg<LitBool>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn group_members() {
+    let args = JoinedArgs::init();
+    let names = |members: Vec<&dyn AnyArg>| {
+        members
+            .iter()
+            .map(|a| a.name().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(JoinedArgs::modes.index(), 0);
+    assert_eq!(
+        names(args.group_members(JoinedArgs::modes)),
+        ["fast", "slow", "lazy"]
+    );
+    let mut groups = args.all_group_members();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(names(groups.remove(0)), ["fast", "slow", "lazy"]);
+    assert_eq!(MyArgs::grp2.index(), 1);
+
+    // members joining twice are reported by the schema, but checked once
+    assert_eq!(
+        RejoinedArgs::validate_schema().unwrap_err(),
+        "`fast` is a member of group `modes` more than once"
+    );
+    let args = syn::parse::Parser::parse_str(RejoinedArgs::parse, "fast, slow").unwrap();
+    assert_eq!(
+        names(args.group_members(RejoinedArgs::modes)),
+        ["fast", "slow"]
+    );
+    assert_eq!(
+        args.finish().unwrap_err().to_string(),
+        "`fast` conflicts with `slow`, as members of group `modes` are mutually exclusive"
+    );
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    #[::derive(Debug)]
+    #[group(modes = [fast, slow])]
+    #[check(exclusive_group = modes)]
+    pub struct RejoinedArgs {
+        #[arg(is_flag)]
+        #[group(modes)]
+        fast: Arg<LitBool>,
+        #[arg(is_flag)]
+        slow: Arg<LitBool>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn check_groups() {
+    let check = |s| {
+        let args: MyArgs = syn::parse::Parser::parse_str(MyArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        args.check(&mut checker);
+        checker.finish()
+    };
+    assert!(check("arg1 = 1, arg5 = 2").is_ok());
+    let err = check("arg1 = 1").unwrap_err();
+    assert_eq!(err.to_string(), "`arg2 | arg5` is required");
+
+    // members may also join groups on their own
+    let check = |s| {
+        let args: JoinedArgs = syn::parse::Parser::parse_str(JoinedArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        args.check(&mut checker);
+        checker.finish()
+    };
+    assert!(check("fast").is_ok());
+    let err = check("fast, slow").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`fast` conflicts with `slow`, as members of group `modes` are mutually exclusive"
+    );
+    let err = check("").unwrap_err();
+    assert_eq!(err.to_string(), "`fast | slow | lazy` is required");
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn reused_checker() {
+    let parse = |s| syn::parse::Parser::parse_str(MyArgs::parse, s).unwrap();
+    let mut checker = Checker::default();
+    let mut check = |args: MyArgs| {
+        args.check(&mut checker);
+        checker.finish().map_err(|e| e.to_string())
+    };
+    assert!(check(parse("arg1 = 1, arg5 = 2")).is_ok());
+    assert_eq!(
+        check(parse("arg1 = 1")).unwrap_err(),
+        "`arg2 | arg5` is required"
+    );
+    assert_eq!(
+        check(parse("arg1 = 1")).unwrap_err(),
+        "`arg2 | arg5` is required"
+    );
+
+    // findings and settings are discarded
+    parse("arg1 = 1").check(&mut checker);
+    checker.with_error_limit(0).reset();
+    assert!(checker.finish().is_ok());
+    checker.with_error_at(proc_macro2::Span::call_site(), "a");
+    assert_eq!(checker.finish().unwrap_err().to_string(), "a");
+
+    // pooled checkers are reset once returned
+    Checker::pooled(|checker| {
+        parse("arg1 = 1").check(checker);
+    });
+    assert!(Checker::pooled(|checker| checker.finish()).is_ok());
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn check_max_members() {
+    let check = |s| {
+        let args: StyledArgs = syn::parse::Parser::parse_str(StyledArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        args.check(&mut checker);
+        checker.finish()
+    };
+    assert!(check(r#"name = "a", level = 1"#).is_ok());
+    let err = check(r#"name = "a", level = 1, ty = "u8""#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`level` exceeds the maximum of 2 arguments from `name | ty | level`"
+    );
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn check_member_range() {
+    let check = |s, min, max| {
+        let args: StyledArgs = syn::parse::Parser::parse_str(StyledArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        let styles: [&dyn AnyArg; 3] = [&args.name, &args.ty, &args.level];
+        checker.members_between(styles, min, max);
+        checker.finish().map_err(|e| e.to_string())
+    };
+    assert!(check(r#"name = "a""#, 1, 2).is_ok());
+    assert_eq!(
+        check("", 1, 2).unwrap_err(),
+        "1 to 2 of `name | ty | level` are required, but none is supplied"
+    );
+    assert_eq!(
+        check(r#"name = "a", level = 1, ty = "u8""#, 1, 2).unwrap_err(),
+        "`level` exceeds the range of 1 to 2 arguments from `name | ty | level`, where 3 are \
+         supplied"
+    );
+
+    let args: StyledArgs = syn::parse::Parser::parse_str(StyledArgs::parse, "level = 1").unwrap();
+    let mut checker = Checker::default();
+    checker.min_members([&args.name as &dyn AnyArg, &args.level], 2);
+    assert_eq!(
+        checker.finish().unwrap_err().to_string(),
+        "at least 2 of `name | level` are required, but 1 is supplied"
+    );
+}
+
+#[test]
+fn parse_until_separator() {
+    let (args, rest) = syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut args = CommonArgs::init();
+            let mut parser = Parser::new(input);
+            parser.parse_until(&mut args, |input| input.peek(syn::Token![;]))?;
+            Ok((args, parser.remaining()))
+        },
+        r#"verbose, url = "a"; other tokens"#,
+    )
+    .unwrap();
+    assert_eq!(args.url.len(), 1);
+    assert_eq!(rest.to_string(), "; other tokens");
+}
+
+#[test]
+fn peeked_key() {
+    let (width, rest) = syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut parser = Parser::new(input);
+            let peeked = parser.peek_next()?;
+            assert!(peeked.is("width"));
+            let (key, parser) = peeked.accept()?;
+            assert_eq!(key, "width");
+            let width: LitInt = parser.next_value(&ArgAttrs::default())?;
+            parser.consume_next()?;
+
+            assert_eq!(
+                format!("{:?}", parser),
+                "Parser { prefix: \"\", last_key: \"width\", parsed: [], parsed_count: 0, \
+                 stopped_at: None, unstable_features: None, errors: [], remaining: \"height = 2\" \
+                 }"
+            );
+            let peeked = parser.peek_next()?;
+            assert!(!peeked.is("width"));
+            assert_eq!(peeked.reject(), "height");
+            Ok((width, parser.remaining()))
+        },
+        r#"width = "1", height = 2"#,
+    )
+    .unwrap();
+    assert_eq!(width.base10_digits(), "1");
+    assert_eq!(rest.to_string(), "height = 2");
+}
+
+#[test]
+fn debug_parser() {
+    let mut debugged = String::new();
+    syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut args = PreviewArgs::init();
+            Parser::new(input)
+                .with_unstable_features(["fancy"])
+                .parse_all_with(|parser| {
+                    debugged = format!("{:?}", parser);
+                    args.parse_next(parser)
+                })
+        },
+        "unstable(fancy), bogus, name = a, fancy = 1",
+    )
+    .unwrap_err();
+    assert_eq!(
+        debugged,
+        "Parser { prefix: \"\", last_key: \"name\", parsed: [(\"unstable\", 0), (\"name\", 2)], \
+         parsed_count: 2, stopped_at: None, unstable_features: Some([\"fancy\"]), errors: \
+         [\"unknown argument\"], remaining: \"fancy = 1\" }"
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic = "neither accepted nor rejected"]
+fn peeked_key_unresolved() {
+    let _ = syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut parser = Parser::new(input);
+            let _ = parser.peek_next()?;
+            Ok(())
+        },
+        "width",
+    );
+}
+
+#[test]
+fn custom_separators() {
+    fn parse(sep: Separator, s: &str) -> syn::Result<CommonArgs> {
+        syn::parse::Parser::parse_str(
+            |input: syn::parse::ParseStream| {
+                let mut args = CommonArgs::init();
+                Parser::new(input)
+                    .with_separator(sep)
+                    .parse_all(&mut args)?;
+                Ok(args)
+            },
+            s,
+        )
+    }
+
+    let args = parse(Separator::Punct(';'), r#"verbose; url = "a";"#).unwrap();
+    assert_eq!((args.verbose.len(), args.url.len()), (1, 1));
+    let err = parse(Separator::Punct(';'), r#"url = "a", verbose"#).unwrap_err();
+    assert_eq!(err.to_string(), "expected a `;`");
+
+    let args = parse(
+        Separator::Whitespace,
+        r#"verbose url = "a" verbose = false"#,
+    )
+    .unwrap();
+    assert_eq!((args.verbose.len(), args.url.len()), (2, 1));
+    let err = parse(Separator::Whitespace, r#"unknown = 1 2 verbose other"#).unwrap_err();
+    let errs = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(errs, ["unknown argument", "unknown argument"]);
+}
+
+#[test]
+fn missing_value_after_eq() {
+    let err = syn::parse::Parser::parse_str(StyledArgs::parse, "name =, ty =").unwrap_err();
+    let errs = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        errs,
+        [
+            "expected a value for `name` after `=`",
+            "expected <TYPE> for `ty` after `=`"
+        ]
+    );
+}
+
+#[test]
+fn value_name_usage() {
+    let mut attrs = ArgAttrs::default();
+    assert_eq!(attrs.usage("ty"), "ty = <value>");
+    attrs.value_name("TYPE");
+    assert_eq!(attrs.usage("ty"), "ty = <TYPE>");
+    attrs.optional();
+    assert_eq!(attrs.usage("ty"), "ty[ = <TYPE>]");
+    attrs.is_flag();
+    assert_eq!(attrs.usage("ty"), "ty");
+}
+
+#[test]
+fn supplied_args() {
+    let args: MergedArgs = syn::parse::Parser::parse_str(
+        MergedArgs::parse,
+        r#"db_url = "a", url = "b", db_url = "c""#,
+    )
+    .unwrap();
+    let supplied = args
+        .supplied()
+        .into_iter()
+        .map(|(name, arg)| (name, arg.occurrences()))
+        .collect::<Vec<_>>();
+    assert_eq!(supplied, [("url".to_owned(), 1), ("db_url".to_owned(), 2)]);
+
+    // names are those cited by messages, e.g. of constraints over them
+    #[cfg(feature = "checking")]
+    {
+        let counts = args.supplied();
+        let counts = counts
+            .iter()
+            .map(|(name, arg)| (name.as_str(), arg.occurrences()));
+        let violations = Constraints::new(counts).exclusive("db_url").finish();
+        assert_eq!(violations[0].message, "`db_url` has too many values (<= 1)");
+    }
+}
+
+#[test]
+fn build_args() {
+    let url = syn::parse_str::<Expr>(r#""a""#).unwrap();
+    let args = CommonArgs::builder()
+        .url(url.clone())
+        .verbose(true)
+        .build()
+        .unwrap();
+    assert_eq!(args.url.entries()[0].value, url);
+    assert!(args.verbose.to_flag().value_or(false));
+
+    #[cfg(feature = "checking")]
+    {
+        let err = CommonArgs::builder()
+            .url(url.clone())
+            .url(url)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "`url` has too many values (<= 1)");
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[group(grp = [a, b])]
+    #[check(exclusive_group = grp)]
+    pub struct DedupArgs {
+        #[arg(is_flag)]
+        #[check(conflicts_with = b)]
+        a: Arg<LitBool>,
+        #[arg(is_flag)]
+        #[warn(blocked)]
+        b: Arg<LitBool>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn dedup_findings() {
+    let args: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "a, b").unwrap();
+    let mut checker = Checker::default();
+    args.check(&mut checker);
+    let err = checker.finish().unwrap_err();
+    let errs = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        errs,
+        [
+            "`a` conflicts with `b`, as members of group `grp` are mutually exclusive",
+            "`b` conflicts with `a`, as members of group `grp` are mutually exclusive"
+        ]
+    );
+    let warnings = checker.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "`b` is not allowed in this context"
+    );
+
+    // keys are identified by their arguments rather than their addresses
+    let copy: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "a, b").unwrap();
+    args.check(&mut checker);
+    copy.check(&mut checker);
+    assert_eq!(checker.finish().unwrap_err().into_iter().count(), 2);
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn error_codes() {
+    let args: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "a, b").unwrap();
+    let mut checker = Checker::default();
+    checker.with_codes(true);
+    args.check(&mut checker);
+    let err = checker.finish().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[PLAP004] `a` conflicts with `b`, as members of group `grp` are mutually exclusive"
+    );
+    assert_eq!(ErrorCode::of(&err), Some(ErrorCode::Conflict));
+
+    let parse = |codes| {
+        move |input: syn::parse::ParseStream| {
+            let mut args = DedupArgs::init();
+            Parser::new(input).with_codes(codes).parse_all(&mut args)?;
+            Ok(args)
+        }
+    };
+    let err = syn::parse::Parser::parse_str(parse(true), "c").unwrap_err();
+    assert_eq!(ErrorCode::of(&err), Some(ErrorCode::UnknownArgument));
+    assert!(!ErrorCode::is_shown());
+    let err = syn::parse::Parser::parse_str(parse(false), "c").unwrap_err();
+    assert_eq!(ErrorCode::of(&err), None);
+    let err = syn::parse::Parser::parse_str(DedupArgs::parse, "c").unwrap_err();
+    assert_eq!(ErrorCode::of(&err), None);
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[check(exclusive_group = [a, b])]
+    struct ReportArgs {
+        #[arg(is_expr)]
+        a: Arg<LitInt>,
+        #[arg(is_expr)]
+        b: Arg<LitInt>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn report_targets() {
+    let check = |target| {
+        let args: ReportArgs =
+            syn::parse::Parser::parse_str(ReportArgs::parse, "a = 1, b = 22").unwrap();
+        let mut checker = Checker::default();
+        checker.with_report_target(ErrorCode::Conflict, target);
+        args.check(&mut checker);
+        checker
+            .finish()
+            .unwrap_err()
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(check(ReportTarget::Key).len(), 2);
+    let errs = check(ReportTarget::Both);
+    assert_eq!(errs.len(), 4);
+    assert_eq!(errs[0].to_string(), errs[1].to_string());
+    let errs = check(ReportTarget::Value);
+    assert_eq!(errs.len(), 2);
+    #[cfg(feature = "span-locations")]
+    {
+        let cols = errs.iter().map(|e| e.span().start().column);
+        assert_eq!(cols.collect::<Vec<_>>(), [4, 11]);
+    }
+}
+
+#[test]
+fn parse_positions() {
+    // positions are counted by each parser from the start of its input
+    for _ in 0..2 {
+        let args: ReportArgs =
+            syn::parse::Parser::parse_str(ReportArgs::parse, "a = 1, b = 2, a = 3").unwrap();
+        let positions = |a: &Arg<_>| a.entries().iter().map(|e| e.position).collect::<Vec<_>>();
+        assert_eq!(positions(&args.a), [0, 2]);
+        assert_eq!(positions(&args.b), [1]);
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn reused_messages() {
+    let args: ReportArgs =
+        syn::parse::Parser::parse_str(ReportArgs::parse, "a = 1, a = 2, b = 3").unwrap();
+    let mut checker = Checker::default();
+    args.check(&mut checker);
+    let errs = checker.finish().unwrap_err().into_iter();
+    let msgs = errs.map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        msgs,
+        [
+            "`a` conflicts with `b`, as members of `a | b` are mutually exclusive",
+            "`a` conflicts with `b`, as members of `a | b` are mutually exclusive",
+            "`b` conflicts with `a`, as members of `a | b` are mutually exclusive",
+        ],
+    );
+
+    // a message attached to many spans is formatted once
+    struct Counted<'a>(&'a std::cell::Cell<usize>);
+    impl std::fmt::Display for Counted<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            self.0.set(self.0.get() + 1);
+            f.write_str("unsupported here")
+        }
+    }
+    let count = std::cell::Cell::new(0);
+    for e in args.a.entries().iter().chain(args.b.entries()) {
+        checker.with_source(e.key.span());
+    }
+    checker.with_error_at_source(Counted(&count));
+    let errs = checker.finish().unwrap_err().into_iter();
+    assert_eq!(
+        errs.map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["unsupported here", "unsupported here", "unsupported here",]
+    );
+    assert_eq!(count.get(), 1);
+
+    // but only within a run, so that settings changed since apply
+    checker.with_codes(true);
+    args.check(&mut checker);
+    let err = checker.finish().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[PLAP004] `a` conflicts with `b`, as members of `a | b` are mutually exclusive"
+    );
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn constraints() {
+    let args: ReportArgs =
+        syn::parse::Parser::parse_str(ReportArgs::parse, "a = 1, b = 2, b = 3").unwrap();
+    let mut checker = Checker::default();
+    checker
+        .exclusive(&args.b)
+        .max_members([&args.a as &dyn AnyArg, &args.b], 1)
+        .blocked(&args.a);
+    let errs = checker.finish().unwrap_err().into_iter();
+    let expected = errs.map(|e| e.to_string()).collect::<Vec<_>>();
+
+    let mut c = Constraints::new([("a", 1), ("b", 2)]);
+    c.exclusive("b").max_members(&["a", "b"], 1).blocked("a");
+    let violations = c.finish();
+    let msgs = violations
+        .iter()
+        .map(|v| v.message.as_str())
+        .collect::<Vec<_>>();
+    // the checker reports each occurrence
+    assert_eq!(expected, [msgs[0], msgs[1], msgs[2], msgs[1], msgs[2]]);
+    assert_eq!(violations[2].code, ErrorCode::TooManyMembers);
+    // attributed to the group, like by the checker
+    assert_eq!(violations[2].name, "a | b");
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    struct RuleArgs {
+        #[arg(is_expr)]
+        a: Arg<LitInt>,
+        #[arg(is_expr)]
+        b: Arg<LitInt>,
+        #[arg(is_expr)]
+        c: Arg<LitInt>,
+        #[arg(is_expr)]
+        d: Arg<LitInt>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn constraints_match_checker() {
+    let args: RuleArgs =
+        syn::parse::Parser::parse_str(RuleArgs::parse, "a = 1, b = 2, c = 3").unwrap();
+    let (a, b, c, d) = (&args.a, &args.b, &args.c, &args.d);
+    let mut checker = Checker::default();
+    checker
+        .exclusive_aliases([a as &dyn AnyArg, b])
+        .exclusive_group([b as &dyn AnyArg, c])
+        .conflicts_with(c, b)
+        .max_members([a as &dyn AnyArg, b, c], 2)
+        .min_members([a as &dyn AnyArg, d], 2)
+        .required_any([d as &dyn AnyArg])
+        .requires(a, d)
+        .conflicts_with_all(a, [b as &dyn AnyArg, c]);
+    let report = checker.finish_report();
+    let names = report.args();
+    let errs = report.fail_except(&[]).unwrap_err().into_iter();
+    let msgs = errs.map(|e| e.to_string()).collect::<Vec<_>>();
+
+    let counts = args.supplied();
+    let counts = counts
+        .iter()
+        .map(|(name, arg)| (name.as_str(), arg.occurrences()));
+    let mut c = Constraints::new(counts);
+    c.exclusive_aliases(&["a", "b"])
+        .exclusive_group(&["b", "c"])
+        .conflicts_with("c", "b")
+        .max_members(&["a", "b", "c"], 2)
+        .min_members(&["a", "d"], 2)
+        .required_any(&["d"])
+        .requires("a", "d")
+        .conflicts_with_all("a", &["b", "c"]);
+    let violations = c.finish();
+
+    // a conflict declared twice is reported as the group explains it
+    assert!(violations
+        .iter()
+        .all(|v| v.message != "`c` conflicts with `b`"));
+    let expected = violations.iter().map(|v| &v.message).collect::<Vec<_>>();
+    assert_eq!(msgs.iter().collect::<Vec<_>>(), expected);
+    let mut expected = Vec::new();
+    for v in violations.iter() {
+        if !expected.contains(&v.name.as_str()) {
+            expected.push(&v.name);
+        }
+        let msgs = report.for_arg(&v.name).unwrap().into_iter();
+        assert!(msgs.map(|e| e.to_string()).any(|m| m == v.message));
+    }
+    assert_eq!(names, expected);
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    #[::derive(Debug)]
+    #[check(exclusive_group = [a, b])]
+    struct OrderArgs {
+        #[arg(is_expr)]
+        #[check(blocked)]
+        a: Arg<LitInt>,
+        #[arg(is_expr)]
+        b: Arg<LitInt>,
+        #[arg(is_expr)]
+        #[check(required)]
+        c: Arg<LitInt>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn error_order() {
+    let check = |s| {
+        let args: OrderArgs = syn::parse::Parser::parse_str(OrderArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        args.check(&mut checker);
+        let errs = checker.finish().unwrap_err().into_iter();
+        errs.map(|e| e.to_string()).collect::<Vec<_>>()
+    };
+    // attribute order first, and then schema order
+    assert_eq!(
+        check("b = 1, a = 2"),
+        [
+            "`b` conflicts with `a`, as members of `a | b` are mutually exclusive",
+            "`a` conflicts with `b`, as members of `a | b` are mutually exclusive",
+            "`a` is not allowed in this context",
+            "`c` is required",
+        ],
+    );
+    assert_eq!(
+        check("a = 2, b = 1"),
+        [
+            "`a` conflicts with `b`, as members of `a | b` are mutually exclusive",
+            "`a` is not allowed in this context",
+            "`b` conflicts with `a`, as members of `a | b` are mutually exclusive",
+            "`c` is required",
+        ],
+    );
+}
+
+#[test]
+fn fingerprints() {
+    let fingerprint = |s| {
+        let args: ReportArgs = syn::parse::Parser::parse_str(ReportArgs::parse, s).unwrap();
+        args.fingerprint()
+    };
+    assert_eq!(fingerprint("a = 1, b = 22"), fingerprint("a=1 ,b =  22"));
+    assert_ne!(fingerprint("a = 1, b = 22"), fingerprint("a = 1, b = 23"));
+    assert_ne!(fingerprint("a = 1"), fingerprint("b = 1"));
+    assert_ne!(fingerprint("a = 1"), fingerprint("a = 1, a = 1"));
+}
+
+thread_local! {
+    static EMITTED: std::cell::RefCell<Vec<String>> = Default::default();
+}
+
+#[test]
+fn error_limit() {
+    let parse = |input: syn::parse::ParseStream| {
+        let mut args = DedupArgs::init();
+        Parser::new(input).with_error_limit(2).parse_all(&mut args)
+    };
+    let err = syn::parse::Parser::parse_str(parse, "x, a, y, z, w").unwrap_err();
+    let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        ["unknown argument", "unknown argument", "and 2 more errors"]
+    );
+}
+
+#[test]
+fn fail_fast() {
+    let err = syn::parse::Parser::parse_str(DedupArgs::parse_fail_fast, "x, a, y").unwrap_err();
+    assert_eq!(err.into_iter().count(), 1);
+    assert!(syn::parse::Parser::parse_str(DedupArgs::parse_fail_fast, "a, b").is_ok());
+}
+
+#[test]
+fn nesting_depth() {
+    // deep enough to overflow the stack of `syn::Expr` in debug builds
+    let deep = format!("arg1 = {}1{}, arg5 = 1", "(".repeat(1000), ")".repeat(1000));
+    let err = syn::parse::Parser::parse_str(MyArgs::parse, &deep).unwrap_err();
+    assert_eq!(
+        err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["value is nested too deeply, at most 128 levels are allowed"]
+    );
+
+    let parse = |s| {
+        syn::parse::Parser::parse_str(
+            |input: syn::parse::ParseStream| {
+                let mut args = MyArgs::init();
+                Parser::new(input)
+                    .with_max_depth(2)
+                    .parse_all(&mut args)
+                    .map(|_| args)
+            },
+            s,
+        )
+    };
+    assert!(parse("arg1 = ((1)), arg3((u8)), arg4 = \"[(u8,)]\"").is_ok());
+    for s in ["arg1 = (((1)))", "arg3((((u8))))", "arg4 = \"[[(u8,)]]\""] {
+        let err = parse(s).unwrap_err();
+        assert_eq!(ErrorCode::of(&err), None);
+        assert!(err.to_string().starts_with("value is nested too deeply"));
+    }
+}
+
+#[test]
+fn parse_progress() {
+    let parse = |input: syn::parse::ParseStream| {
+        let mut args = DedupArgs::init();
+        let mut parser = Parser::new(input).with_fail_fast(true);
+        let res = parser.parse_all(&mut args);
+        assert!(res.is_err());
+        assert!(parser.stopped_at().is_some());
+        parser.remaining();
+        Ok(parser.parsed_count())
+    };
+    let count = syn::parse::Parser::parse_str(parse, "a, b, x, c").unwrap();
+    assert_eq!(count, 2);
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn emit_errors() {
+    Errors::emit_with(Some(|e| {
+        EMITTED.with(|v| v.borrow_mut().push(e.to_string()))
+    }));
+    let err = syn::parse::Parser::parse_str(DedupArgs::parse, "c, a").unwrap_err();
+    assert_eq!(err.to_string(), "aborting due to 1 emitted error");
+    let args: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "a, b").unwrap();
+    let mut checker = Checker::default();
+    args.check(&mut checker);
+    let err = checker.finish().unwrap_err();
+    assert_eq!(err.to_string(), "aborting due to 2 emitted errors");
+    let args = syn::parse::Parser::parse_str(ConfigArgs::parse, "jobs = 256").unwrap();
+    let err = args.resolve().unwrap_err();
+    assert_eq!(err.to_string(), "aborting due to 1 emitted error");
+    Errors::emit_with(None);
+    EMITTED.with(|v| {
+        assert_eq!(
+            *v.borrow(),
+            [
+                "unknown argument",
+                "`a` conflicts with `b`, as members of group `grp` are mutually exclusive",
+                "`b` conflicts with `a`, as members of group `grp` are mutually exclusive",
+                "number too large to fit in target type",
+            ]
+        )
+    });
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn emit_nested_errors() {
+    Errors::emit_with(Some(|e| {
+        EMITTED.with(|v| v.borrow_mut().push(e.to_string()))
+    }));
+    let err = syn::parse::Parser::parse_str(SchemaTableArgs::parse, "index(unique), index(bogus)")
+        .unwrap_err();
+    Errors::emit_with(None);
+    // errors of nested arguments are emitted once, and counted by the outer
+    // placeholder
+    assert_eq!(err.to_string(), "aborting due to 2 emitted errors");
+    EMITTED.with(|v| {
+        assert_eq!(
+            *v.borrow(),
+            ["`name | columns` is required", "unknown argument"]
+        )
+    });
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn warning_findings() {
+    let args: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "a, b").unwrap();
+    let mut checker = Checker::default();
+    checker.warn(|c| {
+        c.exclusive_group([&args.a as &dyn plap::AnyArg, &args.b]);
+    });
+    assert!(checker.finish().is_ok());
+    assert_eq!(checker.take_warnings().len(), 2);
+
+    // warnings of pooled checkers are returned in reports
+    let args: DedupArgs = syn::parse::Parser::parse_str(DedupArgs::parse, "b").unwrap();
+    let (_, report) = args.finish_report().unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.warnings().len(), 1);
+    assert_eq!(
+        report.warnings()[0].to_string(),
+        "`b` is not allowed in this context"
+    );
+}
+
+#[test]
+fn schema_snapshot() {
+    plap::assert_schema_snapshot!(MyArgs, "tests/snapshots/my_args.txt");
+    plap::assert_schema_snapshot!(MergedArgs, "tests/snapshots/merged_args.txt");
+}
+
+#[test]
+#[should_panic(expected = "set `PLAP_UPDATE_SNAPSHOTS` to create it")]
+fn missing_snapshot() {
+    // updating snapshots would create it instead
+    if std::env::var_os("PLAP_UPDATE_SNAPSHOTS").is_some() {
+        panic!("set `PLAP_UPDATE_SNAPSHOTS` to create it");
+    }
+    plap::assert_schema_snapshot!(MyArgs, "tests/snapshots/missing.txt");
+}
+
+#[test]
+fn schema_diff() {
+    let snapshot = include_str!("snapshots/my_args.txt");
+    assert!(Schema::of::<MyArgs>()
+        .diff(&Schema::parse(snapshot))
+        .is_empty());
+
+    let merged = Schema::parse(include_str!("snapshots/merged_args.txt"));
+    let old = Schema::parse(&snapshot.replace("struct MyArgs", "struct MergedArgs"));
+    let diff = merged.diff(&old);
+    assert!(diff.is_breaking());
+    assert_eq!(diff.added, ["url", "db_url", "db_verbose"]);
+    assert_eq!(
+        diff.removed,
+        ["arg1", "arg2", "arg3", "arg4", "arg5", "help"]
+    );
+    assert_eq!(diff.tightened, ["db_url: check exclusive"]);
+
+    let old = Schema::parse(
+        "struct MyArgs\ngroup grp1 = [arg2, arg5]\ncheck exclusive_group = grp1\narg arg1: expr, \
+         optional, heading = \"A, B\"\narg arg2: expr\narg arg3: token_tree\narg arg4: \
+         token_tree\n\x20 check exclusive\narg arg5: expr\n\x20 check exclusive\narg help: help\n",
+    );
+    let diff = Schema::of::<MyArgs>().diff(&old);
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(
+        diff.to_string(),
+        "changed `arg2`: expr -> flag\ntightened `group grp2 = [arg1, arg3]`\ntightened `check \
+         required_any = grp1`\ntightened `arg1: check exclusive`\ntightened `arg1: check \
+         required`\ntightened `arg2: check exclusive`\ntightened `arg2: check requires = \
+         arg3`\ntightened `arg4: check conflicts_with_each = grp1`\ntightened `arg1: optional`\n",
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct PlacedArgs {
+        /// Skips the item
+        #[arg(is_flag)]
+        skip: Arg<LitBool>,
+        /// Renames the field
+        #[arg(is_expr, only_on = &[Target::Field])]
+        rename: Arg<Expr>,
+        #[arg(is_expr, only_on = &[Target::Struct, Target::Enum])]
+        crate_path: Arg<Expr>,
+    }
+}
+
+#[test]
+fn argument_placement() {
+    assert_eq!(
+        PlacedArgs::help(),
+        "  skip                  Skips the item\n\x20 rename = <value>      Renames the field \
+         (field only)\n\x20 crate_path = <value>  (struct or enum only)\n",
+    );
+
+    let args: PlacedArgs =
+        syn::parse::Parser::parse_str(PlacedArgs::parse, "skip, rename = a, crate_path = b")
+            .unwrap();
+    let err = args.check_placement(Target::Struct).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`rename` is not allowed on structs, only on fields"
+    );
+    assert_eq!(ErrorCode::of(&err), None);
+    let err = args.check_placement(Target::Field).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`crate_path` is not allowed on fields, only on structs or enums"
+    );
+
+    let args: PlacedArgs =
+        syn::parse::Parser::parse_str(PlacedArgs::parse, "skip, rename = a").unwrap();
+    assert!(args.check_placement(Target::Field).is_ok());
+    assert!(Schema::of::<PlacedArgs>()
+        .diff(&Schema::parse("struct PlacedArgs\narg rename: expr\n"))
+        .tightened
+        .contains(&"rename: only_on = [Field]".to_owned()));
+}
+
+keywords! {
+    pub Linkage { static_, r#dynamic, weak }
+}
+
+#[derive(Debug)]
+pub struct Link {
+    pub linkage: Option<Linkage>,
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[resolved(Link)]
+    pub struct KeywordArgs {
+        #[arg(is_expr)]
+        linkage: Arg<Keyword<Linkage>>,
+    }
+}
+
+#[test]
+fn keyword_values() {
+    assert_eq!(
+        Linkage::all()
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>(),
+        ["static", "dynamic", "weak"]
+    );
+    assert_eq!("weak".parse::<Linkage>().unwrap(), Linkage::weak);
+    assert_eq!(
+        "strong".parse::<Linkage>().unwrap_err().to_string(),
+        "expected one of `static`, `dynamic` or `weak`, found `strong`"
+    );
+
+    let args: KeywordArgs =
+        syn::parse::Parser::parse_str(KeywordArgs::parse, "linkage = static").unwrap();
+    assert_eq!(
+        args.linkage.entries()[0].value,
+        Keyword::new(Linkage::static_, proc_macro2::Span::call_site())
+    );
+    assert_eq!(
+        args.linkage.entries()[0]
+            .value
+            .to_token_stream()
+            .to_string(),
+        "static"
+    );
+    assert_eq!(args.resolve().unwrap().linkage, Some(Linkage::static_));
+
+    let err = syn::parse::Parser::parse_str(KeywordArgs::parse, "linkage = 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected one of `static`, `dynamic` or `weak`"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct DocsArgs {
+        #[arg(is_expr, docs_url = "https://docs.rs/my-crate/latest/my_crate/#level")]
+        #[check(exclusive, required)]
+        level: Arg<LitInt>,
+    }
+}
+
+#[test]
+fn docs_urls() {
+    let parse = |s| syn::parse::Parser::parse_str(DocsArgs::parse, s);
+    let err = parse("level = a").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected integer literal\n\nsee https://docs.rs/my-crate/latest/my_crate/#level for \
+         details"
+    );
+    #[cfg(feature = "checking")]
+    {
+        let err = parse("level = 1, level = 2").unwrap().finish().unwrap_err();
+        assert!(err
+            .into_iter()
+            .all(|e| e.to_string().ends_with("/my_crate/#level for details")));
+        let err = parse("").unwrap().finish().unwrap_err();
+        assert!(err.to_string().starts_with("`level` is required\n\nsee "));
+    }
+
+    ErrorCode::show_docs(false);
+    let err = parse("level = a").unwrap_err();
+    ErrorCode::show_docs(true);
+    assert_eq!(err.to_string(), "expected integer literal");
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[check(required_any = [name, columns])]
+    pub struct IndexArgs {
+        #[arg(is_expr)]
+        #[check(exclusive)]
+        name: Arg<syn::LitStr>,
+        #[arg(is_expr)]
+        columns: Arg<syn::LitStr>,
+        #[arg(is_flag)]
+        unique: Arg<LitBool>,
+    }
+}
+
+pub struct SchemaTable {
+    pub index: Vec<IndexArgs>,
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[resolved(SchemaTable)]
+    pub struct SchemaTableArgs {
+        #[arg(is_token_tree)]
+        index: Arg<Nested<IndexArgs>>,
+    }
+}
+
+#[test]
+fn nested_args() {
+    let parse = |s| syn::parse::Parser::parse_str(SchemaTableArgs::parse, s);
+    let table = parse(r#"index(name = "a", unique), index(columns = "b")"#)
+        .unwrap()
+        .resolve()
+        .unwrap();
+    assert_eq!(table.index.len(), 2);
+    assert!(table.index[0].unique.is_present());
+    assert_eq!(table.index[1].columns.entries()[0].value.value(), "b");
+
+    #[cfg(feature = "checking")]
+    {
+        let err = parse(r#"index(unique), index(name = "a", name = "b")"#).unwrap_err();
+        assert_eq!(
+            err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            [
+                "`name | columns` is required",
+                "`name` has too many values (<= 1)",
+                "`name` has too many values (<= 1)"
+            ]
+        );
+    }
+
+    assert_eq!(
+        SchemaTableArgs::schema(),
+        "struct SchemaTableArgs\narg index: token_tree\n\x20 nested\n\x20   struct \
+         IndexArgs\n\x20   check required_any = [name, columns]\n\x20   arg name: expr\n\x20     \
+         check exclusive\n\x20   arg columns: expr\n\x20   arg unique: flag\n"
+    );
+    let old = Schema::parse("struct SchemaTableArgs\narg index: token_tree\n");
+    assert_eq!(
+        Schema::of::<SchemaTableArgs>().diff(&old).to_string(),
+        "added `index.name`\nadded `index.columns`\nadded `index.unique`\ntightened `index(..): \
+         check required_any = [name, columns]`\ntightened `index.name: check exclusive`\n"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct NormalizedArgs {
+        #[arg(is_expr)]
+        #[normalize(lowercase, dedup, sort)]
+        tags: Arg<proc_macro2::Ident>,
+        #[arg(is_expr)]
+        #[check(exclusive)]
+        #[normalize(lowercase, dedup)]
+        kind: Arg<syn::LitStr>,
+    }
+}
+
+#[test]
+fn normalized_values() {
+    let parse = |s| syn::parse::Parser::parse_str(NormalizedArgs::parse, s);
+    let args = parse(r#"tags = b, tags = A, tags = a, kind = "x", kind = "x""#)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(
+        args.tags
+            .values()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>(),
+        ["a", "b"]
+    );
+    assert_eq!(args.kind.len(), 1);
+
+    // raw keywords are lowercased to plain ones
+    let args = parse("tags = r#Crate, tags = r#Type, tags = r#type")
+        .unwrap()
+        .finish()
+        .unwrap();
+    let tags = args.tags.entries().iter().map(|e| e.value.to_string());
+    assert_eq!(tags.collect::<Vec<_>>(), ["crate", "r#type"]);
+
+    #[cfg(feature = "checking")]
+    {
+        let err = parse(r#"kind = "x", kind = "y""#)
+            .unwrap()
+            .finish()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "`kind` has too many values (<= 1)");
+    }
+
+    assert_eq!(
+        NormalizedArgs::schema(),
+        "struct NormalizedArgs\narg tags: expr\n\x20 normalize lowercase\n\x20 normalize \
+         dedup\n\x20 normalize sort\narg kind: expr\n\x20 check exclusive\n\x20 normalize \
+         lowercase\n\x20 normalize dedup\n"
+    );
+}
+
+#[test]
+fn none_delimited_groups() {
+    use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+    // as `$meta` and `$expr` fragments are passed to attributes in macro_rules
+    let none = |s: &str| TokenTree::Group(Group::new(Delimiter::None, s.parse().unwrap()));
+    let mut tokens = TokenStream::new();
+    tokens.extend([
+        none("url = 1"),
+        TokenTree::Punct(proc_macro2::Punct::new(',', proc_macro2::Spacing::Alone)),
+        none("verbose"),
+    ]);
+    let args = syn::parse::Parser::parse2(CommonArgs::parse, tokens).unwrap();
+    assert_eq!((args.url.len(), args.verbose.len()), (1, 1));
+
+    let mut tokens = "url =".parse::<TokenStream>().unwrap();
+    tokens.extend([none("1 + 2")]);
+    let args = syn::parse::Parser::parse2(CommonArgs::parse, tokens).unwrap();
+    assert_eq!(args.url.len(), 1);
+}
+
+#[test]
+fn duplicate_keys() {
+    let args =
+        syn::parse::Parser::parse_str(CommonArgs::parse, "verbose, url = 1, verbose, verbose")
+            .unwrap();
+    let flags = &args.verbose;
+    assert_eq!(flags.duplicate_keys(), [vec![0, 1, 2]]);
+    assert!(flags.span_of_key(1).is_some());
+    assert!(flags.span_of_key(3).is_none());
+
+    // defaults are never duplicates
+    let mut arg = Arg::<LitInt>::new("level");
+    arg.default_value(LitInt::new("1", proc_macro2::Span::call_site()));
+    assert!(arg.duplicate_keys().is_empty());
+}
+
+#[test]
+fn used_keys() {
+    let args = syn::parse::Parser::parse_str(MergedArgs::parse, "db_url = 1, url = 2").unwrap();
+    assert_eq!(args.db.url.name(), "url");
+    assert_eq!(args.db.url.used_key_str().as_deref(), Some("db_url"));
+    assert_eq!(args.url.used_key_str().as_deref(), Some("url"));
+    assert_eq!(args.db.verbose.used_key_str(), None);
+
+    let mut arg = Arg::<LitInt>::new("level");
+    arg.default_value(LitInt::new("1", proc_macro2::Span::call_site()));
+    assert_eq!(arg.used_key_str(), None);
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn error_reports() {
+    let args = syn::parse::Parser::parse_str(MergedArgs::parse, "db_url = 1, db_url = 2").unwrap();
+    let mut checker = Checker::default();
+    checker.check_prefixed("db_", &args.db);
+    let report = checker.finish_report();
+    assert_eq!(report.args(), ["db_url"]);
+    assert_eq!(report.for_arg("db_url").unwrap().into_iter().count(), 2);
+    assert!(report.for_arg("url").is_none());
+    assert!(report.unattributed().is_none());
+    assert!(report.fail_except(&["db_url"]).is_ok());
+    assert!(report.into_result().is_err());
+
+    let mut errors = Errors::default();
+    errors.add_for("a", syn::Error::new(proc_macro2::Span::call_site(), "1"));
+    errors.add(syn::Error::new(proc_macro2::Span::call_site(), "2"));
+    let report = errors.report();
+    assert_eq!(report.unattributed().unwrap().to_string(), "2");
+    assert_eq!(report.fail_except(&["a"]).unwrap_err().to_string(), "2");
+    assert!(errors.fail::<()>().is_ok());
+
+    // findings of rules on groups are attributed to the groups
+    let args = syn::parse::Parser::parse_str(MyArgs::parse, "arg1 = 1").unwrap();
+    let (_, report) = args.finish_report().unwrap();
+    assert_eq!(report.args(), ["grp1"]);
+    assert!(report.fail_except(&["grp1"]).is_ok());
+    let args = syn::parse::Parser::parse_str(ReportArgs::parse, "a = 1, b = 2").unwrap();
+    let (_, report) = args.finish_report().unwrap();
+    assert_eq!(report.args(), ["a | b"]);
+
+    // and so are emitted errors
+    Errors::emit_with(Some(|_| {}));
+    errors.add_for("a", syn::Error::new(proc_macro2::Span::call_site(), "1"));
+    errors.add_for("b", syn::Error::new(proc_macro2::Span::call_site(), "2"));
+    let report = errors.report();
+    Errors::emit_with(None);
+    assert_eq!(report.args(), ["a", "b"]);
+    assert!(report.for_arg("c").is_none());
+    assert!(report.fail_except(&["a", "b"]).is_ok());
+    assert_eq!(
+        report.fail_except(&["a"]).unwrap_err().to_string(),
+        "aborting due to 1 emitted error"
+    );
+    assert_eq!(
+        report.into_result().unwrap_err().to_string(),
+        "aborting due to 2 emitted errors"
+    );
+}
+
+#[test]
+fn reused_args() {
+    let mut args = MergedArgs::init();
+    for input in ["url = 1, db_verbose", "db_url = 2"] {
+        args.reset();
+        syn::parse::Parser::parse_str(
+            |input: syn::parse::ParseStream| Parser::new(input).parse_all(&mut args),
+            input,
+        )
+        .unwrap();
+    }
+    assert!(args.url.is_empty());
+    assert!(args.db.verbose.is_empty());
+    assert_eq!(args.db.url.len(), 1);
+    assert_eq!(args.db.url.name(), "url");
+}
+
+define_args! {
+    #[::derive(Debug)]
+    struct LookupArgs {
+        #[arg(is_expr, family = "on_")]
+        on: Arg<Expr>,
+        #[arg(is_flag)]
+        zeta: Arg<LitBool>,
+        #[arg(is_flag, rename = "alpha")]
+        on_off: Arg<LitBool>,
+        #[arg(is_expr)]
+        mid: Arg<LitInt>,
+        #[arg(is_flag)]
+        on_ready: Arg<LitBool>,
+    }
+}
+
+#[test]
+fn key_lookup() {
+    let args = syn::parse::Parser::parse_str(LookupArgs::parse, "alpha, zeta, mid = 1, on_off = 1")
+        .unwrap();
+    assert_eq!(
+        (args.on_off.len(), args.zeta.len(), args.mid.len()),
+        (1, 1, 1)
+    );
+    // renamed arguments are only known by their new keys
+    assert_eq!(args.on.entries()[0].key, "on_off");
+    assert!(syn::parse::Parser::parse_str(LookupArgs::parse, "beta").is_err());
+
+    // exact keys take precedence over families declared before them
+    let args = syn::parse::Parser::parse_str(LookupArgs::parse, "on_ready, on_go = 1").unwrap();
+    assert_eq!(args.on_ready.len(), 1);
+    assert!(Presence::keys(&args.on).eq(["on_go"]));
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[group(sizes = [len, length])]
+    #[check(exclusive_aliases = sizes)]
+    pub struct AliasedArgs {
+        #[arg(is_expr)]
+        len: Arg<LitInt>,
+        #[arg(is_expr)]
+        length: Arg<LitInt>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn exclusive_aliases() {
+    let check = |s: &str| {
+        syn::parse::Parser::parse_str(AliasedArgs::parse, s)
+            .unwrap()
+            .finish()
+            .map(|_| ())
+    };
+    assert!(check("len = 1").is_ok());
+    assert!(check("length = 1").is_ok());
+    assert_eq!(
+        check("len = 1, length = 2").unwrap_err().to_string(),
+        "`len` has too many values (<= 1)"
+    );
+}
+
+#[test]
+fn attribute_targets() {
+    let span = proc_macro2::Span::call_site();
+    assert!(StyledArgs::check_target(span, "styled", Target::Field).is_ok());
+    assert!(MyArgs::check_target(span, "my", Target::Fn).is_ok());
+
+    let err = StyledArgs::check_target(span, "styled", Target::EnumVariant).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`styled` is not allowed on enum variants, only on structs, enums or fields"
+    );
+}
+
+define_args! {
+    #[merge(pub db: CommonArgs, prefix = "db_")]
+    pub struct HelpArgs {
+        /// Hostname of the server
+        #[arg(is_expr, value_name = "HOST", heading = "Connection options")]
+        host: Arg<Expr>,
+        /// Print fewer logs
+        #[arg(is_flag, display_order = 0)]
+        quiet: Arg<LitBool>,
+        /// Port of the server
+        #[arg(is_expr, heading = "Connection options", display_order = 1)]
+        port: Arg<LitInt>,
+    }
+}
+
+#[test]
+fn help_sections() {
+    assert_eq!(
+        HelpArgs::help(),
+        "  quiet             Print fewer logs
+  db_url = <value>
+  db_verbose
+
+Connection options:
+  port = <value>    Port of the server
+  host = <HOST>     Hostname of the server
+",
+    );
+}
+
+#[test]
+fn completion_snippets() {
+    let snippets = |schema: Schema| {
+        schema
+            .snippets()
+            .iter()
+            .map(|s| format!("{}: {}", s.name, s.body))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        snippets(Schema::of::<HelpArgs>()),
+        [
+            "host: host = ${1:HOST}",
+            "quiet: quiet",
+            "port: port = ${1:value}",
+            "db_url: db_url = ${1:value}",
+            "db_verbose: db_verbose",
+        ]
+    );
+    assert_eq!(
+        snippets(Schema::of::<SchemaTableArgs>()),
+        ["index: index(${1:value})"]
+    );
+    assert_eq!(
+        snippets(Schema::of::<HookArgs>()),
+        ["on: on_${1:suffix} = ${2:value}", "once: once"]
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct TableArgs {
+        #[arg(is_expr)]
+        name: Arg<syn::Ident>,
+        #[arg(is_expr)]
+        #[default_from(name)]
+        table: Arg<syn::Ident>,
+    }
+}
+
+#[test]
+fn default_from_sibling() {
+    let mut args: TableArgs = syn::parse::Parser::parse_str(TableArgs::parse, "").unwrap();
+    let err = args.resolve_defaults().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`table` defaults to `name`, which is not supplied"
+    );
+
+    let ident = syn::Ident::new("User", proc_macro2::Span::call_site());
+    args.name.default_value(ident);
+    args.resolve_defaults().unwrap();
+    assert_eq!(args.table.entries()[0].value, "User");
+
+    let mut args: TableArgs =
+        syn::parse::Parser::parse_str(TableArgs::parse, "name = A, table = B").unwrap();
+    args.resolve_defaults().unwrap();
+    assert_eq!(args.table.entries()[0].value, "B");
+
+    // neither argument is supplied, so the error is at the attribute
+    #[cfg(feature = "span-locations")]
+    {
+        let attr = syn::parse_str::<syn::Ident>("table").unwrap().span();
+        let args: TableArgs = syn::parse::Parser::parse_str(TableArgs::parse, "").unwrap();
+        let err = args.finish_at(attr).unwrap_err();
+        assert_eq!(err.span().byte_range(), attr.byte_range());
+    }
+}
+
+#[test]
+fn generic_presence() {
+    fn describe(a: &dyn Presence) -> String {
+        match (a.occurrences(), a.is_defaulted()) {
+            (0, _) => format!("{} is absent", a.name()),
+            (_, true) => format!("{} is defaulted", a.name()),
+            (n, false) => format!("{} is supplied {} time(s)", a.name(), n),
+        }
+    }
+
+    let mut args: TableArgs = syn::parse::Parser::parse_str(TableArgs::parse, "name = A").unwrap();
+    assert_eq!(describe(&args.table), "table is absent");
+    args.resolve_defaults().unwrap();
+    assert_eq!(describe(&args.name), "name is supplied 1 time(s)");
+    assert_eq!(describe(&args.table), "table is supplied 1 time(s)");
+
+    let mut arg = Arg::<LitInt>::new("level");
+    arg.default_value(syn::parse_str("1").unwrap());
+    assert_eq!(describe(&arg), "level is defaulted");
+}
+
+define_args! {
+    #[accessors(pub)]
+    pub struct AccessorArgs {
+        #[arg(is_flag)]
+        quiet: Arg<LitBool>,
+        #[arg(is_expr)]
+        #[check(exclusive)]
+        level: Arg<LitInt>,
+        #[arg(is_token_tree)]
+        #[accessor(include_all)]
+        include: Arg<Type>,
+        // which would otherwise shadow `Args::check`
+        #[arg(is_flag)]
+        #[accessor(get_check)]
+        check: Arg<LitBool>,
+    }
+}
+
+#[test]
+fn value_accessors() {
+    let args = syn::parse::Parser::parse_str(
+        AccessorArgs::parse,
+        r#"quiet, level = 2, include = "A", include = "B""#,
+    )
+    .unwrap();
+    assert!(args.quiet());
+    assert_eq!(args.level().unwrap().base10_digits(), "2");
+    assert_eq!(args.include_all().len(), 2);
+    assert!(!args.get_check());
+
+    let args = syn::parse::Parser::parse_str(AccessorArgs::parse, "check").unwrap();
+    assert!(!args.quiet());
+    assert!(args.level().is_none());
+    assert_eq!(args.include_all().len(), 0);
+    assert!(args.get_check());
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub quiet: bool,
+    pub jobs: u8,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+}
+
+define_args! {
+    #[resolved(Config)]
+    pub struct ConfigArgs {
+        #[arg(is_flag)]
+        quiet: Arg<LitBool>,
+        #[arg(is_expr)]
+        jobs: Arg<LitInt>,
+        #[arg(is_expr)]
+        name: Arg<syn::LitStr>,
+        #[arg(is_expr)]
+        tags: Arg<syn::LitStr>,
+    }
+}
+
+#[test]
+fn resolved_config() {
+    let parse = |s| syn::parse::Parser::parse_str(ConfigArgs::parse, s).unwrap();
+    let config = parse(r#"jobs = 4, tags = "a", tags = "b""#)
+        .resolve()
+        .unwrap();
+    assert!(!config.quiet);
+    assert_eq!(config.jobs, 4);
+    assert_eq!(config.name, None);
+    assert_eq!(config.tags, ["a", "b"]);
+
+    let err = parse(r#"name = "x", jobs = 256"#).resolve().unwrap_err();
+    assert_eq!(err.to_string(), "number too large to fit in target type");
+    let err = parse("quiet").resolve().unwrap_err();
+    assert_eq!(err.to_string(), "`jobs` is required");
+}
+
+grammar! {
+    #[::derive(Debug)]
+    pub GrammarArgs {
+        /// Paths to include
+        include: Type [is_token_tree, required, multiple];
+        fast: flag;
+        safe: flag;
+        level: LitInt;
+        group modes [exclusive, required] { fast | safe }
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn grammar_syntax() {
+    let check = |s| syn::parse::Parser::parse_str(GrammarArgs::parse, s)?.finish();
+    let args = check(r#"include = "A", include = "B", fast, level = 1"#).unwrap();
+    assert_eq!(args.include.len(), 2);
+    assert_eq!(
+        check(r#"fast"#).unwrap_err().to_string(),
+        "`include` is required"
+    );
+    assert_eq!(
+        check(r#"include = "A", fast, safe"#)
+            .unwrap_err()
+            .to_string(),
+        "`fast` conflicts with `safe`, as members of group `modes` are mutually exclusive"
+    );
+    assert_eq!(
+        check(r#"include = "A", safe, level = 1, level = 2"#)
+            .unwrap_err()
+            .to_string(),
+        "`level` has too many values (<= 1)"
+    );
+    assert!(GrammarArgs::help().starts_with("  include = <value>  Paths to include\n"));
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[rename_all("camelCase")]
+    pub enum Wrap {
+        #[arg(is_expr)]
+        max_width(LitInt),
+        #[arg(is_flag, rename = "off")]
+        no_wrap(LitBool),
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct RenamedArgs {
+        #[arg(is_expr)]
+        wrap: Arg<Wrap>,
+        #[arg(is_flag, rename = "break")]
+        break_: Arg<LitBool>,
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[rename_all("camelCase")]
+    pub struct CasedArgs {
+        #[arg(is_expr)]
+        max_width: Arg<LitInt>,
+        #[arg(is_flag, rename = "off")]
+        no_wrap: Arg<LitBool>,
+    }
+}
+
+#[test]
+fn renamed_keys() {
+    let parse = |s| syn::parse::Parser::parse_str(RenamedArgs::parse, s);
+    let args = parse("wrap = maxWidth(80)").unwrap();
+    assert!(matches!(args.wrap.entries()[0].value, Wrap::max_width(_)));
+    let args = parse("wrap = off").unwrap();
+    assert!(matches!(args.wrap.entries()[0].value, Wrap::no_wrap(_)));
+    assert!(parse("wrap = max_width(80)").is_err());
+    let args = parse("break").unwrap();
+    assert_eq!(args.break_.name(), "break");
+    assert_eq!(RenamedArgs::help(), "  wrap = <value>\n  break\n");
+
+    let parse = |s| syn::parse::Parser::parse_str(CasedArgs::parse, s);
+    let args = parse("maxWidth = 80, off").unwrap();
+    assert_eq!(args.max_width.name(), "maxWidth");
+    assert_eq!(args.max_width.entries()[0].key, "maxWidth");
+    assert!(args.no_wrap.is_present());
+    assert!(parse("max_width = 80").is_err());
+    assert_eq!(CasedArgs::help(), "  maxWidth = <value>\n  off\n");
+    assert!(CasedArgs::schema().contains("\nrename_all camelCase\n"));
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct ExplicitArgs {
+        #[arg(is_flag, explicit)]
+        quiet: Arg<LitBool>,
+        #[arg(is_expr, optional, explicit, value_name = "N")]
+        level: OptionalArg<LitInt>,
+    }
+}
+
+#[test]
+fn explicit_values() {
+    let parse = |s| syn::parse::Parser::parse_str(ExplicitArgs::parse, s);
+    assert!(parse("quiet = false, level = 1").is_ok());
+    assert_eq!(
+        parse("quiet").unwrap_err().to_string(),
+        "`quiet` requires an explicit value, e.g. `quiet = true`"
+    );
+    assert_eq!(
+        parse("level").unwrap_err().to_string(),
+        "`level` requires an explicit value, e.g. `level = <N>`"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[group(styles = [Compact, Pretty])]
+    #[check(exclusive_group = styles, requires(Pretty, Indent))]
+    #[rename_all("snake_case")]
+    pub enum CheckedArgEnum {
+        #[arg(is_flag)]
+        Compact(LitBool),
+        #[arg(is_flag)]
+        Pretty(LitBool),
+        #[arg(is_expr)]
+        #[check(conflicts_with = Compact)]
+        Indent(LitInt),
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn enum_checks() {
+    let check = |s| {
+        let values = syn::parse::Parser::parse_str(
+            |input: syn::parse::ParseStream| {
+                let mut parser = Parser::new(input);
+                let mut values = Vec::new();
+                while !parser.is_empty() {
+                    values.extend(CheckedArgEnum::parse_next(&mut parser)?);
+                    parser.consume_next()?;
+                }
+                Ok(values)
+            },
+            s,
+        )
+        .unwrap();
+        let mut checker = Checker::default();
+        CheckedArgEnum::check(&values, &mut checker);
+        checker.finish().map_err(|e| e.to_string())
+    };
+    assert!(check("pretty, indent = 2").is_ok());
+    assert_eq!(
+        check("compact, pretty, indent = 2").unwrap_err(),
+        "`compact` conflicts with `pretty`, as members of group `styles` are mutually exclusive"
+    );
+    assert_eq!(check("pretty").unwrap_err(), "`pretty` requires `indent`");
+    assert_eq!(
+        check("compact, indent = 2").unwrap_err(),
+        "`compact` conflicts with `indent`"
+    );
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn collect_enums() {
+    let mut collector = Collector::<CheckedArgEnum>::new();
+    collector.parse_tokens(quote::quote!(compact)).unwrap();
+    assert!(collector.parse_tokens(quote::quote!(unknown)).is_err());
+    collector.parse_tokens(quote::quote!(pretty)).unwrap();
+    assert_eq!(collector.values().len(), 2);
+    let errs = collector.finish().unwrap_err();
+    let errs = errs.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        errs,
+        [
+            "`compact` conflicts with `pretty`, as members of group `styles` are mutually \
+             exclusive",
+            "`pretty` conflicts with `compact`, as members of group `styles` are mutually \
+             exclusive",
+            "`pretty` requires `indent`",
+        ]
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    struct HookArgs {
+        #[arg(is_expr, family = "on_")]
+        on: Arg<Expr>,
+        #[arg(is_flag)]
+        once: Arg<LitBool>,
+    }
+}
+
+#[test]
+fn key_families() {
+    let args: HookArgs =
+        syn::parse::Parser::parse_str(HookArgs::parse, "on_create = a, once, on_delete = b")
+            .unwrap();
+    let hooks = args.on.suffixes();
+    let hooks = hooks.iter().map(|(s, _)| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(hooks, ["create", "delete"]);
+    assert!(args.once.is_present());
+
+    let err =
+        syn::parse::Parser::parse_str(HookArgs::parse, "on_create = a, on_create = b").unwrap_err();
+    assert_eq!(err.to_string(), "`on_create` is supplied more than once");
+    let err = syn::parse::Parser::parse_str(HookArgs::parse, "on_ = a").unwrap_err();
+    assert_eq!(err.to_string(), "unknown argument");
+    assert!(HookArgs::help().contains("on_* = <value>"));
+
+    // keys of merged families also have the prefix of the merge
+    let args: MergedHookArgs =
+        syn::parse::Parser::parse_str(MergedHookArgs::parse, "db_on_create = a, on_ready").unwrap();
+    let hooks = args.db.on.suffixes();
+    assert_eq!(hooks[0].0, "create");
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[merge(db: HookArgs, prefix = "db_")]
+    struct MergedHookArgs {
+        #[arg(is_flag)]
+        on_ready: Arg<LitBool>,
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    struct ValidatedArgs {
+        #[arg(is_expr, validate = plap::validators::non_empty)]
+        name: Arg<syn::LitStr>,
+        #[arg(is_expr, validate = plap::validators::absolute_path)]
+        krate: Arg<syn::Expr>,
+        #[arg(is_expr, validate = plap::validators::url)]
+        url: Arg<syn::LitStr>,
+        #[arg(is_expr, validate = plap::validators::semver)]
+        version: Arg<syn::LitStr>,
+        #[arg(is_expr, validate = plap::validators::glob)]
+        files: Arg<syn::LitStr>,
+    }
+}
+
+#[test]
+fn value_validators() {
+    let parse = |s| {
+        syn::parse::Parser::parse_str(ValidatedArgs::parse, s)
+            .map(|_| ())
+            .map_err(|e| e.into_iter().map(|e| e.to_string()).collect::<Vec<_>>())
+    };
+    parse(
+        r#"name = "a", krate = ::serde, url = "https://a.b", version = "1.0.0-rc.1+001",
+        files = "src/**/*.{rs,toml}""#,
+    )
+    .unwrap();
+    parse(r#"krate = crate::private, krate = "::a::b""#).unwrap();
+    assert_eq!(
+        parse(r#"name = "", krate = serde, url = "a.b", version = "1.02.0", files = "[a""#)
+            .unwrap_err(),
+        [
+            "expected a non-empty string",
+            "expected an absolute path, e.g. `::std::fmt`",
+            "expected a URL, e.g. `https://example.com`",
+            "expected a semantic version, e.g. `1.0.0`",
+            "invalid glob pattern: unclosed `[`",
+        ]
+    );
+}
+
+#[test]
+fn parser_context() {
+    struct Item(&'static str);
+
+    let args = syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut args = TableArgs::init();
+            let mut parser = Parser::new(input);
+            parser.insert_context(Item("User"));
+            parser.parse_all(&mut args)?;
+            let item = parser.context::<Item>().unwrap();
+            args.name
+                .default_value(syn::Ident::new(item.0, proc_macro2::Span::call_site()));
+            args.resolve_defaults()?;
+            Ok(args)
+        },
+        "",
+    )
+    .unwrap();
+    assert_eq!(args.table.entries()[0].value, "User");
+}
+
+define_args! {
+    pub struct PlainArgs {
+        plain: Arg<syn::LitStr>,
+    }
+}
+
+define_args! {
+    pub enum PlainArgEnum {
+        plain(syn::LitStr),
+    }
+}
+
+define_args! {
+    pub struct EnvArgs {
+        #[arg(is_expr)]
+        table: Arg<syn::Ident>,
+        #[arg(is_expr, env = "PLAP_TEST_SCHEMA")]
+        schema: Arg<syn::Ident>,
+    }
+}
+
+#[test]
+fn env_fallback() {
+    std::env::set_var("PLAP_TEST_SCHEMA", "public");
+    let mut args: EnvArgs = syn::parse::Parser::parse_str(EnvArgs::parse, "table = A").unwrap();
+    args.resolve_defaults().unwrap();
+    assert_eq!(args.schema.entries()[0].value, "public");
+    assert_eq!(
+        args.schema.entries()[0].source,
+        ValueSource::Env("PLAP_TEST_SCHEMA")
+    );
+    assert_eq!(args.table.entries()[0].source, ValueSource::Supplied);
+
+    let mut args: EnvArgs =
+        syn::parse::Parser::parse_str(EnvArgs::parse, "table = A, schema = B").unwrap();
+    args.resolve_defaults().unwrap();
+    assert_eq!(args.schema.entries()[0].value, "B");
+
+    std::env::set_var("PLAP_TEST_SCHEMA", "1");
+    let mut args: EnvArgs = syn::parse::Parser::parse_str(EnvArgs::parse, "table = A").unwrap();
+    let err = args.resolve_defaults().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value of environment variable `PLAP_TEST_SCHEMA`: expected identifier"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct EnvConflictArgs {
+        #[arg(is_expr)]
+        table: Arg<syn::Ident>,
+        #[arg(is_expr, env = "PLAP_TEST_VIEW")]
+        #[check(conflicts_with = table)]
+        view: Arg<syn::Ident>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn env_sources_in_messages() {
+    std::env::set_var("PLAP_TEST_VIEW", "v");
+    let err = syn::parse::Parser::parse_str(EnvConflictArgs::parse, "table = A")
+        .unwrap()
+        .finish()
+        .unwrap_err();
+    let msgs = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        msgs,
+        [
+            "`table` conflicts with `view`",
+            "`view` conflicts with `table` (`view` is set from environment variable \
+             `PLAP_TEST_VIEW`)",
+        ]
+    );
+}
+
+#[test]
+fn registry_dispatch() {
+    let mut registry = Registry::new()
+        .register::<CommonArgs>("common")
+        .register::<TableArgs>("table");
+    assert!(registry
+        .parse_tokens("common", "verbose".parse().unwrap())
+        .unwrap());
+    assert!(registry
+        .parse_tokens("table", "name = A".parse().unwrap())
+        .unwrap());
+    assert!(registry
+        .parse_tokens("common", r#"url = "a""#.parse().unwrap())
+        .unwrap());
+    assert!(!registry.parse_tokens("doc", Default::default()).unwrap());
+
+    assert!(registry.get::<TableArgs>("common").is_none());
+    let common = registry.take::<CommonArgs>("common").unwrap();
+    assert_eq!((common.verbose.len(), common.url.len()), (1, 1));
+    assert_eq!(registry.get::<TableArgs>("table").unwrap().name.len(), 1);
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn registry_check() {
+    let registry = Registry::new().register::<MyArgs>("my");
+    let err = registry.check(proc_macro2::Span::call_site()).unwrap_err();
+    let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        ["`arg2 | arg5` is required", "`arg1` is required"]
+    );
+}
+
+#[test]
+#[cfg(feature = "attributes")]
+fn attribute_paths() {
+    let attrs = syn::parse::Parser::parse_str(
+        syn::Attribute::parse_outer,
+        r#"#[my::common(verbose)] #[common(url = "a")] #[other::common(verbose)]"#,
+    )
+    .unwrap();
+    let mut registry = Registry::new().register::<CommonArgs>("my::common");
+    registry.parse_attrs(&attrs).unwrap();
+    let common = registry.take::<CommonArgs>("my::common").unwrap();
+    assert_eq!((common.verbose.len(), common.url.len()), (1, 0));
+
+    let ctx = ParseContext::of(&attrs[0]);
+    assert!(ctx.is("my::common") && !ctx.is_inner());
+    assert_eq!(ctx.to_string(), "#[my::common(...)]");
+}
+
+#[test]
+#[cfg(feature = "attributes")]
+fn attribute_matches() {
+    let attrs = syn::parse::Parser::parse_str(
+        syn::Attribute::parse_outer,
+        r#"#[my::common(verbose)] #[common(url = "a")] #[other::common(verbose)] #[common]"#,
+    )
+    .unwrap();
+    let mut common = CommonArgs::init();
+    let mut errors = Errors::default();
+    for attr in attrs.iter() {
+        if let Some(m) = plap::attr_name!(attr, "common" | "my::common") {
+            errors.add_result(m.parse_into(&mut common));
+        }
+    }
+    assert_eq!((common.verbose.len(), common.url.len()), (1, 1));
+    // `#[common]` is not a list
+    assert!(errors.fail::<()>().is_err());
+    assert!(plap::attr_name!(&attrs[2], "common" => CommonArgs).is_none());
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    #[::derive(Debug)]
+    #[group(modes = [fast, safe])]
+    #[check(exclusive_group = modes)]
+    pub struct UnsatisfiableArgs {
+        #[arg(is_flag)]
+        #[check(required)]
+        fast: Arg<LitBool>,
+        #[arg(is_flag)]
+        #[check(required)]
+        safe: Arg<LitBool>,
+    }
+}
+
+#[test]
+#[cfg(all(feature = "checking", debug_assertions))]
+#[should_panic = "`fast` and `safe` are both required but conflict with each other"]
+fn unsatisfiable_checks() {
+    // which never fails on finishing
+    let args = syn::parse::Parser::parse_str(UnsatisfiableArgs::parse, "fast").unwrap();
+    assert_eq!(args.finish().unwrap_err().to_string(), "`safe` is required");
+    plap::debug_assert_schema!(MyArgs);
+    plap::debug_assert_schema!(UnsatisfiableArgs);
+}
+
+define_args! {
+    #[::derive(Debug)]
+    #[rename_all("camelCase")]
+    pub struct ShadowedArgs {
+        #[arg(is_expr)]
+        max_len: Arg<LitInt>,
+        #[arg(is_expr, rename = "maxLen")]
+        limit: Arg<LitInt>,
+    }
+}
+
+#[test]
+fn shadowed_keys() {
+    // the first declared wins, and the other is reported by the schema
+    let args = syn::parse::Parser::parse_str(ShadowedArgs::parse, "maxLen = 1").unwrap();
+    assert_eq!(args.max_len.len(), 1);
+    assert!(args.limit.is_empty());
+    assert_eq!(
+        ShadowedArgs::validate_schema().unwrap_err(),
+        "`maxLen` is the key of both `max_len` and `limit`, which is never parsed"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct VisArgs {
+        #[arg(is_expr, accepts_empty)]
+        vis: Arg<syn::Visibility>,
+        #[arg(is_expr)]
+        name: Arg<proc_macro2::Ident>,
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct MisdeclaredVisArgs {
+        #[arg(is_expr)]
+        vis: Arg<syn::Visibility>,
+    }
+}
+
+#[test]
+fn empty_values() {
+    let parse = |s| syn::parse::Parser::parse_str(VisArgs::parse, s);
+    let args = parse("vis, name = a").unwrap();
+    assert!(matches!(
+        args.vis.entries()[0].value,
+        syn::Visibility::Inherited
+    ));
+    let args = parse("vis = pub(crate)").unwrap();
+    assert!(matches!(
+        args.vis.entries()[0].value,
+        syn::Visibility::Restricted(_)
+    ));
+
+    let err = parse("vis = name").unwrap_err();
+    assert_eq!(err.to_string(), "expected a value for `vis` after `=`");
+    assert_eq!(
+        VisArgs::schema(),
+        "struct VisArgs\narg vis: expr, accepts_empty\narg name: expr\n"
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic = "`vis` parses empty values, declare it with `accepts_empty`"]
+fn misdeclared_empty_values() {
+    // which never fails on parsing
+    let args = syn::parse::Parser::parse_str(MisdeclaredVisArgs::parse, "vis = pub").unwrap();
+    assert_eq!(args.vis.len(), 1);
+    plap::debug_assert_schema!(VisArgs);
+    plap::debug_assert_schema!(MisdeclaredVisArgs);
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub enum Style {
+        #[arg(is_flag)]
+        compact(LitBool),
+        #[arg(is_expr)]
+        indent(LitInt),
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct StyleArgs {
+        #[arg(is_expr)]
+        style: Arg<Style>,
+    }
+}
+
+#[test]
+fn enum_values() {
+    let args: StyleArgs =
+        syn::parse::Parser::parse_str(StyleArgs::parse, "style = compact, style(indent = 2)")
+            .unwrap();
+    assert!(matches!(args.style.entries()[0].value, Style::compact(_)));
+    assert!(matches!(&args.style.entries()[1].value, Style::indent(i) if i.base10_digits() == "2"));
+
+    let err = syn::parse::Parser::parse_str(StyleArgs::parse, "style = wide").unwrap_err();
+    assert_eq!(err.to_string(), "unknown variant");
+}
+
+define_args! {
+    pub struct SpannedArgs {
+        #[arg(is_flag)]
+        enabled: Arg<SpannedBool>,
+        #[arg(is_expr)]
+        name: Arg<SpannedIdent>,
+        #[arg(is_expr)]
+        path: Arg<SpannedPath>,
+    }
+}
+
+#[test]
+fn spanned_values() {
+    let args: SpannedArgs = syn::parse::Parser::parse_str(
+        SpannedArgs::parse,
+        "enabled, name = foo, path = ::std::fmt::Debug",
+    )
+    .unwrap();
+    assert!(args.enabled.entries()[0].value.value());
+    assert_eq!(
+        args.enabled.entries()[0]
+            .value
+            .to_token_stream()
+            .to_string(),
+        "true"
+    );
+    assert_eq!(args.name.entries()[0].value, *"foo");
+    let path = &args.path.entries()[0].value;
+    assert_eq!(path.to_string(), "::std::fmt::Debug");
+    assert_eq!(path.to_token_stream().to_string(), ":: std :: fmt :: Debug");
+}
+
+#[test]
+fn skip_marked_args() {
+    let tokens = plap::mark_parsed("unknown, verbose".parse().unwrap());
+    assert!(plap::is_parsed(&tokens));
+    assert!(!plap::is_parsed(&"verbose".parse().unwrap()));
+    let args: CommonArgs = syn::parse2(tokens)
+        .map(|a: ArgsOf<CommonArgs>| a.0)
+        .unwrap();
+    assert!(args.verbose.is_empty());
+    assert!(args.is_skipped());
+
+    // missing arguments are not reported either
+    let parse = |tokens| syn::parse2(tokens).map(|a: ArgsOf<RequiredArgs>| a.0);
+    let args = parse(plap::mark_parsed("other = 1".parse().unwrap())).unwrap();
+    assert!(args.is_skipped());
+    assert!(args.finish().unwrap().x.is_empty());
+    let args = parse(plap::mark_parsed(Default::default())).unwrap();
+    assert!(args.finish_report().unwrap().1.is_empty());
+    #[cfg(feature = "checking")]
+    {
+        let err = parse(Default::default()).unwrap().finish().unwrap_err();
+        assert_eq!(err.to_string(), "`x` is required");
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    struct RequiredArgs {
+        #[arg(is_expr)]
+        #[check(required)]
+        x: Arg<LitInt>,
+    }
+}
+
+struct ArgsOf<A>(A);
+
+impl<A: Args> syn::parse::Parse for ArgsOf<A> {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        A::parse(input).map(ArgsOf)
+    }
+}
+
+#[test]
+fn arg_entries() {
+    let key = |s| syn::Ident::new(s, proc_macro2::Span::call_site());
+    let mut arg = Arg::<LitInt>::new("level");
+    arg.add(key("level"), syn::parse_str("1").unwrap());
+    arg.push(Entry {
+        delimiter: ArgDelimiter::Eq,
+        ..Entry::new(key("lvl"), syn::parse_str("2").unwrap())
+    });
+    assert_eq!(arg.len(), 2);
+    assert_eq!(arg.entries()[0].delimiter, ArgDelimiter::Omitted);
+    assert_eq!(arg.entries()[1].delimiter, ArgDelimiter::Eq);
+    assert!(Presence::keys(&arg).eq(["level", "lvl"]));
+
+    let entries = arg.into_entries();
+    assert_eq!(entries[1].key, "lvl");
+    assert_eq!(entries[1].value.base10_digits(), "2");
+    assert_eq!(entries[1].source, ValueSource::Supplied);
+}
+
+#[test]
+fn single_values() {
+    let key = |s| syn::Ident::new(s, proc_macro2::Span::call_site());
+    let mut arg = Arg::<LitInt>::new("level");
+    assert_eq!(
+        arg.clone()
+            .exactly_one_or_err("my_attr")
+            .unwrap_err()
+            .to_string(),
+        "`level` is required in `#[my_attr(...)]`"
+    );
+    assert!(arg.clone().at_most_one_or_err().unwrap().is_none());
+    assert_eq!(
+        arg.clone().try_take_many().unwrap_err().to_string(),
+        "`level` is required"
+    );
+
+    // an absent value is reported at the given span, e.g. of the attribute
+    let attr = syn::parse_str::<syn::Ident>("  my_attr").unwrap().span();
+    let errs = [
+        arg.clone().try_take_one_at(attr).unwrap_err(),
+        arg.clone().try_take_many_at(attr).unwrap_err(),
+        arg.clone()
+            .exactly_one_or_err_at("my_attr", attr)
+            .unwrap_err(),
+    ];
+    assert_eq!(
+        errs[2].to_string(),
+        "`level` is required in `#[my_attr(...)]`"
+    );
+    #[cfg(feature = "span-locations")]
+    for err in &errs {
+        assert_eq!(err.span().start().column, 2);
+    }
+
+    arg.add(key("level"), syn::parse_str("1").unwrap());
+    assert_eq!(
+        arg.clone()
+            .exactly_one_or_err("my_attr")
+            .unwrap()
+            .base10_digits(),
+        "1"
+    );
+    assert_eq!(arg.clone().try_take_one().unwrap().base10_digits(), "1");
+
+    arg.add(key("level"), syn::parse_str("2").unwrap());
+    arg.add(key("level"), syn::parse_str("3").unwrap());
+    assert_eq!(arg.clone().try_take_many().unwrap().len(), 3);
+    let err = arg.at_most_one_or_err().unwrap_err();
+    assert_eq!(
+        err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "`level` has too many values (<= 1)",
+            "`level` has too many values (<= 1)"
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "span-locations")]
+fn value_spans() {
+    let args = syn::parse::Parser::parse_str(CommonArgs::parse, "verbose, url = a + b").unwrap();
+    let verbose = args.verbose.entries()[0].value_span;
+    assert_eq!(verbose.byte_range(), 0..7);
+    assert_eq!(args.url.entries()[0].value_span.byte_range(), 15..20);
+}
+
+#[test]
+#[cfg(feature = "span-locations")]
+fn error_byte_ranges() {
+    // spans resolve to byte ranges of the parsed string outside of proc-macros
+    let input = "verbose, bogus = 1";
+    let err = syn::parse::Parser::parse_str(CommonArgs::parse, input).unwrap_err();
+    let ranges = plap::byte_ranges(&err);
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(&input[ranges[0].0.clone()], "bogus");
+}
+
+#[test]
+#[cfg(feature = "string-parse")]
+fn parse_from_str() {
+    let args = plap::from_str::<CommonArgs>(r#"verbose, url = "a""#).unwrap();
+    assert_eq!(args.verbose.len(), 1);
+    assert_eq!(args.url.len(), 1);
+
+    let err = plap::from_str::<CommonArgs>("verbose,\n  bogus = 1").unwrap_err();
+    let start = err.span().start();
+    assert_eq!((start.line, start.column), (2, 2));
+}
+
+/// A toy format of `key:value` lines.
+#[derive(Debug)]
+struct Lines(Vec<String>);
+
+impl FromEmbedded for Lines {
+    fn from_embedded(s: &str) -> Result<Self, EmbeddedError> {
+        for (i, line) in s.lines().enumerate() {
+            if !line.contains(':') {
+                return Err(EmbeddedError::at_line_column(i + 1, 1, "expected `:`"));
+            }
+        }
+        Ok(Lines(s.lines().map(str::to_owned).collect()))
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct EmbeddedArgs {
+        #[arg(is_expr)]
+        config: Arg<Embedded<Lines>>,
+    }
+}
+
+#[test]
+fn embedded_values() {
+    let args =
+        syn::parse::Parser::parse_str(EmbeddedArgs::parse, r#"config = "a:1\nb:2""#).unwrap();
+    let config = args.config.take_last().map(Embedded::into_value).unwrap();
+    assert_eq!(config.0, ["a:1", "b:2"]);
+
+    let inputs = [
+        r#"config = "a:1\nb""#,
+        r#"config = "a:1\u{a}b""#,
+        "config = r#\"a:1\nb\"#",
+    ];
+    for input in inputs {
+        let err = syn::parse::Parser::parse_str(EmbeddedArgs::parse, input).unwrap_err();
+        assert_eq!(err.to_string(), "expected `:`, at line 2, column 1");
+        #[cfg(feature = "span-locations")]
+        assert_eq!(&input[err.span().byte_range()], "b");
+    }
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct PreviewArgs {
+        #[arg(is_expr)]
+        name: Arg<syn::Ident>,
+        #[arg(is_expr, unstable = "fancy")]
+        fancy: Arg<LitInt>,
+    }
+}
+
+#[test]
+fn unstable_args() {
+    let err = syn::parse::Parser::parse_str(PreviewArgs::parse, "fancy = 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`fancy` is unstable, opt in with `unstable(fancy)` before it"
+    );
+    let args =
+        syn::parse::Parser::parse_str(PreviewArgs::parse, "unstable(fancy), fancy = 1").unwrap();
+    assert_eq!(args.fancy.len(), 1);
+    let err = syn::parse::Parser::parse_str(PreviewArgs::parse, "unstable(other), fancy = 1")
+        .unwrap_err();
+    let mut errs = err.into_iter();
+    let err = errs.next().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "unknown unstable feature `other`, expected one of `fancy`"
+    );
+    #[cfg(feature = "span-locations")]
+    assert_eq!(err.span().start().column, 9);
+    assert_eq!(
+        errs.next().unwrap().to_string(),
+        "`fancy` is unstable, opt in with `unstable(fancy)` before it"
+    );
+
+    // custom parsing accepts no `unstable(...)` unless features are declared
+    let parse = |input: syn::parse::ParseStream| {
+        let mut args = PreviewArgs::init();
+        Parser::new(input).parse_all_with(|parser| args.parse_next(parser))
+    };
+    let err = syn::parse::Parser::parse_str(parse, "unstable(other)").unwrap_err();
+    assert_eq!(err.to_string(), "unknown argument");
+    syn::parse::Parser::parse_str(
+        |input: syn::parse::ParseStream| {
+            let mut args = PreviewArgs::init();
+            Parser::new(input)
+                .with_unstable_features(["fancy"])
+                .parse_all_with(|parser| args.parse_next(parser))
+        },
+        "unstable(fancy), fancy = 1",
+    )
+    .unwrap();
+
+    // nor do collectors of enums without unstable variants
+    let mut collector = Collector::<MyArgEnum>::new();
+    let err = collector
+        .parse_tokens("unstable(whatever), arg5 = 1".parse().unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown unstable feature `whatever`, none is declared"
+    );
+}
+
+define_args! {
+    #[::derive(Debug)]
+    pub struct GatedArgs {
+        /// Emits extra code
+        #[arg(is_flag, requires_feature = FeatureGate::new("extra-codegen", "my-macros", false))]
+        codegen: Arg<LitBool>,
+        #[arg(is_flag, requires_feature = plap::feature!("checking"))]
+        checked: Arg<LitBool>,
+    }
+}
+
+#[test]
+fn feature_gated_args() {
+    let err = syn::parse::Parser::parse_str(GatedArgs::parse, "codegen").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`codegen` requires the `extra-codegen` feature of `my-macros`, enable it to use `codegen`"
+    );
+    let args = syn::parse::Parser::parse_str(GatedArgs::parse, "checked");
+    assert_eq!(args.is_ok(), cfg!(feature = "checking"));
+    assert!(GatedArgs::help()
+        .starts_with("  codegen  Emits extra code (requires the `extra-codegen` feature)\n"));
+    #[cfg(feature = "checking")]
+    assert!(GatedArgs::help().ends_with("  checked\n"));
+}
+
+#[cfg(feature = "checking")]
+define_args! {
+    #[::derive(Debug)]
+    pub struct ComboArgs {
+        #[arg(is_flag)]
+        #[check(conflicts_with_all([x, y]))]
+        a: Arg<LitBool>,
+        #[arg(is_flag)]
+        x: Arg<LitBool>,
+        #[arg(is_flag)]
+        y: Arg<LitBool>,
+    }
+}
+
+#[test]
+#[cfg(feature = "checking")]
+fn conjunctive_conflicts() {
+    let check = |s: &str| {
+        let args: ComboArgs = syn::parse::Parser::parse_str(ComboArgs::parse, s).unwrap();
+        let mut checker = Checker::default();
+        args.check(&mut checker);
+        checker.finish()
+    };
+    assert!(check("a, x").is_ok());
+    assert!(check("x, y").is_ok());
+    assert_eq!(
+        check("a, x, y").unwrap_err().to_string(),
+        "`a` conflicts with the combination of `x` and `y`"
+    );
+}
+
+#[test]
+#[cfg(feature = "perf-metrics")]
+fn perf_metrics() {
+    use std::cell::RefCell;
+
+    use plap::metrics::{self, Phase, Sample};
+
+    thread_local! {
+        static SAMPLES: RefCell<Vec<Sample>> = RefCell::new(Vec::new());
+    }
+    fn collect(sample: &Sample) {
+        SAMPLES.with(|s| s.borrow_mut().push(sample.clone()));
+    }
+
+    metrics::reset();
+    metrics::set_callback(Some(collect));
+    let attr = syn::parse_str::<syn::Ident>("common").unwrap().span();
+    for input in ["verbose", r#"url = "a""#] {
+        syn::parse::Parser::parse_str(CommonArgs::parse, input)
+            .unwrap()
+            .finish_at(attr)
+            .unwrap();
+    }
+    #[cfg(feature = "checking")]
+    {
+        let mut registry = Registry::new().register::<CommonArgs>("common");
+        registry
+            .parse_tokens("common", "verbose".parse().unwrap())
+            .unwrap();
+        registry.check(attr).unwrap();
+    }
+    metrics::set_callback(None);
+
+    let summary = metrics::summary();
+    let counts = summary
+        .iter()
+        .map(|s| (s.phase, s.count))
+        .collect::<Vec<_>>();
+    if cfg!(feature = "checking") {
+        assert_eq!(counts, [(Phase::Parse, 3), (Phase::Check, 3)]);
+    } else {
+        assert_eq!(counts, [(Phase::Parse, 2), (Phase::Check, 2)]);
+    }
+    assert!(summary
+        .iter()
+        .all(|s| s.schema.ends_with("CommonArgs") && s.max <= s.total));
+
+    // checks are measured at the span they report missing arguments at
+    let samples = SAMPLES.with(|s| s.take());
+    assert_eq!(samples.len(), counts.iter().map(|(_, n)| n).sum::<usize>());
+    #[cfg(feature = "span-locations")]
+    for sample in samples.iter().filter(|s| s.phase == Phase::Check) {
+        assert_eq!(sample.span.byte_range(), attr.byte_range());
+    }
+}